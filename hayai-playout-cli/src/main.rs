@@ -0,0 +1,262 @@
+use anyhow::{anyhow, Result};
+use hayai_playout_core::{
+    join_stream_key, redact_url, user_message, EncodingSettings, OutputTarget, PlayoutEvent, RepeatMode, StreamStats,
+    Streamer, MEDIA_EXTENSIONS,
+};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: hayai-playout-cli --playlist <playlist.json> --rtmp <url> [options]
+       hayai-playout-cli --folder <dir> --rtmp <url> [options]
+
+The URL may also be an srt:// address; --hls <dir> writes HLS files instead. --playlist and --folder can be combined; the
+folder's files are played after the playlist.
+
+Options:
+    --key <stream key>       Appended to the URL
+    --hls <dir>              Write HLS segments and playlist.m3u8 to a folder instead of --rtmp
+    --also <url>             Send the same stream to another destination too; repeatable
+    --folder <dir>           Play the media files in a folder, in file name order
+    --recursive              With --folder, include subfolders
+    --encoder <element>      Video encoder, e.g. x264enc (default: x264enc)
+    --bitrate <kbps>         Video bitrate (default: 4000)
+    --record <file>          Also record the program (.mp4, otherwise Matroska)
+    --repeat <none|all|one>  What to do after the last item (default: all)
+    --shuffle                Play the items in random order
+    --preload <seconds>      Prepare the next item this long before the current one ends,
+                             for gapless transitions
+    --no-probe               Start without checking that every item can be decoded
+    --stats <seconds>        Print bitrate, bytes sent and dropped frames this often
+    -h, --help               Show this help";
+
+#[derive(Debug)]
+struct Args {
+    playlist: Option<PathBuf>,
+    folder: Option<PathBuf>,
+    recursive: bool,
+    target: OutputTarget,
+    also: Vec<String>,
+    settings: EncodingSettings,
+    repeat: RepeatMode,
+    shuffle: bool,
+    probe: bool,
+    preload: Option<Duration>,
+    stats_interval: Option<Duration>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>> {
+    let mut playlist = None;
+    let mut folder = None;
+    let mut recursive = false;
+    let mut rtmp = None;
+    let mut hls = None;
+    let mut key = None;
+    let mut also = Vec::new();
+    let mut settings = EncodingSettings::default();
+    let mut repeat = RepeatMode::RepeatAll;
+    let mut shuffle = false;
+    let mut probe = true;
+    let mut preload = None;
+    let mut stats_interval = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+        match arg.as_str() {
+            "--playlist" => playlist = Some(PathBuf::from(value()?)),
+            "--folder" => folder = Some(PathBuf::from(value()?)),
+            "--recursive" => recursive = true,
+            "--rtmp" => rtmp = Some(value()?),
+            "--hls" => hls = Some(PathBuf::from(value()?)),
+            "--key" => key = Some(value()?),
+            "--also" => also.push(value()?),
+            "--encoder" => settings.video_encoder = value()?,
+            "--bitrate" => {
+                let bitrate = value()?;
+                settings.bitrate_kbps = bitrate.parse()
+                    .map_err(|_| anyhow!("Invalid bitrate: {}", bitrate))?;
+            }
+            "--record" => settings.record_path = Some(PathBuf::from(value()?)),
+            "--repeat" => {
+                repeat = match value()?.as_str() {
+                    "none" => RepeatMode::None,
+                    "all" => RepeatMode::RepeatAll,
+                    "one" => RepeatMode::RepeatOne,
+                    other => return Err(anyhow!("Unknown repeat mode: {}", other)),
+                }
+            }
+            "--shuffle" => shuffle = true,
+            "--no-probe" => probe = false,
+            "--preload" => {
+                let seconds = value()?;
+                let seconds: f64 = seconds.parse().ok().filter(|s: &f64| s.is_finite() && *s >= 0.0)
+                    .ok_or_else(|| anyhow!("Invalid preload time: {}", seconds))?;
+                preload = Some(Duration::from_secs_f64(seconds));
+            }
+            "--stats" => {
+                let seconds = value()?;
+                let seconds: f64 = seconds.parse().ok().filter(|s: &f64| s.is_finite() && *s > 0.0)
+                    .ok_or_else(|| anyhow!("Invalid stats interval: {}", seconds))?;
+                stats_interval = Some(Duration::from_secs_f64(seconds));
+            }
+            "-h" | "--help" => return Ok(None),
+            other => return Err(anyhow!("Unknown argument: {}", other)),
+        }
+    }
+
+    if playlist.is_none() && folder.is_none() {
+        return Err(anyhow!("--playlist or --folder is required"));
+    }
+    let target = match (rtmp, hls) {
+        (Some(rtmp), None) => OutputTarget::from(match key {
+            Some(key) => join_stream_key(&rtmp, &key),
+            None => rtmp,
+        }),
+        (None, Some(dir)) => OutputTarget::hls(dir),
+        (Some(_), Some(_)) => return Err(anyhow!("--rtmp and --hls cannot be combined; use --also for more destinations")),
+        (None, None) => return Err(anyhow!("--rtmp or --hls is required")),
+    };
+    Ok(Some(Args { playlist, folder, recursive, target, also, settings, repeat, shuffle, probe, preload, stats_interval }))
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(args) {
+        eprintln!("Error: {}", user_message(&e));
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    let mut streamer = Streamer::new()?;
+    if let Some(playlist) = &args.playlist {
+        let count = streamer.load_playlist(playlist)?;
+        println!("Loaded {} item(s) from {}.", count, playlist.display());
+    }
+    if let Some(folder) = &args.folder {
+        let ids = streamer.add_directory(folder, MEDIA_EXTENSIONS, args.recursive)?;
+        println!("Added {} file(s) from {}.", ids.len(), folder.display());
+    }
+    if args.probe {
+        check_playlist(&streamer)?;
+    }
+    streamer.set_repeat_mode(args.repeat);
+    streamer.set_shuffle(args.shuffle);
+    streamer.set_preload(args.preload);
+
+    let events = streamer.subscribe();
+    let report = streamer.start(args.target.clone(), &args.settings)?;
+    println!("Streaming to {} with {}.", report.output_url, report.encoder_used);
+    if report.fallback_applied {
+        println!("Encoder {} is unavailable, using {} instead.", args.settings.video_encoder, report.encoder_used);
+    }
+    for url in &args.also {
+        match streamer.add_output(url.as_str().into()) {
+            Ok(()) => println!("Also streaming to {}.", redact_url(url)),
+            Err(e) => eprintln!("Cannot stream to {}: {}", redact_url(url), user_message(&e)),
+        }
+    }
+
+    // Ctrl-C stops cleanly on Unix; elsewhere it ends the process.
+    let main_loop = glib::MainLoop::new(None, false);
+    let streamer = Rc::new(RefCell::new(streamer));
+
+    #[cfg(unix)]
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        let main_loop = main_loop.clone();
+        glib::unix_signal_add_local(signal, move || {
+            println!("Stopping...");
+            main_loop.quit();
+            glib::ControlFlow::Break
+        });
+    }
+
+    // The newest audio peaks, shown with the stats rather than printed as they come.
+    let audio_peaks = Rc::new(RefCell::new(Vec::<f64>::new()));
+    glib::timeout_add_local(Duration::from_millis(200), {
+        let main_loop = main_loop.clone();
+        let audio_peaks = audio_peaks.clone();
+        move || {
+            while let Ok(event) = events.try_recv() {
+                if let PlayoutEvent::AudioLevels { peak_db, .. } = event {
+                    *audio_peaks.borrow_mut() = peak_db;
+                    continue;
+                }
+                println!("Event: {:?}", event);
+                if matches!(event,
+                    PlayoutEvent::PlaylistEnded
+                        | PlayoutEvent::StoppedAfterCurrent
+                        | PlayoutEvent::ReconnectGaveUp
+                        | PlayoutEvent::TooManyFailures { .. })
+                {
+                    main_loop.quit();
+                    return glib::ControlFlow::Break;
+                }
+            }
+            glib::ControlFlow::Continue
+        }
+    });
+
+    if let Some(interval) = args.stats_interval {
+        let streamer = streamer.clone();
+        glib::timeout_add_local(interval, move || {
+            println!("{}", format_stats(&streamer.borrow().get_stats(), &audio_peaks.borrow()));
+            glib::ControlFlow::Continue
+        });
+    }
+
+    main_loop.run();
+    streamer.borrow_mut().stop()?;
+    println!("Stream stopped.");
+    Ok(())
+}
+
+fn format_stats(stats: &StreamStats, audio_peaks: &[f64]) -> String {
+    let uptime = stats.uptime.as_secs();
+    let mut line = format!(
+        "Stats: {:.0} kbps, {:.1} MB sent, {} frame(s) dropped, up {:02}:{:02}:{:02}",
+        stats.bitrate_kbps,
+        stats.bytes_sent as f64 / 1_000_000.0,
+        stats.dropped_frames,
+        uptime / 3600,
+        uptime / 60 % 60,
+        uptime % 60,
+    );
+    if !audio_peaks.is_empty() {
+        let peaks: Vec<String> = audio_peaks.iter().map(|db| format!("{:.1}", db)).collect();
+        line.push_str(&format!(", audio peak {} dBFS", peaks.join("/")));
+    }
+    line
+}
+
+/// Refuses to go live with items that can't be decoded; items without audio only get a warning.
+fn check_playlist(streamer: &Streamer) -> Result<()> {
+    let mut broken = 0;
+    for (item, info) in streamer.probe_all() {
+        match info {
+            Ok(info) if !info.has_audio() => println!("Warning: {} has no audio.", redact_url(&item.uri)),
+            Ok(_) => (),
+            Err(e) => {
+                eprintln!("Cannot play {}: {:#}", redact_url(&item.uri), e);
+                broken += 1;
+            }
+        }
+    }
+    if broken > 0 {
+        return Err(anyhow!("{} playlist item(s) cannot be played; fix them or pass --no-probe", broken));
+    }
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use log::warn;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::blanks::{BlankThresholds, LeadingBlankDetector};
+use crate::{redact_url, LOG_SOURCE};
+
+// Frames are scaled down before measuring; luma averages don't need full resolution.
+const ANALYSIS_WIDTH: i32 = 64;
+const ANALYSIS_HEIGHT: i32 = 36;
+const ANALYSIS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Decodes the head of `uri` in a throwaway pipeline and returns how much leading
+/// black/silence to skip, if any.
+pub(crate) fn detect_leading_blanks(uri: &str, thresholds: &BlankThresholds) -> Result<Option<gst::ClockTime>> {
+    let pipeline = gst::Pipeline::new();
+    let source = gst::ElementFactory::make("uridecodebin")
+        .property("uri", uri)
+        .build()?;
+    pipeline.add(&source)?;
+
+    let detector = Arc::new(Mutex::new(LeadingBlankDetector::new(thresholds.clone())));
+    let weak_pipeline = pipeline.downgrade();
+    let detector_clone = detector.clone();
+    source.connect_pad_added(move |_, pad| {
+        if let Some(pipeline) = weak_pipeline.upgrade() {
+            if let Err(e) = attach_analyzer(&pipeline, pad, &detector_clone) {
+                warn!(target: LOG_SOURCE, "Blank analysis: failed to analyse pad '{}': {}", pad.name(), e);
+            }
+        }
+    });
+
+    pipeline.set_state(gst::State::Playing)?;
+    let bus = pipeline.bus().unwrap();
+    let deadline = Instant::now() + ANALYSIS_TIMEOUT;
+    let mut result = Ok(());
+    while Instant::now() < deadline && !detector.lock().unwrap().is_done() {
+        if let Some(msg) = bus.timed_pop(gst::ClockTime::from_mseconds(50)) {
+            match msg.view() {
+                gst::MessageView::Eos(_) => break,
+                gst::MessageView::Error(err) => {
+                    result = Err(anyhow!("Blank analysis of {} failed: {}", redact_url(uri), err.error()));
+                    break;
+                }
+                _ => (),
+            }
+        }
+    }
+    pipeline.set_state(gst::State::Null)?;
+    result?;
+
+    let trim = detector.lock().unwrap().trim_point();
+    Ok(trim.map(|d| gst::ClockTime::from_nseconds(d.as_nanos() as u64)))
+}
+
+fn attach_analyzer(pipeline: &gst::Pipeline, pad: &gst::Pad, detector: &Arc<Mutex<LeadingBlankDetector>>) -> Result<()> {
+    let caps = pad.current_caps().ok_or_else(|| anyhow!("pad has no caps"))?;
+    let media_type = caps.structure(0).map(|s| s.name().to_string()).unwrap_or_default();
+    let is_video = media_type.starts_with("video/");
+
+    let mut chain = Vec::new();
+    if is_video {
+        let caps = gst::Caps::builder("video/x-raw")
+            .field("format", "GRAY8")
+            .field("width", ANALYSIS_WIDTH)
+            .field("height", ANALYSIS_HEIGHT)
+            .build();
+        chain.push(gst::ElementFactory::make("videoconvert").build()?);
+        chain.push(gst::ElementFactory::make("videoscale").build()?);
+        chain.push(gst::ElementFactory::make("capsfilter").property("caps", caps).build()?);
+    } else if media_type.starts_with("audio/") {
+        let caps = gst::Caps::builder("audio/x-raw")
+            .field("format", "S16LE")
+            .field("layout", "interleaved")
+            .field("channels", 1i32)
+            .build();
+        chain.push(gst::ElementFactory::make("audioconvert").build()?);
+        chain.push(gst::ElementFactory::make("capsfilter").property("caps", caps).build()?);
+    } else {
+        return Ok(());
+    }
+    let sink = gst::ElementFactory::make("fakesink").property("sync", false).build()?;
+    chain.push(sink.clone());
+
+    pipeline.add_many(&chain)?;
+    gst::Element::link_many(&chain)?;
+    for elem in &chain {
+        elem.sync_state_with_parent()?;
+    }
+    pad.link(&chain[0].static_pad("sink").unwrap())?;
+
+    let detector = detector.clone();
+    sink.static_pad("sink").unwrap().add_probe(gst::PadProbeType::BUFFER, move |_, info| {
+        if let Some(buffer) = info.buffer() {
+            if let (Some(pts), Ok(map)) = (buffer.pts(), buffer.map_readable()) {
+                let pts = Duration::from_nanos(pts.nseconds());
+                let mut detector = detector.lock().unwrap();
+                if is_video {
+                    detector.push_video_frame(pts, map.as_slice());
+                } else {
+                    detector.push_audio(pts, map.as_slice());
+                }
+            }
+        }
+        gst::PadProbeReturn::Ok
+    });
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Levels below which the start of a source counts as blank and gets trimmed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlankThresholds {
+    /// Frames with a mean luma (0-255) at or below this are black. Video black is 16.
+    pub black_luma: f64,
+    /// Audio whose peak stays at or below this level (dBFS) is silence.
+    pub silence_dbfs: f64,
+    /// Never trim more than this; media after it is not analysed.
+    pub max_trim: Duration,
+}
+
+impl Default for BlankThresholds {
+    fn default() -> Self {
+        Self {
+            black_luma: 24.0,
+            silence_dbfs: -50.0,
+            max_trim: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Mean luma of a tightly packed GRAY8 frame.
+pub fn mean_luma(gray8: &[u8]) -> f64 {
+    if gray8.is_empty() {
+        return 0.0;
+    }
+    gray8.iter().map(|&v| v as u64).sum::<u64>() as f64 / gray8.len() as f64
+}
+
+/// Peak level of interleaved S16LE samples in dBFS; `-inf` for digital silence.
+pub fn peak_dbfs(s16le: &[u8]) -> f64 {
+    let peak = s16le.chunks_exact(2)
+        .map(|b| (i16::from_le_bytes([b[0], b[1]]) as i32).abs())
+        .max()
+        .unwrap_or(0);
+    if peak == 0 {
+        return f64::NEG_INFINITY;
+    }
+    20.0 * (peak as f64 / 32768.0).log10()
+}
+
+#[derive(Debug, Default)]
+struct StreamScan {
+    seen: bool,
+    first_content: Option<Duration>,
+    last_pts: Duration,
+}
+
+impl StreamScan {
+    fn push(&mut self, pts: Duration, is_blank: bool, max_trim: Duration) {
+        self.seen = true;
+        self.last_pts = self.last_pts.max(pts);
+        // Content past the window doesn't count; only that the window was covered does.
+        if !is_blank && pts <= max_trim && self.first_content.is_none() {
+            self.first_content = Some(pts);
+        }
+    }
+
+    fn is_resolved(&self, max_trim: Duration) -> bool {
+        self.first_content.is_some() || self.last_pts >= max_trim
+    }
+}
+
+/// Finds where real content starts by looking at the first frames/samples of each stream.
+#[derive(Debug)]
+pub struct LeadingBlankDetector {
+    thresholds: BlankThresholds,
+    video: StreamScan,
+    audio: StreamScan,
+}
+
+impl LeadingBlankDetector {
+    pub fn new(thresholds: BlankThresholds) -> Self {
+        Self { thresholds, video: StreamScan::default(), audio: StreamScan::default() }
+    }
+
+    pub fn push_video_frame(&mut self, pts: Duration, gray8: &[u8]) {
+        let is_black = mean_luma(gray8) <= self.thresholds.black_luma;
+        self.video.push(pts, is_black, self.thresholds.max_trim);
+    }
+
+    pub fn push_audio(&mut self, pts: Duration, s16le: &[u8]) {
+        let is_silent = peak_dbfs(s16le) <= self.thresholds.silence_dbfs;
+        self.audio.push(pts, is_silent, self.thresholds.max_trim);
+    }
+
+    /// True once every stream seen so far has either shown content or run past `max_trim`.
+    pub fn is_done(&self) -> bool {
+        let streams = [&self.video, &self.audio];
+        streams.iter().any(|s| s.seen)
+            && streams.iter().filter(|s| s.seen).all(|s| s.is_resolved(self.thresholds.max_trim))
+    }
+
+    /// Where playback should start: the earliest content on any stream, so audio over black
+    /// (or video over silence) is never cut. `None` when there is nothing to trim or no
+    /// content was found within `max_trim`.
+    pub fn trim_point(&self) -> Option<Duration> {
+        [&self.video, &self.audio].iter()
+            .filter_map(|s| s.first_content)
+            .min()
+            .filter(|start| !start.is_zero())
+    }
+}
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use log::warn;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::LOG_PLAYBACK;
+
+/// The thread handling a running stream's bus, kept so stopping can wait for it to be gone.
+#[derive(Debug)]
+pub(crate) struct BusThread {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl BusThread {
+    /// Runs `f` on a thread of its own. `f` is to check the flag it is given between messages
+    /// and return once it is set.
+    pub(crate) fn spawn(f: impl FnOnce(&AtomicBool) + Send + 'static) -> Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let handle = thread::Builder::new()
+            .name("hayai-bus".to_string())
+            .spawn(move || f(&flag))?;
+        Ok(Self { stop, handle })
+    }
+
+    /// Tells the thread to finish and waits until it has. Not for the thread itself.
+    pub(crate) fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if self.handle.join().is_err() {
+            warn!(target: LOG_PLAYBACK, "The bus thread panicked.");
+        }
+    }
+}
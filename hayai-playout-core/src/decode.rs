@@ -0,0 +1,66 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::glib;
+use gst::prelude::*;
+use log::warn;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{ItemKind, LOG_SOURCE, PlaylistItem, SOURCE_NAME_PREFIX};
+
+static NEXT_SOURCE_SEQ: AtomicU64 = AtomicU64::new(1);
+
+// GST_AUTOPLUG_SELECT_SKIP
+const AUTOPLUG_SELECT_SKIP: i32 = 2;
+
+/// Builds the decoder element that plays `item`. Each element gets a fresh name suffix so
+/// replaying an item can't clash with its previous, not yet removed, element.
+pub fn create_source(item: &PlaylistItem) -> Result<gst::Element> {
+    let seq = NEXT_SOURCE_SEQ.fetch_add(1, Ordering::SeqCst);
+    let source_elem = gst::ElementFactory::make("uridecodebin")
+        .name(&format!("{}{}_{}", SOURCE_NAME_PREFIX, item.id, seq))
+        .build()?;
+    source_elem.set_property("uri", &item.uri);
+    if item.force_software_decode {
+        force_software_decoding(&source_elem);
+    }
+    if let ItemKind::Live { latency_ms } = item.kind {
+        set_live_latency(&source_elem, latency_ms);
+    }
+    Ok(source_elem)
+}
+
+// The jitter buffer of `rtspsrc` and the like holds packets this long; it is only made once
+// uridecodebin knows the URI's scheme.
+fn set_live_latency(source_elem: &gst::Element, latency_ms: u32) {
+    source_elem.connect("source-setup", false, move |values| {
+        if let Ok(source) = values[1].get::<gst::Element>() {
+            if source.has_property("latency") {
+                source.set_property("latency", latency_ms);
+            }
+        }
+        None
+    });
+}
+
+fn force_software_decoding(source_elem: &gst::Element) {
+    if source_elem.has_property("force-sw-decoders") {
+        source_elem.set_property("force-sw-decoders", true);
+        return;
+    }
+    // Older GStreamer has no switch, so turn hardware decoders down one by one as decodebin
+    // considers them.
+    let result_type = match glib::Type::from_name("GstAutoplugSelectResult") {
+        Some(t) => t,
+        None => {
+            warn!(target: LOG_SOURCE, "Cannot force software decoding with this GStreamer version.");
+            return;
+        }
+    };
+    source_elem.connect("autoplug-select", false, move |values| {
+        let is_hardware = values[3].get::<gst::ElementFactory>()
+            .map_or(false, |factory| factory.klass().contains("Hardware"));
+        // TRY (0) lets decodebin go ahead with the factory.
+        let result = if is_hardware { AUTOPLUG_SELECT_SKIP } else { 0 };
+        glib::EnumClass::with_type(result_type).and_then(|class| class.to_value(result))
+    });
+}
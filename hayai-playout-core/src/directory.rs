@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::sort::natural_cmp;
+
+/// File extensions [`Streamer::add_directory`](crate::Streamer::add_directory) callers can
+/// pass to pick up the usual video and audio files.
+pub const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "m4v", "mkv", "mov", "avi", "webm", "ts", "mts", "m2ts", "mxf", "flv", "mpg", "mpeg", "wmv",
+    "mp3", "m4a", "aac", "wav", "flac", "ogg", "opus",
+];
+
+/// Whether `path` ends in one of [`MEDIA_EXTENSIONS`], in any case. Doesn't look at the file.
+pub fn is_media_file(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| {
+        MEDIA_EXTENSIONS.iter().any(|wanted| ext.to_string_lossy().eq_ignore_ascii_case(wanted))
+    })
+}
+
+/// The files under `dir` whose extension is one of `extensions` (case-insensitive, with or
+/// without the dot), sorted naturally by their path below `dir`. Hidden files and folders
+/// are left out, and symlinked folders are not followed.
+pub(crate) fn media_files(dir: &Path, extensions: &[&str], recursive: bool) -> Result<Vec<PathBuf>> {
+    let extensions: Vec<String> = extensions.iter()
+        .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+        .collect();
+    let mut files = Vec::new();
+    collect(dir, &extensions, recursive, &mut files)?;
+    files.sort_by(|a, b| {
+        let relative = |path: &Path| path.strip_prefix(dir).unwrap_or(path).to_string_lossy().into_owned();
+        natural_cmp(&relative(a), &relative(b))
+    });
+    Ok(files)
+}
+
+fn collect(dir: &Path, extensions: &[String], recursive: bool, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("Could not read folder {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Could not read folder {}", dir.display()))?;
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if recursive {
+                collect(&path, extensions, recursive, files)?;
+            }
+        } else if path.is_file() && has_extension(&path, extensions) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .map_or(false, |ext| extensions.iter().any(|wanted| ext.to_string_lossy().eq_ignore_ascii_case(wanted)))
+}
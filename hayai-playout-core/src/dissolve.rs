@@ -0,0 +1,152 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::freeze::create_still_bin;
+use crate::pipeline::require_factory;
+
+const DISSOLVE_MIXER_NAME: &str = "dissolve_mixer";
+const DISSOLVE_STILL_NAME: &str = "dissolve_still";
+
+/// How the picture changes from one playlist entry to the next.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransitionStyle {
+    /// Switch straight over. Nothing is added to the pipeline for it.
+    #[default]
+    Cut,
+    /// Blend from the outgoing picture into the incoming one over `duration`.
+    Dissolve { duration: Duration },
+}
+
+// The still being faded away, and when it started to fade.
+struct Ramp {
+    pad: gst::Pad,
+    from: Option<gst::ClockTime>,
+    duration: gst::ClockTime,
+}
+
+impl Ramp {
+    fn progress(&self, now: gst::ClockTime) -> f64 {
+        match self.from {
+            Some(from) if self.duration > gst::ClockTime::ZERO => {
+                (now.saturating_sub(from).nseconds() as f64 / self.duration.nseconds() as f64).min(1.0)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// A `compositor` between the video selector and the output that lays the last outgoing frame
+/// over the incoming item and fades it away.
+///
+/// Only the selector's output reaches the mixer, so the outgoing side of the dissolve is held
+/// still while the incoming item already plays; the selectors themselves still cut, and audio
+/// is left to the audio fades.
+pub(crate) struct Dissolver {
+    mixer: gst::Element,
+    last_frame: Arc<Mutex<Option<gst::Sample>>>,
+    ramp: Arc<Mutex<Option<Ramp>>>,
+    still: Option<gst::Bin>,
+}
+
+impl Dissolver {
+    /// Puts the mixer between `selector` and the `sink_pad` of `downstream`. `last_frame` is
+    /// kept up to date with the newest program frame by the caller.
+    pub(crate) fn insert(
+        pipeline: &gst::Pipeline,
+        selector: &gst::Element,
+        downstream: &gst::Element,
+        sink_pad: &str,
+        last_frame: Arc<Mutex<Option<gst::Sample>>>,
+    ) -> Result<Self> {
+        require_factory("compositor")?;
+        let mixer = gst::ElementFactory::make("compositor")
+            .name(DISSOLVE_MIXER_NAME)
+            .property_from_str("background", "black")
+            .build()?;
+        pipeline.add(&mixer)?;
+        let program = mixer.request_pad_simple("sink_%u")
+            .ok_or_else(|| anyhow!("{} did not provide a sink pad", mixer.name()))?;
+        selector.static_pad("src").unwrap().link(&program)?;
+        mixer.link_pads(Some("src"), downstream, Some(sink_pad))?;
+
+        // The alpha steps with every program frame; the bus thread only cleans up afterwards.
+        let ramp: Arc<Mutex<Option<Ramp>>> = Arc::default();
+        let ramp_clone = ramp.clone();
+        program.add_probe(gst::PadProbeType::BUFFER, move |pad, _| {
+            if let Some(ramp) = ramp_clone.lock().unwrap().as_mut() {
+                if let Some(now) = pad.parent_element().and_then(|mixer| mixer.current_running_time()) {
+                    ramp.from.get_or_insert(now);
+                    ramp.pad.set_property("alpha", 1.0 - ramp.progress(now));
+                }
+                // The still is stretched over the incoming picture, whatever size either is.
+                if let Some(s) = pad.current_caps().as_ref().and_then(|caps| caps.structure(0)) {
+                    if let (Ok(width), Ok(height)) = (s.get::<i32>("width"), s.get::<i32>("height")) {
+                        ramp.pad.set_property("width", width);
+                        ramp.pad.set_property("height", height);
+                    }
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
+        Ok(Self { mixer, last_frame, ramp, still: None })
+    }
+
+    /// The frame to dissolve from, taken before the outgoing entry is switched away.
+    pub(crate) fn outgoing_frame(&self) -> Option<gst::Sample> {
+        self.last_frame.lock().unwrap().clone()
+    }
+
+    /// Starts fading `frame` away over `duration`. A dissolve still running is cut short.
+    pub(crate) fn begin(&mut self, pipeline: &gst::Pipeline, frame: &gst::Sample, duration: Duration) -> Result<()> {
+        self.end(pipeline);
+        let bin = create_still_bin(DISSOLVE_STILL_NAME, frame, true)?;
+        pipeline.add(&bin)?;
+        let pad = match self.mixer.request_pad_simple("sink_%u") {
+            Some(pad) => pad,
+            None => {
+                let _ = pipeline.remove(&bin);
+                return Err(anyhow!("{} did not provide a sink pad", self.mixer.name()));
+            }
+        };
+        if let Err(e) = bin.static_pad("video_src").unwrap().link(&pad) {
+            self.mixer.release_request_pad(&pad);
+            let _ = pipeline.remove(&bin);
+            return Err(anyhow!("linking the dissolve still failed: {}", e));
+        }
+        bin.sync_state_with_parent()?;
+
+        *self.ramp.lock().unwrap() = Some(Ramp {
+            pad,
+            from: self.mixer.current_running_time(),
+            duration: gst::ClockTime::from_nseconds(duration.as_nanos() as u64),
+        });
+        self.still = Some(bin);
+        Ok(())
+    }
+
+    /// Removes the still once it has faded away completely.
+    pub(crate) fn finish_if_done(&mut self, pipeline: &gst::Pipeline) {
+        let done = match (self.ramp.lock().unwrap().as_ref(), self.mixer.current_running_time()) {
+            (Some(ramp), Some(now)) => ramp.progress(now) >= 1.0,
+            _ => false,
+        };
+        if done {
+            self.end(pipeline);
+        }
+    }
+
+    fn end(&mut self, pipeline: &gst::Pipeline) {
+        let ramp = self.ramp.lock().unwrap().take();
+        if let Some(bin) = self.still.take() {
+            let _ = bin.set_state(gst::State::Null);
+            if let Some(ramp) = ramp {
+                self.mixer.release_request_pad(&ramp.pad);
+            }
+            let _ = pipeline.remove(&bin);
+        }
+    }
+}
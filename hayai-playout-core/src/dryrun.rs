@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::events::EventBus;
+use crate::fill::FillPicture;
+use crate::pipeline::{build_processing_bin, resolve_encoder, AUDIO_SINK_PAD, VIDEO_SINK_PAD};
+use crate::{add_source, redact_url, EncodingSettings, PlayoutEvent, PlaylistItem, SourceSetup, SourceStart};
+
+// Long enough for a network source to connect and buffer.
+const DRY_RUN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Prerolls `item` through the encoders for `settings` into a sink that throws it all away,
+/// linked up the way [`Streamer::start`](crate::Streamer::start) would, then tears it down.
+pub(crate) fn run(item: &PlaylistItem, settings: &EncodingSettings, video_fill: FillPicture) -> Result<()> {
+    let (effective, _) = resolve_encoder(settings);
+    effective.validate()?;
+    // Nothing gets recorded either.
+    let effective = EncodingSettings { record_path: None, ..effective };
+    let sink = gst::ElementFactory::make("fakesink").build()?;
+    let processing_bin = build_processing_bin(&effective, Some("flvmux"), &sink)?;
+
+    let pipeline = gst::Pipeline::new();
+    let video_selector = gst::ElementFactory::make("input-selector").build()?;
+    let audio_selector = gst::ElementFactory::make("input-selector").build()?;
+    pipeline.add_many(&[&video_selector, &audio_selector, processing_bin.upcast_ref()])?;
+    video_selector.link_pads(Some("src"), &processing_bin, Some(VIDEO_SINK_PAD))?;
+    audio_selector.link_pads(Some("src"), &processing_bin, Some(AUDIO_SINK_PAD))?;
+
+    // Only for hearing about pads that could not be linked.
+    let events = Arc::new(EventBus::default());
+    let link_failures = events.subscribe();
+    let setup = SourceSetup { video_fill, ..SourceSetup::new(SourceStart::Beginning) };
+    add_source(&pipeline, &video_selector, &audio_selector, item, &setup, &events)?;
+
+    let result = preroll(&pipeline);
+    let _ = pipeline.set_state(gst::State::Null);
+    if let Ok(PlayoutEvent::SourceLinkFailed { error, .. }) = link_failures.try_recv() {
+        return Err(anyhow!("{} could not be connected to the encoders: {}", redact_url(&item.uri), error));
+    }
+    result
+}
+
+// Waits until every sink in `pipeline` has had its first buffer.
+fn preroll(pipeline: &gst::Pipeline) -> Result<()> {
+    // A failure is left to the error it posts, which says more.
+    if let Ok(gst::StateChangeSuccess::NoPreroll) = pipeline.set_state(gst::State::Paused) {
+        // Live sources only start flowing once playing.
+        pipeline.set_state(gst::State::Playing)?;
+    }
+    let bus = pipeline.bus().unwrap();
+    let msg = bus.timed_pop_filtered(
+        gst::ClockTime::from_nseconds(DRY_RUN_TIMEOUT.as_nanos() as u64),
+        &[gst::MessageType::AsyncDone, gst::MessageType::Error],
+    );
+    match msg.as_ref().map(|m| m.view()) {
+        Some(gst::MessageView::AsyncDone(_)) => Ok(()),
+        Some(gst::MessageView::Error(err)) => Err(anyhow!("Dry run failed: {}", err.error())),
+        _ => Err(anyhow!("Dry run did not get going within {:?}", DRY_RUN_TIMEOUT)),
+    }
+}
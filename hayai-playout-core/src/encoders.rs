@@ -0,0 +1,100 @@
+use gstreamer as gst;
+use gst::glib::translate::IntoGlib;
+use gst::prelude::*;
+use log::warn;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::{EncoderProfile, LOG_OUTPUT};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncoderKind {
+    Video,
+    Audio,
+}
+
+impl EncoderKind {
+    fn klass(self) -> &'static str {
+        match self {
+            EncoderKind::Video => "Codec/Encoder/Video",
+            EncoderKind::Audio => "Codec/Encoder/Audio",
+        }
+    }
+}
+
+/// An installed encoder element.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncoderInfo {
+    /// Factory name, as used in [`EncodingSettings`](crate::EncodingSettings).
+    pub name: String,
+    pub long_name: String,
+    /// Plugin rank; autopluggers prefer higher ranks. 0 means the element is never picked
+    /// automatically, which is how broken or disabled encoders are usually demoted.
+    pub rank: i32,
+    pub hardware: bool,
+    /// The family the encoder is configured as, if it is a known one.
+    pub profile: Option<EncoderProfile>,
+    /// Whether the encoder could actually be opened on this machine. Hardware encoders are
+    /// often registered even when the device or driver behind them is missing.
+    pub usable: bool,
+}
+
+// Probing hardware encoders can take a while, and the answer doesn't change while running.
+static PROBE_CACHE: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
+
+/// Installed encoders of `kind`, sorted by name.
+pub fn list_encoders(kind: EncoderKind) -> Vec<EncoderInfo> {
+    if gst::init().is_err() {
+        return Vec::new();
+    }
+    let mut encoders: Vec<EncoderInfo> = gst::Registry::get()
+        .features(gst::ElementFactory::static_type())
+        .into_iter()
+        .filter_map(|feature| feature.downcast::<gst::ElementFactory>().ok())
+        .filter(|factory| factory.klass().contains(kind.klass()))
+        .map(|factory| {
+            let name = factory.name().to_string();
+            EncoderInfo {
+                usable: probe_encoder(&name),
+                long_name: factory.longname().to_string(),
+                rank: factory.rank().into_glib(),
+                hardware: factory.klass().contains("Hardware"),
+                profile: EncoderProfile::for_encoder(&name),
+                name,
+            }
+        })
+        .collect();
+    encoders.sort_by(|a, b| a.name.cmp(&b.name));
+    encoders
+}
+
+/// The installed encoder of `kind` called `name`, if any. Use this to check an encoder name
+/// from settings or the command line before starting a stream.
+pub fn find_encoder(kind: EncoderKind, name: &str) -> Option<EncoderInfo> {
+    list_encoders(kind).into_iter().find(|encoder| encoder.name == name)
+}
+
+/// Checks that the encoder can be created and brought to READY, which is where hardware
+/// encoders open their device. Unknown factories are reported as unusable. Results are cached.
+pub fn probe_encoder(name: &str) -> bool {
+    let cache = PROBE_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(usable) = cache.lock().unwrap().get(name) {
+        return *usable;
+    }
+    let usable = gst::init().is_ok() && try_open(name);
+    cache.lock().unwrap().insert(name.to_string(), usable);
+    usable
+}
+
+fn try_open(name: &str) -> bool {
+    let element = match gst::ElementFactory::make(name).build() {
+        Ok(element) => element,
+        Err(_) => return false,
+    };
+    let opened = element.set_state(gst::State::Ready).is_ok();
+    let _ = element.set_state(gst::State::Null);
+    if !opened {
+        warn!(target: LOG_OUTPUT, "Encoder {} is installed but could not be opened.", name);
+    }
+    opened
+}
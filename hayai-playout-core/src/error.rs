@@ -0,0 +1,117 @@
+use std::fmt;
+
+/// Failures an operator can do something about. Returned inside `anyhow::Error`; use
+/// [`user_message`] to turn any error into something to show in a dialog.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamerError {
+    /// A GStreamer element needed for the stream is not installed.
+    MissingPlugin { element: String },
+    /// The output URL cannot be streamed to. Holds the redacted URL.
+    InvalidUrl { url: String },
+    /// An `rtmps://` URL, but no installed RTMP sink can do TLS. Holds the redacted URL.
+    TlsUnsupported { url: String },
+    AlreadyRunning,
+    NotRunning,
+    ItemNotFound { id: u64 },
+    InvalidPosition { index: usize, len: usize },
+    /// Every playlist item is disabled or filtered out, or the playlist is empty.
+    NoPlayableItems,
+    /// Not a URI, or one no installed GStreamer element can read. Holds the redacted URI.
+    UnsupportedUri { uri: String },
+    /// Video bitrate outside [`EncodingSettings::MIN_BITRATE_KBPS`](crate::EncodingSettings::MIN_BITRATE_KBPS)
+    /// to [`EncodingSettings::MAX_BITRATE_KBPS`](crate::EncodingSettings::MAX_BITRATE_KBPS).
+    InvalidBitrate { kbps: u32 },
+    /// Scaling is on, but a dimension is zero or odd.
+    InvalidScale { width: u32, height: u32 },
+    /// The keyframe interval works out to zero frames.
+    InvalidKeyframeInterval,
+    /// An output frame rate with a zero or out-of-range denominator or numerator.
+    InvalidFramerate { num: u32, den: u32 },
+}
+
+impl StreamerError {
+    /// What went wrong and how to fix it, worded for operators rather than developers.
+    pub fn user_message(&self) -> String {
+        match self {
+            Self::MissingPlugin { element } => format!(
+                "The GStreamer element '{}' is not installed. Install the plugin set that provides it \
+                 (RTMP and FLV output need gst-plugins-bad and gst-plugins-good), then restart.",
+                element
+            ),
+            Self::InvalidUrl { url } => format!(
+                "'{}' is not a usable output address. Check the RTMP URL; it should look like \
+                 rtmp://server/app (or srt://server:port for SRT).",
+                url
+            ),
+            Self::TlsUnsupported { url } => format!(
+                "'{}' needs a secure (TLS) RTMP connection, which the installed RTMP plugin cannot make. \
+                 Install the rtmp2 plugin from gst-plugins-bad, or use an rtmp:// URL if the server offers one.",
+                url
+            ),
+            Self::AlreadyRunning => "A stream is already running. Stop it before starting a new one.".to_string(),
+            Self::NotRunning => "No stream is running. Start the stream first.".to_string(),
+            Self::ItemNotFound { id } => format!(
+                "Playlist item {} no longer exists. It may have been removed; refresh the playlist.",
+                id
+            ),
+            Self::InvalidPosition { index, len } => format!(
+                "Position {} is outside the playlist, which has {} item(s).",
+                index + 1, len
+            ),
+            Self::NoPlayableItems => "There is nothing to play. Add files to the playlist, or enable \
+                 items and check the tag filter."
+                .to_string(),
+            Self::UnsupportedUri { uri } => format!(
+                "'{}' cannot be played. Use a file, or an http(s):// or rtsp:// address; other kinds of \
+                 address need the GStreamer plugin that reads them.",
+                uri
+            ),
+            Self::InvalidBitrate { kbps } => format!(
+                "A video bitrate of {} kbps is not usable. Choose a bitrate between {} and {} kbps.",
+                kbps, crate::EncodingSettings::MIN_BITRATE_KBPS, crate::EncodingSettings::MAX_BITRATE_KBPS
+            ),
+            Self::InvalidScale { width, height } => format!(
+                "Cannot scale to {}x{}. Width and height must be even numbers greater than zero, e.g. 1280x720.",
+                width, height
+            ),
+            Self::InvalidKeyframeInterval => "The keyframe interval must be at least one frame. \
+                 Most streaming services ask for a keyframe every 2 seconds."
+                .to_string(),
+            Self::InvalidFramerate { num, den } => format!(
+                "{}/{} is not a usable frame rate. Use e.g. 30/1, 25/1 or 30000/1001, or 0 to keep the source rate.",
+                num, den
+            ),
+        }
+    }
+}
+
+impl fmt::Display for StreamerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPlugin { element } => write!(f, "GStreamer element {} is not available", element),
+            Self::InvalidUrl { url } => write!(f, "Invalid output URL: {}", url),
+            Self::TlsUnsupported { url } => write!(f, "No RTMP sink with TLS support for {}", url),
+            Self::AlreadyRunning => write!(f, "Stream is already running"),
+            Self::NotRunning => write!(f, "Stream is not running"),
+            Self::ItemNotFound { id } => write!(f, "ID {} not found", id),
+            Self::InvalidPosition { index, len } => write!(f, "Index {} out of bounds for {} items", index, len),
+            Self::NoPlayableItems => write!(f, "Playlist has no enabled items"),
+            Self::UnsupportedUri { uri } => write!(f, "Unsupported URI: {}", uri),
+            Self::InvalidBitrate { kbps } => write!(f, "Invalid bitrate: {} kbps", kbps),
+            Self::InvalidScale { width, height } => write!(f, "Invalid scale size: {}x{}", width, height),
+            Self::InvalidKeyframeInterval => write!(f, "Keyframe interval is zero"),
+            Self::InvalidFramerate { num, den } => write!(f, "Invalid frame rate: {}/{}", num, den),
+        }
+    }
+}
+
+impl std::error::Error for StreamerError {}
+
+/// The operator-facing message for `err`: [`StreamerError::user_message`] if it is (or wraps)
+/// a [`StreamerError`], otherwise the full error chain.
+pub fn user_message(err: &anyhow::Error) -> String {
+    match err.downcast_ref::<StreamerError>() {
+        Some(e) => e.user_message(),
+        None => format!("{:#}", err),
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+const HISTORY_MAX_EVENTS: usize = 1000;
+const HISTORY_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Notifications about what the playout engine is doing, delivered to subscribers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlayoutEvent {
+    /// The output dropped and the program was switched to standby while reconnecting.
+    ReconnectStandbyEntered,
+    /// The output came back and the program returned from standby.
+    ReconnectStandbyLeft,
+    /// Reconnection attempts ran out and the stream was stopped.
+    ReconnectGaveUp,
+    /// Reconnection attempts ran out; standby stays on air while retrying at a slow interval.
+    ReconnectStandby,
+    /// The output dropped and reconnection attempt `attempt` is scheduled.
+    Reconnecting { attempt: u32 },
+    /// The stream ended after the current item, as asked for with `stop_after_current`.
+    StoppedAfterCurrent,
+    /// The last item finished with [`crate::RepeatMode::None`] and the stream ended.
+    PlaylistEnded,
+    /// A stream of a new source could not be connected to the program. `uri` is redacted.
+    SourceLinkFailed { uri: String, error: String },
+    /// The title of the entry on air: its file name when it goes on air, then the title from
+    /// its tags once they are read, if it has one.
+    TitleChanged { title: String },
+    /// Nothing in the playlist could be played, so the fallback clip went on air.
+    FallbackStarted,
+    /// The playlist is playing again after the fallback clip.
+    FallbackEnded,
+    /// The item with `id` failed while on air and was skipped. `uri` is redacted.
+    ItemFailed { id: u64, uri: String, error: String },
+    /// `count` items in a row failed; the stream went to the fallback clip if there is one,
+    /// and stopped otherwise.
+    TooManyFailures { count: u32 },
+    /// Frames were dropped because the pipeline couldn't keep up, e.g. with an encoder preset
+    /// too slow for the machine: `dropped` of them, and `processed` that made it, over the last
+    /// few seconds. Sent at most once per window.
+    QosWarning { dropped: u64, processed: u64 },
+    /// An output added with `add_output` failed and was taken out; the stream carries on to
+    /// the others. `url` is redacted.
+    OutputFailed { url: String, error: String },
+    /// Loudness of the program audio, per channel in dBFS, a few times a second while
+    /// streaming: the `peak` and the `rms` average since the last reading. Silence reads as
+    /// [`crate::LEVEL_FLOOR_DB`]. Too frequent to be kept in the event history.
+    AudioLevels { peak_db: Vec<f64>, rms_db: Vec<f64> },
+}
+
+/// Recent events with the time they happened, bounded by count and by age.
+#[derive(Debug)]
+pub struct EventHistory {
+    entries: VecDeque<(SystemTime, PlayoutEvent)>,
+    max_events: usize,
+    max_age: Duration,
+}
+
+impl Default for EventHistory {
+    fn default() -> Self {
+        Self::new(HISTORY_MAX_EVENTS, HISTORY_MAX_AGE)
+    }
+}
+
+impl EventHistory {
+    pub fn new(max_events: usize, max_age: Duration) -> Self {
+        Self { entries: VecDeque::new(), max_events, max_age }
+    }
+
+    /// Records `event` as happening `at`, evicting whatever falls out of the count or age limit.
+    pub fn record(&mut self, at: SystemTime, event: PlayoutEvent) {
+        self.entries.push_back((at, event));
+        while self.entries.len() > self.max_events {
+            self.entries.pop_front();
+        }
+        if let Some(cutoff) = at.checked_sub(self.max_age) {
+            while self.entries.front().map_or(false, |(t, _)| *t < cutoff) {
+                self.entries.pop_front();
+            }
+        }
+    }
+
+    /// Events recorded at or after `since`, oldest first.
+    pub fn since(&self, since: SystemTime) -> Vec<PlayoutEvent> {
+        self.entries.iter()
+            .filter(|(t, _)| *t >= since)
+            .map(|(_, event)| event.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Fans events out to every live subscriber; disconnected receivers are dropped on the next emit.
+#[derive(Default)]
+pub(crate) struct EventBus {
+    subscribers: Mutex<Vec<Sender<PlayoutEvent>>>,
+    history: Mutex<EventHistory>,
+}
+
+impl EventBus {
+    pub(crate) fn subscribe(&self) -> Receiver<PlayoutEvent> {
+        let (tx, rx) = channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    pub(crate) fn since(&self, since: SystemTime) -> Vec<PlayoutEvent> {
+        self.history.lock().unwrap().since(since)
+    }
+
+    pub(crate) fn emit(&self, event: PlayoutEvent) {
+        if !matches!(event, PlayoutEvent::AudioLevels { .. }) {
+            self.history.lock().unwrap().record(SystemTime::now(), event.clone());
+        }
+        self.subscribers.lock().unwrap().retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
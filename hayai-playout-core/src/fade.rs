@@ -0,0 +1,117 @@
+use gstreamer as gst;
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+struct Ramps {
+    /// Timestamp of the first buffer, where the fade-in starts.
+    fade_in_from: Option<gst::ClockTime>,
+    fade_out_requested: bool,
+    /// Timestamp of the first buffer after the fade-out was requested.
+    fade_out_from: Option<gst::ClockTime>,
+}
+
+/// Fades a source's audio in from silence when it starts and, on request, back out to
+/// silence, so cuts between sources don't pop.
+///
+/// The gain is applied to the decoded samples in a pad probe, one step per buffer, rather
+/// than by a `volume` element, so the source stays linked straight to the selector. Raw
+/// F32, F64, S16 and S32 audio is faded; anything else passes through untouched.
+#[derive(Clone, Debug)]
+pub(crate) struct Fader {
+    duration: gst::ClockTime,
+    ramps: Arc<Mutex<Ramps>>,
+}
+
+impl Fader {
+    pub(crate) fn new(duration: gst::ClockTime) -> Self {
+        Self { duration, ramps: Arc::default() }
+    }
+
+    pub(crate) fn duration(&self) -> gst::ClockTime {
+        self.duration
+    }
+
+    /// Fades the audio coming out of `pad`.
+    pub(crate) fn attach(&self, pad: &gst::Pad) {
+        let fader = self.clone();
+        pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+            let format = pad.current_caps()
+                .and_then(|caps| caps.structure(0).and_then(|s| s.get::<String>("format").ok()));
+            if let (Some(format), Some(gst::PadProbeData::Buffer(buffer))) = (format, &mut info.data) {
+                let gain = fader.gain_at(buffer.pts());
+                if gain < 1.0 {
+                    scale_samples(buffer.make_mut(), &format, gain);
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
+    }
+
+    /// Starts fading out from the next buffer. Later calls change nothing.
+    pub(crate) fn fade_out(&self) {
+        self.ramps.lock().unwrap().fade_out_requested = true;
+    }
+
+    pub(crate) fn is_fading_out(&self) -> bool {
+        self.ramps.lock().unwrap().fade_out_requested
+    }
+
+    fn gain_at(&self, pts: Option<gst::ClockTime>) -> f64 {
+        let pts = match pts {
+            Some(pts) => pts,
+            None => return 1.0,
+        };
+        let mut ramps = self.ramps.lock().unwrap();
+        let fade_in_from = *ramps.fade_in_from.get_or_insert(pts);
+        let mut gain = self.progress(fade_in_from, pts);
+        if ramps.fade_out_requested {
+            let fade_out_from = *ramps.fade_out_from.get_or_insert(pts);
+            gain *= 1.0 - self.progress(fade_out_from, pts);
+        }
+        gain
+    }
+
+    // How far into a ramp starting at `from` the buffer at `pts` is, from 0 to 1.
+    fn progress(&self, from: gst::ClockTime, pts: gst::ClockTime) -> f64 {
+        if self.duration == gst::ClockTime::ZERO {
+            return 1.0;
+        }
+        (pts.saturating_sub(from).nseconds() as f64 / self.duration.nseconds() as f64).min(1.0)
+    }
+}
+
+fn scale_samples(buffer: &mut gst::BufferRef, format: &str, gain: f64) {
+    let mut map = match buffer.map_writable() {
+        Ok(map) => map,
+        Err(_) => return,
+    };
+    let data = map.as_mut_slice();
+    match format {
+        "F32LE" => {
+            for sample in data.chunks_exact_mut(4) {
+                let value = f32::from_le_bytes(sample.try_into().unwrap()) * gain as f32;
+                sample.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        "F64LE" => {
+            for sample in data.chunks_exact_mut(8) {
+                let value = f64::from_le_bytes(sample.try_into().unwrap()) * gain;
+                sample.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        "S16LE" => {
+            for sample in data.chunks_exact_mut(2) {
+                let value = (i16::from_le_bytes(sample.try_into().unwrap()) as f64 * gain) as i16;
+                sample.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        "S32LE" => {
+            for sample in data.chunks_exact_mut(4) {
+                let value = (i32::from_le_bytes(sample.try_into().unwrap()) as f64 * gain) as i32;
+                sample.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        _ => (),
+    }
+}
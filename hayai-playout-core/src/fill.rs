@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::freeze::create_still_bin;
+
+/// Name of the ghost pad a source gets when its audio is filled in.
+const FILL_AUDIO_PAD_NAME: &str = "fill_audio";
+/// Name of the ghost pad a source gets when its video is filled in.
+const FILL_VIDEO_PAD_NAME: &str = "fill_video";
+const FILL_PICTURE_NAME: &str = "fill_picture";
+// Size and rate of the test patterns; scaling, if on, still applies afterwards.
+const PATTERN_WIDTH: i32 = 1280;
+const PATTERN_HEIGHT: i32 = 720;
+const PATTERN_FRAMERATE: i32 = 30;
+
+/// What plays in place of the picture of an item that has none, such as a music bed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VideoFill {
+    #[default]
+    Black,
+    ColorBars,
+    /// A still image, e.g. a PNG or JPEG slate.
+    Image(PathBuf),
+}
+
+/// A [`VideoFill`] made ready to play; images are decoded once, up front.
+#[derive(Clone, Debug)]
+pub(crate) enum FillPicture {
+    Pattern(&'static str),
+    Still(gst::Sample),
+}
+
+impl Default for FillPicture {
+    fn default() -> Self {
+        Self::Pattern("black")
+    }
+}
+
+impl FillPicture {
+    pub(crate) fn load(fill: &VideoFill) -> Result<Self> {
+        Ok(match fill {
+            VideoFill::Black => Self::Pattern("black"),
+            VideoFill::ColorBars => Self::Pattern("smpte"),
+            VideoFill::Image(path) => Self::Still(decode_image(path)?),
+        })
+    }
+}
+
+/// Decodes the first frame of the image at `path`.
+fn decode_image(path: &Path) -> Result<gst::Sample> {
+    let location = path.to_str()
+        .ok_or_else(|| anyhow!("Image path {} is not valid UTF-8", path.display()))?;
+    let pipeline = gst::parse::launch(
+        "filesrc name=file ! decodebin ! videoconvert ! fakesink name=sink enable-last-sample=true",
+    )?
+    .downcast::<gst::Pipeline>()
+    .map_err(|_| anyhow!("Not a pipeline"))?;
+    pipeline.by_name("file").unwrap().set_property("location", location);
+
+    pipeline.set_state(gst::State::Paused)?;
+    let (result, _, _) = pipeline.state(gst::ClockTime::from_seconds(5));
+    let sample = pipeline.by_name("sink").unwrap().property::<Option<gst::Sample>>("last-sample");
+    let _ = pipeline.set_state(gst::State::Null);
+    result.map_err(|_| anyhow!("Cannot decode image {}", path.display()))?;
+    sample.ok_or_else(|| anyhow!("{} has no picture", path.display()))
+}
+
+/// Gives `source`, a decoder that turned out to have no audio, a silent audio pad of its own,
+/// so the audio selector and the muxer behind it keep getting timestamps. The silence lives
+/// inside the source and goes when it does.
+///
+/// Returns the silence and its pad, not started yet: link the pad, then bring the silence up
+/// with `sync_state_with_parent`.
+pub(crate) fn add_silence(source: &gst::Element) -> Result<(gst::Element, gst::Pad)> {
+    // Not live: it is timestamped from zero, like the source's own streams.
+    let silence = gst::ElementFactory::make("audiotestsrc")
+        .property_from_str("wave", "silence")
+        .build()?;
+    let target = silence.static_pad("src").unwrap();
+    add_fill(source, &silence, &target, FILL_AUDIO_PAD_NAME)
+}
+
+/// Like [`add_silence`], with `picture` for a source that has no video, so outputs that
+/// expect a video track get one.
+pub(crate) fn add_picture(source: &gst::Element, picture: &FillPicture) -> Result<(gst::Element, gst::Pad)> {
+    let fill = match picture {
+        FillPicture::Pattern(pattern) => {
+            let bin = gst::Bin::with_name(FILL_PICTURE_NAME);
+            let pattern_src = gst::ElementFactory::make("videotestsrc")
+                .property_from_str("pattern", pattern)
+                .build()?;
+            let caps = gst::ElementFactory::make("capsfilter")
+                .property("caps", gst::Caps::builder("video/x-raw")
+                    .field("width", PATTERN_WIDTH)
+                    .field("height", PATTERN_HEIGHT)
+                    .field("framerate", gst::Fraction::new(PATTERN_FRAMERATE, 1))
+                    .build())
+                .build()?;
+            bin.add_many(&[&pattern_src, &caps])?;
+            pattern_src.link(&caps)?;
+            let pad = gst::GhostPad::with_target(&caps.static_pad("src").unwrap())?;
+            pad.set_property("name", "video_src");
+            bin.add_pad(&pad)?;
+            bin
+        }
+        FillPicture::Still(frame) => create_still_bin(FILL_PICTURE_NAME, frame, false)?,
+    };
+    let target = fill.static_pad("video_src").unwrap();
+    add_fill(source, fill.upcast_ref(), &target, FILL_VIDEO_PAD_NAME)
+}
+
+// Puts `fill` inside `source`, exposing `target` as a pad of the source named `pad_name`.
+fn add_fill(source: &gst::Element, fill: &gst::Element, target: &gst::Pad, pad_name: &str) -> Result<(gst::Element, gst::Pad)> {
+    let bin = source.downcast_ref::<gst::Bin>()
+        .ok_or_else(|| anyhow!("{} is not a bin", source.name()))?;
+    bin.add(fill)?;
+    let pad = gst::GhostPad::with_target(target)?;
+    pad.set_property("name", pad_name);
+    pad.set_active(true)?;
+    source.add_pad(&pad)?;
+    Ok((fill.clone(), pad.upcast()))
+}
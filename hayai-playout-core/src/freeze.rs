@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use log::info;
+
+use crate::selector::link_to_selector;
+use crate::standby;
+use crate::LOG_PLAYBACK;
+
+pub(crate) const FREEZE_BIN_NAME: &str = "freeze_bin";
+
+/// A still of the last program frame that temporarily replaces the video; audio keeps playing.
+pub(crate) struct Freeze {
+    bin: gst::Bin,
+    video_pad: gst::Pad,
+}
+
+/// A source repeating `frame`, on a `video_src` ghost pad. A live one is timestamped by the
+/// clock, otherwise from zero.
+pub(crate) fn create_still_bin(name: &str, frame: &gst::Sample, live: bool) -> Result<gst::Bin> {
+    let caps = frame.caps().ok_or_else(|| anyhow!("Last frame has no caps"))?;
+    let buffer = frame.buffer().ok_or_else(|| anyhow!("Last frame has no buffer"))?;
+
+    let bin = gst::Bin::with_name(name);
+    let appsrc = gst::ElementFactory::make("appsrc")
+        .property("caps", caps.to_owned())
+        .property_from_str("format", "time")
+        .build()?;
+    let freeze = gst::ElementFactory::make("imagefreeze").property("is-live", live).build()?;
+    bin.add_many(&[&appsrc, &freeze])?;
+    appsrc.link(&freeze)?;
+
+    let vpad = gst::GhostPad::with_target(&freeze.static_pad("src").unwrap())?;
+    vpad.set_property("name", "video_src");
+    bin.add_pad(&vpad)?;
+
+    // imagefreeze repeats the single buffer it receives until it is removed.
+    let mut still = buffer.copy();
+    {
+        let still = still.make_mut();
+        still.set_pts(gst::ClockTime::ZERO);
+        still.set_dts(gst::ClockTime::NONE);
+        still.set_duration(gst::ClockTime::NONE);
+    }
+    let flow = appsrc.emit_by_name::<gst::FlowReturn>("push-buffer", &[&still]);
+    if flow != gst::FlowReturn::Ok {
+        return Err(anyhow!("Could not queue the frozen frame: {:?}", flow));
+    }
+    appsrc.emit_by_name::<gst::FlowReturn>("end-of-stream", &[]);
+    Ok(bin)
+}
+
+impl Freeze {
+    /// Puts a still of `frame` on the video selector. Standby, if on air, stays on air.
+    pub(crate) fn enter(pipeline: &gst::Pipeline, vs: &gst::Element, frame: &gst::Sample) -> Result<Self> {
+        info!(target: LOG_PLAYBACK, "Freezing video.");
+        let bin = create_still_bin(FREEZE_BIN_NAME, frame, true)?;
+        pipeline.add(&bin)?;
+
+        let video_pad = link_to_selector(&bin.static_pad("video_src").unwrap(), vs)?;
+        bin.sync_state_with_parent()?;
+
+        if !standby::is_on_air(vs) {
+            vs.set_property("active-pad", &video_pad);
+        }
+        Ok(Self { bin, video_pad })
+    }
+
+    /// Returns the video selector to the program and removes the still.
+    pub(crate) fn leave(self, pipeline: &gst::Pipeline, vs: &gst::Element) {
+        info!(target: LOG_PLAYBACK, "Unfreezing video.");
+        let active = vs.property::<Option<gst::Pad>>("active-pad");
+        if active.as_ref() == Some(&self.video_pad) {
+            standby::restore_active_pad(vs, &self.video_pad);
+        }
+
+        let _ = self.bin.set_state(gst::State::Null);
+        vs.release_request_pad(&self.video_pad);
+        let _ = pipeline.remove(&self.bin);
+    }
+}
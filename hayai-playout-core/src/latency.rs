@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// One knob for the latency/stability trade-off of the output chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LatencyProfile {
+    /// Minimal buffering and no encoder lookahead; first to stutter on a busy machine.
+    UltraLowLatency,
+    #[default]
+    Balanced,
+    /// Generous buffering; adds a few seconds of delay.
+    Stable,
+}
+
+/// The concrete element settings a profile stands for. Pacing the output is not part of it;
+/// see [`EncodingSettings::live_sync`](crate::EncodingSettings::live_sync).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LatencyParams {
+    /// `max-size-time` of the queues in front of the encoders.
+    pub queue_max_time: Duration,
+    /// `latency` of the muxer (extra time it waits for all inputs).
+    pub mux_latency: Duration,
+    /// `tune` for encoders that support it; `None` leaves the encoder default.
+    pub encoder_tune: Option<&'static str>,
+}
+
+impl LatencyProfile {
+    pub fn params(self) -> LatencyParams {
+        match self {
+            LatencyProfile::UltraLowLatency => LatencyParams {
+                queue_max_time: Duration::from_millis(200),
+                mux_latency: Duration::ZERO,
+                encoder_tune: Some("zerolatency"),
+            },
+            LatencyProfile::Balanced => LatencyParams {
+                queue_max_time: Duration::from_secs(1),
+                mux_latency: Duration::from_millis(100),
+                encoder_tune: Some("zerolatency"),
+            },
+            LatencyProfile::Stable => LatencyParams {
+                queue_max_time: Duration::from_secs(3),
+                mux_latency: Duration::from_millis(500),
+                encoder_tune: None,
+            },
+        }
+    }
+}
@@ -1,64 +1,380 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use gstreamer as gst;
 use gst::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU64, Ordering};
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+mod analysis;
+mod blanks;
+mod bus_thread;
+mod decode;
+mod directory;
+mod dissolve;
+mod dryrun;
+mod encoders;
+mod error;
+mod events;
+mod fade;
+mod fill;
+mod freeze;
+mod latency;
+mod logo;
+mod meter;
+mod models;
+mod outputs;
+mod pipeline;
+mod preload;
+mod preview;
+mod probe;
+mod profile;
+mod qos;
+mod reconnect;
+mod seek;
+mod selector;
+mod selftest;
+mod sequencer;
+mod sort;
+mod standby;
+mod stats;
+mod stream_url;
+mod teardown;
+mod text_overlay;
+mod transition;
+#[cfg(feature = "webrtc")]
+mod webrtc;
+
+pub use blanks::{mean_luma, peak_dbfs, BlankThresholds, LeadingBlankDetector};
+pub use decode::create_source;
+pub use directory::{is_media_file, MEDIA_EXTENSIONS};
+pub use dissolve::TransitionStyle;
+pub use encoders::{find_encoder, list_encoders, probe_encoder, EncoderInfo, EncoderKind};
+pub use error::{user_message, StreamerError};
+pub use events::{EventHistory, PlayoutEvent};
+pub use fill::VideoFill;
+pub use latency::{LatencyParams, LatencyProfile};
+pub use logo::Corner;
+pub use meter::{audio_levels, LEVEL_FLOOR_DB};
+pub use models::{EncodingSettings, ItemKind, PlaylistItem};
+pub use pipeline::{
+    create_processing_bin, OutputTarget, AUDIO_ENCODER_NAME, AUDIO_LEVEL_NAME, AUDIO_SINK_PAD, AUDIO_TEE_NAME,
+    CLOCK_OVERLAY_NAME, FRAME_RATE_NAME, HLS_PLAYLIST_NAME, LOGO_OVERLAY_NAME, MUX_NAME, OUTPUT_SINK_NAME,
+    PREVIEW_TEE_NAME, PROCESSING_BIN_NAME, RECORD_MUX_NAME, RECORD_SINK_NAME, TEXT_OVERLAY_NAME, VIDEO_ENCODER_NAME,
+    VIDEO_SINK_PAD, VIDEO_TEE_NAME, VOLUME_NAME,
+};
+pub use probe::{AudioStreamInfo, MediaInfo, VideoStreamInfo};
+pub use profile::EncoderProfile;
+pub use qos::QosMonitor;
+pub use reconnect::{GiveUpPolicy, OutputState, ReconnectAction, ReconnectMonitor, ReconnectPolicy};
+pub use selector::{active_source_for, link_source_to_selectors, release_source_pads};
+pub use selftest::SelfTestReport;
+pub use sequencer::{
+    compute_next_index, compute_next_index_with_tags, compute_previous_index, InterstitialConfig, NextEntry, RepeatMode,
+    Sequencer,
+};
+pub use sort::{natural_cmp, SortOrder};
+pub use stats::{bitrate_between, StreamStats};
+pub use stream_url::{join_stream_key, redact_url, redact_urls_in, title_from_uri};
+pub use teardown::{TeardownStep, TEARDOWN_ORDER};
+pub use text_overlay::{TextOverlayConfig, TextPosition};
+pub use transition::TransitionGuard;
+#[cfg(feature = "webrtc")]
+pub use webrtc::{build_webrtc_bin, payloaders_for, WebRtcSignaling};
+
+use bus_thread::BusThread;
+use dissolve::Dissolver;
+use events::EventBus;
+use fade::Fader;
+use fill::FillPicture;
+use freeze::Freeze;
+use logo::Logo;
+use pipeline::{create_output, resolve_encoder};
+use preload::Blocker;
+use standby::Standby;
+use stats::StatsCounter;
 
 static NEXT_ID: AtomicU64 = AtomicU64::new(1);
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct PlaylistItem { 
-    pub id: u64, 
-    pub uri: String 
+const SOURCE_NAME_PREFIX: &str = "source_elem_";
+// `log` targets, one per area, so applications can pick what they want to see.
+const LOG_PLAYBACK: &str = "hayai::playback";
+const LOG_SOURCE: &str = "hayai::source";
+const LOG_OUTPUT: &str = "hayai::output";
+const LOG_GSTREAMER: &str = "hayai::gstreamer";
+const DEFAULT_MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// What [`Streamer::start`] actually set up, which may differ from what was asked for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StartReport {
+    /// Where the stream goes, with the stream key masked.
+    pub output_url: String,
+    /// Factory name of the video encoder in use.
+    pub encoder_used: String,
+    /// Whether the requested encoder was unavailable and a fallback was substituted.
+    pub fallback_applied: bool,
+    /// Factory name of the output sink in use.
+    pub sink_element: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct EncodingSettings {
-    pub video_encoder: String,
-    pub audio_encoder: String,
-    pub bitrate_kbps: u32,
-    pub speed_preset: String,
-    pub scale_enabled: bool,
-    pub scale_width: u32,
-    pub scale_height: u32,
+/// Per-channel options that shape how sources are played, as opposed to how they are encoded.
+#[derive(Clone, Debug)]
+struct PlayoutOptions {
+    trim_leading_blanks: Option<BlankThresholds>,
+    /// How long before the end of an item the next one is built; `None` waits for the end.
+    preload_lead: Option<Duration>,
+    /// Audio fade out of and into each item; zero cuts straight over.
+    audio_fade: Duration,
+    transition_style: TransitionStyle,
+    /// Played in place of the picture of items without video.
+    video_fill: FillPicture,
+    /// Looped while nothing in the playlist can be played; `None` stops the stream instead.
+    fallback_uri: Option<String>,
+    /// How many items in a row may fail before the stream gives up on the playlist.
+    max_consecutive_failures: u32,
 }
 
-impl Default for EncodingSettings {
+impl Default for PlayoutOptions {
     fn default() -> Self {
         Self {
-            video_encoder: "x264enc".to_string(),
-            audio_encoder: "faac".to_string(),
-            bitrate_kbps: 4000,
-            speed_preset: "ultrafast".to_string(),
-            scale_enabled: false,
-            scale_width: 1920,
-            scale_height: 1080,
+            trim_leading_blanks: None,
+            preload_lead: None,
+            audio_fade: Duration::ZERO,
+            transition_style: TransitionStyle::default(),
+            video_fill: FillPicture::default(),
+            fallback_uri: None,
+            max_consecutive_failures: DEFAULT_MAX_CONSECUTIVE_FAILURES,
         }
     }
 }
 
+/// Shared handles the transition code needs, cloned into the bus thread.
+#[derive(Clone)]
+struct PlayoutState {
+    playlist: Arc<Mutex<Vec<PlaylistItem>>>,
+    currently_playing_id: Arc<Mutex<Option<u64>>>,
+    sequencer: Arc<Mutex<Sequencer>>,
+    options: Arc<Mutex<PlayoutOptions>>,
+    events: Arc<EventBus>,
+    on_air: Arc<Mutex<Option<OnAir>>>,
+    transitions: Arc<Mutex<TransitionGuard>>,
+    /// Name of the fallback source, while it is on air.
+    fallback: Arc<Mutex<Option<String>>>,
+}
+
+/// The source of the entry on air, and when to cut away from it without waiting for EOS.
+#[derive(Clone, Debug)]
+struct OnAir {
+    /// Name of the source element.
+    source: String,
+    /// Id of the playlist item, or of the interstitial.
+    id: u64,
+    uri: String,
+    /// The title from its tags, once read.
+    title: Option<String>,
+    /// Its [`TransitionGuard`] generation.
+    generation: u64,
+    /// The item's set duration, if any.
+    cut_after: Option<Duration>,
+    /// Pipeline running time when it went on air; taken on the first check if the clock
+    /// wasn't running yet.
+    started: Option<gst::ClockTime>,
+    /// Fades its audio, if audio fades are on.
+    fader: Option<Fader>,
+}
+
 pub struct Streamer {
     pipeline: Option<gst::Pipeline>,
     playlist: Arc<Mutex<Vec<PlaylistItem>>>,
     currently_playing_id: Arc<Mutex<Option<u64>>>,
+    sequencer: Arc<Mutex<Sequencer>>,
+    options: Arc<Mutex<PlayoutOptions>>,
+    reconnect: Arc<Mutex<ReconnectMonitor>>,
+    events: Arc<EventBus>,
+    last_frame: Arc<Mutex<Option<gst::Sample>>>,
+    freeze: Mutex<Option<Freeze>>,
+    logo: Mutex<Option<Logo>>,
+    text_overlay: Arc<Mutex<TextOverlayConfig>>,
+    volume: Mutex<f64>,
+    on_air: Arc<Mutex<Option<OnAir>>>,
+    transitions: Arc<Mutex<TransitionGuard>>,
+    fallback: Arc<Mutex<Option<String>>>,
+    /// Counts what the running stream sends.
+    stats: Mutex<Option<Arc<StatsCounter>>>,
+    /// Destinations added with [`Streamer::add_output`], with the branch sending to each.
+    extra_outputs: Arc<Mutex<Vec<(OutputTarget, String)>>>,
+    /// Where the program is previewed, kept from one stream to the next.
+    preview_sink: Mutex<Option<gst::Element>>,
+    /// Handles the bus of the running stream.
+    bus_thread: Option<BusThread>,
 }
 
 impl Streamer {
+    /// Highest master volume, as a linear factor: four times, about +12 dB.
+    pub const MAX_VOLUME: f64 = 4.0;
+
     pub fn new() -> Result<Self> {
         gst::init()?;
         Ok(Self {
             pipeline: None,
             playlist: Arc::new(Mutex::new(Vec::new())),
             currently_playing_id: Arc::new(Mutex::new(None)),
+            sequencer: Arc::new(Mutex::new(Sequencer::new())),
+            options: Arc::new(Mutex::new(PlayoutOptions::default())),
+            reconnect: Arc::new(Mutex::new(ReconnectMonitor::default())),
+            events: Arc::new(EventBus::default()),
+            last_frame: Arc::new(Mutex::new(None)),
+            freeze: Mutex::new(None),
+            logo: Mutex::new(None),
+            text_overlay: Arc::new(Mutex::new(TextOverlayConfig::default())),
+            volume: Mutex::new(1.0),
+            on_air: Arc::new(Mutex::new(None)),
+            transitions: Arc::new(Mutex::new(TransitionGuard::new())),
+            fallback: Arc::new(Mutex::new(None)),
+            stats: Mutex::new(None),
+            extra_outputs: Arc::new(Mutex::new(Vec::new())),
+            preview_sink: Mutex::new(None),
+            bus_thread: None,
         })
     }
 
-    pub fn start(&mut self, rtmp_url: &str, settings: &EncodingSettings) -> Result<()> {
-        if self.pipeline.is_some() { 
-            return Err(anyhow!("Stream is already running")); 
+    /// Starts streaming the playlist to `target`: an [`OutputTarget`], or a URL whose scheme
+    /// picks the protocol.
+    pub fn start(&mut self, target: impl Into<OutputTarget>, settings: &EncodingSettings) -> Result<StartReport> {
+        self.ensure_stopped()?;
+        let (processing_bin, report) = create_output(&target.into(), settings)?;
+        self.options.lock().unwrap().audio_fade = Duration::from_millis(settings.audio_fade_ms.into());
+        self.launch(processing_bin, None)?;
+        Ok(report)
+    }
+
+    /// Streams `uri` to `target` on a seamless loop, leaving the playlist alone: a holding
+    /// slate or "starting soon" clip for before the show.
+    ///
+    /// The same source keeps playing and is sought back to its start at the end instead of
+    /// being replaced, so there is no gap and audio and video stay in sync across the loop.
+    /// [`Streamer::stop`] ends it like any other stream.
+    pub fn start_standby(&mut self, target: impl Into<OutputTarget>, settings: &EncodingSettings, uri: &str) -> Result<StartReport> {
+        self.ensure_stopped()?;
+        let (processing_bin, report) = create_output(&target.into(), settings)?;
+        self.launch(processing_bin, Some(uri))?;
+        Ok(report)
+    }
+
+    /// Checks that `url` can be streamed to: a supported scheme, and the muxer and sink it
+    /// needs are installed (for `rtmps://`, one that does TLS). Doesn't contact the server.
+    pub fn validate_output(url: &str) -> Result<()> {
+        gst::init()?;
+        pipeline::check_output(&OutputTarget::from(url))?;
+        Ok(())
+    }
+
+    /// Reads the streams and duration of `uri` without playing it, to find broken files
+    /// before going live.
+    pub fn probe_item(uri: &str) -> Result<MediaInfo> {
+        probe::discover(uri)
+    }
+
+    /// [`Streamer::probe_item`] for every playlist item, in playlist order. Also records each
+    /// item's media length for [`Streamer::total_duration`] and [`Streamer::time_remaining`].
+    pub fn probe_all(&self) -> Vec<(PlaylistItem, Result<MediaInfo>)> {
+        let playlist = self.get_playlist_clone();
+        let results: Vec<_> = playlist.into_iter()
+            .map(|mut item| {
+                let info = Self::probe_item(&item.uri);
+                if let Ok(info) = &info {
+                    item.media_duration = info.duration.map(|d| Duration::from_nanos(d.nseconds()));
+                }
+                (item, info)
+            })
+            .collect();
+        // Probing takes a while; the playlist may have changed meanwhile, so match by id.
+        let mut playlist = self.playlist.lock().unwrap();
+        for (probed, _) in results.iter().filter(|(_, info)| info.is_ok()) {
+            if let Some(item) = playlist.iter_mut().find(|item| item.id == probed.id) {
+                item.media_duration = probed.media_duration;
+            }
+        }
+        drop(playlist);
+        results
+    }
+
+    /// How long one pass through the enabled playlist items takes, or `None` if any of them
+    /// has an unknown length (see [`PlaylistItem::play_length`]).
+    pub fn total_duration(&self) -> Option<gst::ClockTime> {
+        let playlist = self.playlist.lock().unwrap();
+        sum_play_lengths(playlist.iter().filter(|item| item.enabled))
+    }
+
+    /// How long until the end of the playlist: what is left of the entry on air plus the
+    /// enabled items after it, ignoring repeat. `None` if nothing is playing or a length
+    /// isn't known.
+    pub fn time_remaining(&self) -> Option<gst::ClockTime> {
+        let pipeline = self.pipeline.as_ref()?;
+        let on_air = self.on_air.lock().unwrap().clone()?;
+        let current_left = time_left(pipeline, &on_air)?;
+        let playlist = self.playlist.lock().unwrap();
+        let playing_id = (*self.currently_playing_id.lock().unwrap())?;
+        let after = playlist.iter().position(|item| item.id == playing_id).map_or(0, |index| index + 1);
+        let rest = sum_play_lengths(playlist[after..].iter().filter(|item| item.enabled))?;
+        Some(current_left + rest)
+    }
+
+    /// Like [`Streamer::start`], with the stream key given separately from the ingest URL.
+    pub fn start_with_key(&mut self, base_url: &str, stream_key: &str, settings: &EncodingSettings) -> Result<StartReport> {
+        self.start(join_stream_key(base_url, stream_key), settings)
+    }
+
+    /// Streams to a single WebRTC peer instead of RTMP. Experimental.
+    ///
+    /// The offer and local ICE candidates are handed to `signaling`; the peer's answer and
+    /// candidates go back in through [`Streamer::set_webrtc_answer`] and
+    /// [`Streamer::add_webrtc_ice_candidate`]. Needs an H.264/VP8/VP9 video encoder and Opus audio.
+    #[cfg(feature = "webrtc")]
+    pub fn start_webrtc(&mut self, settings: &EncodingSettings, signaling: WebRtcSignaling) -> Result<StartReport> {
+        self.ensure_stopped()?;
+        let (effective, fallback_applied) = resolve_encoder(settings);
+        effective.validate()?;
+        let processing_bin = build_webrtc_bin(&effective, signaling)?;
+        self.options.lock().unwrap().audio_fade = Duration::from_millis(settings.audio_fade_ms.into());
+        self.launch(processing_bin, None)?;
+        Ok(StartReport {
+            output_url: "webrtc".to_string(),
+            encoder_used: effective.video_encoder,
+            fallback_applied,
+            sink_element: "webrtcbin".to_string(),
+        })
+    }
+
+    #[cfg(feature = "webrtc")]
+    pub fn set_webrtc_answer(&self, sdp: &str) -> Result<()> {
+        let pipeline = self.pipeline.as_ref().ok_or(StreamerError::NotRunning)?;
+        webrtc::set_remote_answer(pipeline, sdp)
+    }
+
+    #[cfg(feature = "webrtc")]
+    pub fn add_webrtc_ice_candidate(&self, mline_index: u32, candidate: &str) -> Result<()> {
+        let pipeline = self.pipeline.as_ref().ok_or(StreamerError::NotRunning)?;
+        webrtc::add_ice_candidate(pipeline, mline_index, candidate)
+    }
+
+    fn ensure_stopped(&mut self) -> Result<()> {
+        // A pipeline left in NULL was shut down from the bus thread (reconnection gave up).
+        if self.is_running() {
+            return Err(StreamerError::AlreadyRunning.into());
+        }
+        if self.pipeline.is_some() {
+            self.stop()?;
         }
+        Ok(())
+    }
 
+    /// Puts the selectors in front of `processing_bin` and starts playing the playlist, or
+    /// `loop_uri` on repeat if given.
+    fn launch(&mut self, processing_bin: gst::Bin, loop_uri: Option<&str>) -> Result<()> {
         let pipeline = gst::Pipeline::new();
         
         // Create selectors for switching between sources
@@ -68,55 +384,194 @@ impl Streamer {
         let audio_selector = gst::ElementFactory::make("input-selector")
             .name("audio_selector")
             .build()?;
-            
-        // Create processing bin
-        let processing_bin = create_processing_bin(rtmp_url, settings)?;
         
         // Add elements to pipeline
         pipeline.add_many(&[&video_selector, &audio_selector, processing_bin.upcast_ref()])?;
+        if let Some(volume) = processing_bin.by_name(VOLUME_NAME) {
+            volume.set_property("volume", *self.volume.lock().unwrap());
+        }
+        if let Some(logo) = self.logo.lock().unwrap().as_ref() {
+            match processing_bin.by_name(LOGO_OVERLAY_NAME) {
+                Some(overlay) => logo::apply(&overlay, Some(logo))?,
+                None => warn!(target: LOG_OUTPUT, "gdkpixbufoverlay is not installed, streaming without the logo."),
+            }
+        }
+        {
+            let config = self.text_overlay.lock().unwrap();
+            match (processing_bin.by_name(TEXT_OVERLAY_NAME), processing_bin.by_name(CLOCK_OVERLAY_NAME)) {
+                (Some(text), Some(clock)) => {
+                    text_overlay::apply_text(&text, &config);
+                    text_overlay::apply_clock(&clock, &config);
+                    text_overlay::attach_ticker(&text, self.text_overlay.clone());
+                }
+                _ if config.text.is_some() || config.clock.is_some() => {
+                    warn!(target: LOG_OUTPUT, "textoverlay is not installed, streaming without the text overlay.");
+                }
+                _ => (),
+            }
+        }
+        if let Some(sink) = self.preview_sink.lock().unwrap().as_ref() {
+            if let Err(e) = preview::attach(&processing_bin, sink) {
+                warn!(target: LOG_OUTPUT, "Streaming without the preview: {:#}", e);
+            }
+        }
         
-        // Link selectors to processing bin
-        video_selector.link_pads(Some("src"), &processing_bin, Some("video_sink"))?;
-        audio_selector.link_pads(Some("src"), &processing_bin, Some("audio_sink"))?;
+        // Link selectors to processing bin, through the dissolve mixer if dissolves are on
+        let mut dissolver = match self.options.lock().unwrap().transition_style {
+            TransitionStyle::Cut => {
+                video_selector.link_pads(Some("src"), &processing_bin, Some(VIDEO_SINK_PAD))?;
+                None
+            }
+            TransitionStyle::Dissolve { .. } => Some(Dissolver::insert(
+                &pipeline, &video_selector, processing_bin.upcast_ref(), VIDEO_SINK_PAD, self.last_frame.clone(),
+            )?),
+        };
+        audio_selector.link_pads(Some("src"), &processing_bin, Some(AUDIO_SINK_PAD))?;
+
+        // Keep the newest program frame around so the video can be frozen on it.
+        let last_frame = self.last_frame.clone();
+        video_selector.static_pad("src").unwrap().add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+            if let Some(gst::PadProbeData::Buffer(buffer)) = &info.data {
+                if let Some(caps) = pad.current_caps() {
+                    *last_frame.lock().unwrap() = Some(gst::Sample::builder().buffer(buffer).caps(&caps).build());
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
         
         let bus = pipeline.bus().unwrap();
         let weak_pipeline = pipeline.downgrade();
-        let playlist_clone = self.playlist.clone();
-        let playing_id_clone = self.currently_playing_id.clone();
+        let state = self.playout_state();
+        let reconnect_clone = self.reconnect.clone();
+        let events_clone = self.events.clone();
+        let extra_outputs = self.extra_outputs.clone();
+        let stats = Arc::new(StatsCounter::new());
+        if let Some(sink) = processing_bin.by_name(OUTPUT_SINK_NAME) {
+            stats.attach(&sink);
+        }
+        *self.stats.lock().unwrap() = Some(stats.clone());
 
         // Start a background thread to handle bus messages
         let bus_clone = bus.clone();
         let weak_pipeline_clone = weak_pipeline.clone();
-        let playlist_clone2 = playlist_clone.clone();
-        let playing_id_clone2 = playing_id_clone.clone();
         
-        std::thread::spawn(move || {
+        let bus_thread = BusThread::spawn(move |stop| {
+            // Only this thread switches standby in and out, so it owns the standby source.
+            let mut standby: Option<Standby> = None;
+            // The entry decided on ahead of time, and the source it was last checked for.
+            let mut preloaded: Option<Preloaded> = None;
+            let mut preload_checked: Option<String> = None;
+            let mut early_titles: HashMap<String, String> = HashMap::new();
+            // Items that failed since one last played through.
+            let mut failures: u32 = 0;
+            let mut qos = QosMonitor::default();
+            // When to restart a dropped output. Kept here rather than on a timer thread, so
+            // stopping the stream also drops a pending retry.
+            let mut retry_at: Option<Instant> = None;
             loop {
-                if let Some(msg) = bus_clone.timed_pop(gst::ClockTime::from_mseconds(100)) {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let msg = bus_clone.timed_pop(gst::ClockTime::from_mseconds(100));
+                stats.sample();
+                if let Some(msg) = msg {
                     if let Some(p) = weak_pipeline_clone.upgrade() {
                         match msg.view() {
                             gst::MessageView::Error(err) => {
-                                eprintln!("[GStreamer Error] from {:?}: {}", 
-                                        err.src().map(|s| s.path_string()), err.error());
+                                error!(target: LOG_GSTREAMER, "GStreamer error from {:?}: {}", 
+                                        err.src().map(|s| s.path_string()), redact_urls_in(&err.error().to_string()));
+                                if err.src().map_or(false, |s| s.name() == OUTPUT_SINK_NAME) {
+                                    let (actions, delay) = {
+                                        let mut monitor = reconnect_clone.lock().unwrap();
+                                        let actions = monitor.on_sink_error();
+                                        (actions, monitor.retry_delay())
+                                    };
+                                    if !apply_reconnect_actions(&p, &actions, delay, &mut retry_at, &mut standby, &events_clone) {
+                                        break;
+                                    }
+                                } else if let Some(branch) = err.src().and_then(outputs::branch_containing) {
+                                    let error = redact_urls_in(&err.error().to_string());
+                                    drop_failed_output(&p, &branch, &error, &extra_outputs, &events_clone);
+                                } else if let Some(source) = err.src().and_then(source_containing) {
+                                    let error = redact_urls_in(&err.error().to_string());
+                                    if !on_source_error(&p, &state, &source, &error, &mut failures, &mut preloaded, &mut dissolver) {
+                                        break;
+                                    }
+                                }
                             }
                             gst::MessageView::Application(app_msg) => {
-                                if app_msg.structure().map_or(false, |s| s.name() == "hayai-playlist-eos") {
-                                    println!("[hayai] Received EOS signal, switching to next source.");
-                                    let old_src_name = app_msg.structure().unwrap()
-                                        .get::<String>("source-name").unwrap();
-                                    let old_src = p.by_name(&old_src_name);
-                                    
-                                    // Get the selectors
-                                    let vs = p.by_name("video_selector").unwrap();
-                                    let as_ = p.by_name("audio_selector").unwrap();
-                                    
-                                    if let Err(e) = play_next(&p, &vs, &as_, &playlist_clone2, &playing_id_clone2, old_src) {
-                                        eprintln!("[hayai] Failed to play next: {}", e);
+                                if app_msg.structure().map_or(false, |s| s.name() == "hayai-output-flowing") {
+                                    let actions = reconnect_clone.lock().unwrap().on_sink_recovered();
+                                    apply_reconnect_actions(&p, &actions, Duration::ZERO, &mut retry_at, &mut standby, &events_clone);
+                                } else if let Some(eos) = app_msg.structure().filter(|s| s.name() == "hayai-playlist-eos") {
+                                    let old_src_name = match eos.get::<String>("source-name") {
+                                        Ok(name) => name,
+                                        Err(e) => {
+                                            warn!(target: LOG_PLAYBACK, "Ignoring an end of item that names no source: {}", e);
+                                            continue;
+                                        }
+                                    };
+                                    // Each pad of a source reports EOS, and a source cut over by
+                                    // its duration may still end afterwards; only the first counts.
+                                    let generation = match on_air_generation(&state, &old_src_name) {
+                                        Some(generation) => generation,
+                                        None => {
+                                            debug!(target: LOG_PLAYBACK, "Ignoring EOS of {}, it is no longer on air.", old_src_name);
+                                            continue;
+                                        }
+                                    };
+                                    info!(target: LOG_PLAYBACK, "Received EOS signal, switching to next source.");
+                                    played_through(&state, &mut failures);
+                                    advance(&p, &state, generation, None, &mut preloaded, &mut dissolver);
+                                    if p.current_state() == gst::State::Null {
+                                        break;
+                                    }
+                                } else if let Some(skip) = app_msg.structure().filter(|s| s.name() == "hayai-skip") {
+                                    let generation = match skip.get::<u64>("generation") {
+                                        Ok(generation) => generation,
+                                        Err(e) => {
+                                            warn!(target: LOG_PLAYBACK, "Ignoring a skip request without a generation: {}", e);
+                                            continue;
+                                        }
+                                    };
+                                    let target = match skip_target(&state, skip) {
+                                        Ok(target) => target,
+                                        Err(e) => {
+                                            warn!(target: LOG_PLAYBACK, "Cannot skip: {}", e);
+                                            continue;
+                                        }
+                                    };
+                                    match &target {
+                                        Some(upcoming) => info!(target: LOG_PLAYBACK, "Skipping to {}", redact_url(&upcoming.item.uri)),
+                                        None => info!(target: LOG_PLAYBACK, "Skipping to the next item."),
                                     }
+                                    advance(&p, &state, generation, target, &mut preloaded, &mut dissolver);
+                                    if p.current_state() == gst::State::Null {
+                                        break;
+                                    }
+                                } else if let Some(tags) = app_msg.structure().filter(|s| s.name() == "hayai-title") {
+                                    if let (Ok(source), Ok(title)) = (tags.get::<&str>("source-name"), tags.get::<&str>("title")) {
+                                        record_title(&state, source, title, &mut early_titles);
+                                    }
+                                }
+                            }
+                            gst::MessageView::Qos(report) => {
+                                let (processed, dropped) = report.stats();
+                                if let (Some(src), Ok(processed), Ok(dropped)) =
+                                    (report.src(), u64::try_from(processed.value()), u64::try_from(dropped.value()))
+                                {
+                                    stats.record_dropped(&src.path_string(), dropped);
+                                    qos.record(&src.path_string(), processed, dropped, Instant::now());
+                                }
+                            }
+                            gst::MessageView::Element(msg) => {
+                                let from_meter = msg.src().map_or(false, |src| src.name() == AUDIO_LEVEL_NAME);
+                                if let Some(event) = msg.structure().filter(|_| from_meter).and_then(audio_levels) {
+                                    events_clone.emit(event);
                                 }
                             }
                             gst::MessageView::Eos(_) => {
-                                println!("[hayai] Pipeline EOS received");
+                                info!(target: LOG_PLAYBACK, "Pipeline EOS received");
                                 break;
                             }
                             _ => (),
@@ -131,254 +586,1815 @@ impl Streamer {
                         break;
                     }
                 }
+                if let Some((dropped, processed)) = qos.poll(Instant::now()) {
+                    warn!(target: LOG_OUTPUT, "Dropped {} of {} frames under load; the encoder may be too slow.", dropped, processed + dropped);
+                    events_clone.emit(PlayoutEvent::QosWarning { dropped, processed });
+                }
+                if let Some(p) = weak_pipeline_clone.upgrade() {
+                    if retry_at.map_or(false, |at| Instant::now() >= at) {
+                        retry_at = None;
+                        restart_output_sink(&p);
+                    }
+                    if let Some(generation) = cutover_due(&p, &state) {
+                        info!(target: LOG_PLAYBACK, "Item reached its set duration, cutting over.");
+                        played_through(&state, &mut failures);
+                        advance(&p, &state, generation, None, &mut preloaded, &mut dissolver);
+                        if p.current_state() == gst::State::Null {
+                            break;
+                        }
+                    }
+                    fade_out_if_due(&p, &state);
+                    leave_fallback_if_ready(&p, &state);
+                    if !early_titles.is_empty() {
+                        claim_early_title(&p, &state, &mut early_titles);
+                    }
+                    if let Some(dissolver) = dissolver.as_mut() {
+                        dissolver.finish_if_done(&p);
+                    }
+                    if preloaded.is_none() {
+                        preloaded = preload_if_due(&p, &state, &mut preload_checked);
+                    }
+                }
             }
-        });
+        })?;
         
         // Start the first item
         let vs = pipeline.by_name("video_selector").unwrap();
         let as_ = pipeline.by_name("audio_selector").unwrap();
         
-        if let Err(e) = play_next(&pipeline, &vs, &as_, &self.playlist, &self.currently_playing_id, None) {
-            return Err(anyhow!("Failed to prepare first item: {}", e));
+        let first = match loop_uri {
+            Some(uri) => {
+                let item = PlaylistItem::new(NEXT_ID.fetch_add(1, Ordering::SeqCst), uri);
+                let setup = SourceSetup::new(SourceStart::Loop);
+                switch_source(&pipeline, &vs, &as_, &item, &setup, &self.events, None).map(|_| ())
+            }
+            None => play_next(&pipeline, &vs, &as_, &self.playout_state(), None),
+        };
+        if let Err(e) = first {
+            bus_thread.stop();
+            return Err(e.context("Failed to prepare first item"));
         }
         
-        pipeline.set_state(gst::State::Playing)?;
+        if let Err(e) = pipeline.set_state(gst::State::Playing) {
+            bus_thread.stop();
+            return Err(e.into());
+        }
         self.pipeline = Some(pipeline);
+        self.bus_thread = Some(bus_thread);
         Ok(())
     }
 
+    /// Whether a stream is live. Also `false` once the pipeline has shut itself down, e.g.
+    /// after reconnecting gave up or the playlist ended.
+    pub fn is_running(&self) -> bool {
+        self.pipeline_state().map_or(false, |state| state != gst::State::Null)
+    }
+
+    /// Current state of the pipeline, or `None` if no stream has been started since the last stop.
+    pub fn pipeline_state(&self) -> Option<gst::State> {
+        self.pipeline.as_ref().map(|p| p.current_state())
+    }
+
     pub fn stop(&mut self) -> Result<()> {
+        self.freeze.lock().unwrap().take();
+        *self.last_frame.lock().unwrap() = None;
+        // Before the pipeline goes, so the thread is not halfway through switching items.
+        if let Some(bus_thread) = self.bus_thread.take() {
+            bus_thread.stop();
+        }
         if let Some(pipeline) = self.pipeline.take() { 
-            pipeline.set_state(gst::State::Null)?; 
+            teardown::run(&pipeline)?;
         }
         *self.currently_playing_id.lock().unwrap() = None;
+        *self.on_air.lock().unwrap() = None;
+        *self.fallback.lock().unwrap() = None;
+        *self.stats.lock().unwrap() = None;
+        self.extra_outputs.lock().unwrap().clear();
+        self.sequencer.lock().unwrap().reset();
+        self.reconnect.lock().unwrap().reset();
+        Ok(())
+    }
+
+    /// Pauses the whole pipeline, so the item on air, its set duration and the output all
+    /// stop where they are; [`Streamer::resume`] carries on from the same frame.
+    ///
+    /// A recording just has nothing added while paused. A network output keeps its connection
+    /// but gets no data, which most ingest servers only tolerate for a few seconds before
+    /// hanging up; for longer breaks on air, [`Streamer::freeze_video`] or a held slate keeps
+    /// the stream flowing instead.
+    pub fn pause(&mut self) -> Result<()> {
+        self.set_live_state(gst::State::Paused)
+    }
+
+    /// Picks up a stream paused with [`Streamer::pause`].
+    pub fn resume(&mut self) -> Result<()> {
+        self.set_live_state(gst::State::Playing)
+    }
+
+    fn set_live_state(&self, state: gst::State) -> Result<()> {
+        if !self.is_running() {
+            return Err(StreamerError::NotRunning.into());
+        }
+        let pipeline = self.pipeline.as_ref().ok_or(StreamerError::NotRunning)?;
+        pipeline.set_state(state)
+            .with_context(|| format!("Could not set the stream to {:?}", state))?;
         Ok(())
     }
+
+    /// Skips leading black/silence at the start of each item, found by a short analysis pass
+    /// before the item goes on air. Disabled with `None` (the default).
+    pub fn set_trim_leading_blanks(&self, thresholds: Option<BlankThresholds>) {
+        self.options.lock().unwrap().trim_leading_blanks = thresholds;
+    }
+
+    /// Builds the next item `lead` before the current one ends and switches to it the moment
+    /// the current one finishes, without a gap. `None` (the default) builds the next item only
+    /// once the current one has ended. The lead must cover opening and prerolling a file;
+    /// a few seconds is plenty for local media.
+    pub fn set_preload(&self, lead: Option<Duration>) {
+        self.options.lock().unwrap().preload_lead = lead;
+    }
+
+    /// How the picture changes between entries. [`TransitionStyle::Cut`] (the default) switches
+    /// straight over; a dissolve adds a mixer to the video path, which happens when the stream
+    /// starts, so turning dissolves on only takes effect from the next start.
+    pub fn set_transition_style(&self, style: TransitionStyle) {
+        self.options.lock().unwrap().transition_style = style;
+    }
+
+    /// Encodes a few seconds of test signal through the configured encoder chain, as fast as
+    /// possible, to check whether `settings` can keep up in realtime on this machine.
+    pub fn self_test(settings: &EncodingSettings) -> Result<SelfTestReport> {
+        gst::init()?;
+        selftest::run(settings)
+    }
+
+    /// Checks that the stream would start, without going on air: builds the encoders for
+    /// `settings` and prerolls the first enabled item through them into a sink that throws it
+    /// all away, then tears everything down. Catches missing plugins, files that won't decode
+    /// and settings the encoders refuse. Nothing is sent anywhere; see
+    /// [`Streamer::validate_output`] for the output.
+    pub fn dry_run(&self, settings: &EncodingSettings) -> Result<()> {
+        gst::init()?;
+        let item = self.playlist.lock().unwrap().iter().find(|item| item.enabled).cloned()
+            .ok_or(StreamerError::NoPlayableItems)?;
+        let video_fill = self.options.lock().unwrap().video_fill.clone();
+        dryrun::run(&item, settings, video_fill)
+    }
+
+    /// Receives every event emitted from now on.
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<PlayoutEvent> {
+        self.events.subscribe()
+    }
+
+    /// Events emitted at or after `since`, oldest first, for consumers that did not subscribe
+    /// from the start. Only recent history is kept.
+    pub fn events_since(&self, since: std::time::SystemTime) -> Vec<PlayoutEvent> {
+        self.events.since(since)
+    }
+
+    /// Shows bars and silence instead of the program while the output reconnects.
+    pub fn set_standby_on_reconnect(&self, enabled: bool) {
+        self.reconnect.lock().unwrap().set_standby_on_reconnect(enabled);
+    }
+
+    /// How many times to retry a dropped output, and what to do when that is exhausted.
+    pub fn set_reconnect_policy(&self, policy: ReconnectPolicy) {
+        self.reconnect.lock().unwrap().set_policy(policy);
+    }
+
+    pub fn output_state(&self) -> OutputState {
+        self.reconnect.lock().unwrap().state()
+    }
+
+    /// Plays `config.uri` between regular items, or disables interstitials with `None`.
+    /// Takes effect from the next transition when the stream is running.
+    pub fn set_interstitial(&self, config: Option<InterstitialConfig>) {
+        self.sequencer.lock().unwrap().set_interstitial(config);
+    }
     
-    pub fn add_item(&self, uri: &str) {
+    /// Appends `uri` and returns its id.
+    pub fn add_item(&self, uri: &str) -> u64 {
+        self.insert(uri, None).expect("appending is never out of bounds")
+    }
+
+    /// Like [`Streamer::add_item`], for a URI typed in by hand: checks first that it is one
+    /// that can be played (see [`Streamer::is_supported_uri`]) and returns the new id.
+    pub fn add_uri(&self, uri: &str) -> Result<u64> {
+        if !Self::is_supported_uri(uri) {
+            return Err(StreamerError::UnsupportedUri { uri: redact_url(uri) }.into());
+        }
+        self.insert(uri, None)
+    }
+
+    /// Whether `uri` is a URI an installed GStreamer element can read, e.g. `file://`,
+    /// `http(s)://` or `rtsp://`. Doesn't open it.
+    pub fn is_supported_uri(uri: &str) -> bool {
+        gst::init().is_ok()
+            && gst::Uri::is_valid(uri)
+            && gst::Uri::protocol(uri).map_or(false, |scheme| gst::Uri::protocol_is_supported(gst::URIType::Src, &scheme))
+    }
+
+    /// Appends the files in `dir` with one of `extensions` (e.g. [`MEDIA_EXTENSIONS`]),
+    /// including subfolders if `recursive`, in natural file name order. Returns the new ids.
+    pub fn add_directory(&self, dir: &Path, extensions: &[&str], recursive: bool) -> Result<Vec<u64>> {
+        let uris = Self::directory_uris(dir, extensions, recursive)?;
+        let mut playlist = self.playlist.lock().unwrap();
+        let ids = uris.iter()
+            .map(|uri| {
+                let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+                playlist.push(PlaylistItem::new(id, uri));
+                id
+            })
+            .collect();
+        Ok(ids)
+    }
+
+    /// The URIs [`Streamer::add_directory`] would add, in the same order, without adding them.
+    pub fn directory_uris(dir: &Path, extensions: &[&str], recursive: bool) -> Result<Vec<String>> {
+        let dir = fs::canonicalize(dir).with_context(|| format!("Could not open folder {}", dir.display()))?;
+        directory::media_files(&dir, extensions, recursive)?.iter()
+            .map(|path| {
+                glib::filename_to_uri(path, None)
+                    .map(String::from)
+                    .with_context(|| format!("Cannot make a URI for {}", path.display()))
+            })
+            .collect()
+    }
+
+    /// Adds `uri` so that it ends up at `index`, moving the items from there down one, and
+    /// returns its id. `index` may be the playlist length, which appends.
+    pub fn insert_item(&self, uri: &str, index: usize) -> Result<u64> {
+        self.insert(uri, Some(index))
+    }
+
+    /// Appends a live feed such as an RTSP camera (see [`ItemKind::Live`]), which stays on air
+    /// for `duration` each time it comes round, and returns its id.
+    pub fn add_live_item(&self, uri: &str, duration: Duration, latency_ms: u32) -> Result<u64> {
+        if !Self::is_supported_uri(uri) {
+            return Err(StreamerError::UnsupportedUri { uri: redact_url(uri) }.into());
+        }
+        if duration.is_zero() {
+            return Err(anyhow!("A live item needs a duration, or it would never end"));
+        }
+        let mut item = PlaylistItem::new(NEXT_ID.fetch_add(1, Ordering::SeqCst), uri);
+        item.kind = ItemKind::Live { latency_ms };
+        item.duration = Some(duration);
+        let id = item.id;
+        self.playlist.lock().unwrap().push(item);
+        Ok(id)
+    }
+
+    // Inserts at `index`, or at the end, under a single lock so the bounds check holds.
+    fn insert(&self, uri: &str, index: Option<usize>) -> Result<u64> {
         let mut playlist = self.playlist.lock().unwrap();
+        let index = index.unwrap_or(playlist.len());
+        if index > playlist.len() {
+            return Err(StreamerError::InvalidPosition { index, len: playlist.len() }.into());
+        }
         let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
-        playlist.push(PlaylistItem { id, uri: uri.to_string() });
+        playlist.insert(index, PlaylistItem::new(id, uri));
+        Ok(id)
+    }
+
+    pub fn set_item_tags(&self, id: u64, tags: Vec<String>) -> Result<()> {
+        let mut playlist = self.playlist.lock().unwrap();
+        let item = playlist.iter_mut().find(|item| item.id == id)
+            .ok_or(StreamerError::ItemNotFound { id })?;
+        item.tags = tags;
+        Ok(())
+    }
+
+    /// Restricts playout to items tagged with at least one of `tags`, or lifts the
+    /// restriction with `None`. Takes effect from the next transition.
+    pub fn set_tag_filter(&self, tags: Option<Vec<String>>) {
+        self.sequencer.lock().unwrap().set_tag_filter(tags);
+    }
+
+    /// Ends the stream cleanly once the current item finishes instead of advancing.
+    /// Calling it again cancels; [`Streamer::stop`] still stops immediately.
+    pub fn stop_after_current(&self) -> Result<()> {
+        if self.pipeline.is_none() {
+            return Err(StreamerError::NotRunning.into());
+        }
+        let armed = self.sequencer.lock().unwrap().toggle_stop_after_current();
+        info!(target: LOG_PLAYBACK, "Stop after current item {}.", if armed { "armed" } else { "cancelled" });
+        Ok(())
+    }
+
+    pub fn is_stop_after_current_pending(&self) -> bool {
+        self.sequencer.lock().unwrap().stop_after_current()
+    }
+
+    /// What happens after the last item. Takes effect from the next transition.
+    pub fn set_repeat_mode(&self, mode: RepeatMode) {
+        self.sequencer.lock().unwrap().set_repeat_mode(mode);
+    }
+
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.sequencer.lock().unwrap().repeat_mode()
+    }
+
+    /// Plays the playlist in random order from the next transition, without reordering it.
+    pub fn set_shuffle(&self, enabled: bool) {
+        self.sequencer.lock().unwrap().set_shuffle(enabled);
+    }
+
+    pub fn is_shuffled(&self) -> bool {
+        self.sequencer.lock().unwrap().shuffle()
+    }
+
+    /// Keeps replaying the current item (e.g. a slate) instead of advancing while `held`.
+    /// Releasing the hold lets the playlist advance again at the next end of item.
+    pub fn hold_current(&self, held: bool) {
+        info!(target: LOG_PLAYBACK, "{} current item.", if held { "Holding" } else { "Releasing" });
+        self.sequencer.lock().unwrap().set_held(held);
+    }
+
+    pub fn is_holding_current(&self) -> bool {
+        self.sequencer.lock().unwrap().is_held()
+    }
+
+    /// Cuts straight to what would follow the current item if it ended now, so repeat,
+    /// shuffle, interstitials, a hold and a pending stop after current all apply.
+    pub fn skip_next(&self) -> Result<()> {
+        self.request_skip(Skip::Next)
+    }
+
+    /// Cuts back to the enabled item before the current one, wrapping to the end of the playlist.
+    pub fn skip_previous(&self) -> Result<()> {
+        self.request_skip(Skip::Previous)
+    }
+
+    /// Cuts to the item with `id` right away, wherever it is in the playlist and even if it is
+    /// disabled. The playlist carries on from there.
+    pub fn play_item(&self, id: u64) -> Result<()> {
+        if !self.playlist.lock().unwrap().iter().any(|item| item.id == id) {
+            return Err(StreamerError::ItemNotFound { id }.into());
+        }
+        self.request_skip(Skip::Item(id))
+    }
+
+    // Transitions only happen on the bus thread; the skip is handed over there and, like an
+    // EOS, only goes ahead if the entry it names is still on air.
+    fn request_skip(&self, to: Skip) -> Result<()> {
+        let pipeline = self.pipeline.as_ref().ok_or(StreamerError::NotRunning)?;
+        let generation = self.on_air.lock().unwrap().as_ref()
+            .map(|on_air| on_air.generation)
+            .ok_or(StreamerError::NotRunning)?;
+        let s = match to {
+            Skip::Next => gst::Structure::builder("hayai-skip").field("to", "next"),
+            Skip::Previous => gst::Structure::builder("hayai-skip").field("to", "previous"),
+            Skip::Item(id) => gst::Structure::builder("hayai-skip").field("to", "item").field("id", id),
+        }
+        .field("generation", generation)
+        .build();
+        pipeline.post_message(gst::message::Application::new(s))
+            .map_err(|_| anyhow!("Could not hand the skip to the playout thread"))?;
+        Ok(())
+    }
+
+    /// Plays the item with software decoders only.
+    pub fn set_item_force_software_decode(&self, id: u64, force: bool) -> Result<()> {
+        let mut playlist = self.playlist.lock().unwrap();
+        let item = playlist.iter_mut().find(|item| item.id == id)
+            .ok_or(StreamerError::ItemNotFound { id })?;
+        item.force_software_decode = force;
+        Ok(())
+    }
+
+    /// Cuts to the next item once this one has played for `duration`, whether or not it has
+    /// ended; `None` plays it to the end. Applies from the item's next time on air.
+    pub fn set_item_duration(&self, id: u64, duration: Option<Duration>) -> Result<()> {
+        let mut playlist = self.playlist.lock().unwrap();
+        let item = playlist.iter_mut().find(|item| item.id == id)
+            .ok_or(StreamerError::ItemNotFound { id })?;
+        item.duration = duration;
+        Ok(())
+    }
+
+    /// Plays at most `max_duration` of the item before cutting to the next one, for trimming
+    /// long files; `None` lifts the limit. Applies from the item's next time on air.
+    pub fn set_item_max_duration(&self, id: u64, max_duration: Option<Duration>) -> Result<()> {
+        let mut playlist = self.playlist.lock().unwrap();
+        let item = playlist.iter_mut().find(|item| item.id == id)
+            .ok_or(StreamerError::ItemNotFound { id })?;
+        item.max_duration = max_duration;
+        Ok(())
+    }
+
+    /// Starts the item `offset` in rather than at the beginning; `None` starts it at the
+    /// beginning. Applies from the item's next time on air.
+    pub fn set_item_start_offset(&self, id: u64, offset: Option<Duration>) -> Result<()> {
+        let mut playlist = self.playlist.lock().unwrap();
+        let item = playlist.iter_mut().find(|item| item.id == id)
+            .ok_or(StreamerError::ItemNotFound { id })?;
+        item.start_offset = offset;
+        Ok(())
+    }
+
+    /// Skips (`false`) or includes (`true`) an item in playout without removing it.
+    pub fn set_item_enabled(&self, id: u64, enabled: bool) -> Result<()> {
+        let mut playlist = self.playlist.lock().unwrap();
+        let item = playlist.iter_mut().find(|item| item.id == id)
+            .ok_or(StreamerError::ItemNotFound { id })?;
+        item.enabled = enabled;
+        Ok(())
     }
     
-    pub fn remove_item(&self, id: u64) { 
-        self.playlist.lock().unwrap().retain(|item| item.id != id); 
+    /// Empties the playlist. An item on air plays to its end, then playout ends as if the
+    /// playlist had finished.
+    pub fn clear_playlist(&self) {
+        self.playlist.lock().unwrap().clear();
+    }
+
+    /// Swaps the whole playlist for `uris` in one go, with fresh ids. An item on air plays to
+    /// its end and the new playlist follows from the top.
+    pub fn replace_playlist(&self, uris: Vec<String>) {
+        let items = uris.iter()
+            .map(|uri| PlaylistItem::new(NEXT_ID.fetch_add(1, Ordering::SeqCst), uri))
+            .collect();
+        *self.playlist.lock().unwrap() = items;
+    }
+
+    /// Removes an item. Removing the item on air cuts straight to the enabled item after it,
+    /// or, with nothing else to play, lets it finish and ends playout. If an interstitial
+    /// follows it on air, the playlist still resumes after the removed item.
+    pub fn remove_item(&self, id: u64) {
+        let mut playlist = self.playlist.lock().unwrap();
+        let mut playing_id = self.currently_playing_id.lock().unwrap();
+        let mut cut_to = None;
+        if *playing_id == Some(id) && self.is_running() {
+            if self.sequencer.lock().unwrap().in_interstitial() {
+                // Resume after the interstitial from where the removed item was.
+                let index = playlist.iter().position(|item| item.id == id).unwrap_or(0);
+                *playing_id = index.checked_sub(1).map(|previous| playlist[previous].id);
+            } else {
+                cut_to = compute_next_index(&playlist, Some(id))
+                    .map(|next| playlist[next].id)
+                    .filter(|&next| next != id);
+            }
+        }
+        playlist.retain(|item| item.id != id);
+        drop(playing_id);
+        drop(playlist);
+        if let Some(next) = cut_to {
+            info!(target: LOG_PLAYBACK, "Removed the item on air, cutting to the next one.");
+            if let Err(e) = self.request_skip(Skip::Item(next)) {
+                warn!(target: LOG_PLAYBACK, "Could not cut away from the removed item: {}", e);
+            }
+        }
     }
     
     pub fn move_item(&self, id: u64, new_index: usize) -> Result<()> {
         let mut playlist = self.playlist.lock().unwrap();
         if new_index >= playlist.len() { 
-            return Err(anyhow!("Index out of bounds")); 
+            return Err(StreamerError::InvalidPosition { index: new_index, len: playlist.len() }.into());
         }
         let old_index = playlist.iter().position(|item| item.id == id)
-            .ok_or_else(|| anyhow!("ID not found"))?;
+            .ok_or(StreamerError::ItemNotFound { id })?;
         let item = playlist.remove(old_index);
         playlist.insert(new_index, item);
         Ok(())
     }
     
+    /// Moves the items with `ids` together as a block, in their playlist order, so that the
+    /// first of them ends up at `new_index`. Nothing moves if an id is unknown or the block
+    /// would run past the end.
+    pub fn move_items(&self, ids: &[u64], new_index: usize) -> Result<()> {
+        let mut playlist = self.playlist.lock().unwrap();
+        if let Some(&id) = ids.iter().find(|id| !playlist.iter().any(|item| item.id == **id)) {
+            return Err(StreamerError::ItemNotFound { id }.into());
+        }
+        let count = playlist.iter().filter(|item| ids.contains(&item.id)).count();
+        if new_index + count > playlist.len() {
+            return Err(StreamerError::InvalidPosition { index: new_index, len: playlist.len() }.into());
+        }
+        let (moved, mut rest): (Vec<_>, Vec<_>) = playlist.drain(..).partition(|item| ids.contains(&item.id));
+        rest.splice(new_index..new_index, moved);
+        *playlist = rest;
+        Ok(())
+    }
+
+    /// Reorders the playlist. Ids stay the same, so the item on air keeps playing and the
+    /// playlist carries on after it in the new order.
+    pub fn sort_playlist(&self, order: SortOrder) {
+        sort::sort_items(&mut self.playlist.lock().unwrap(), order);
+    }
+
+    fn playout_state(&self) -> PlayoutState {
+        PlayoutState {
+            playlist: self.playlist.clone(),
+            currently_playing_id: self.currently_playing_id.clone(),
+            sequencer: self.sequencer.clone(),
+            options: self.options.clone(),
+            events: self.events.clone(),
+            on_air: self.on_air.clone(),
+            transitions: self.transitions.clone(),
+            fallback: self.fallback.clone(),
+        }
+    }
+
+    /// Replaces the playlist with the items saved in `path` and returns how many were loaded.
+    /// Items get fresh ids; everything else, including disabled items, is kept.
+    pub fn load_playlist(&self, path: &Path) -> Result<usize> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Could not read playlist {}", path.display()))?;
+        let mut items: Vec<PlaylistItem> = serde_json::from_str(&data)
+            .with_context(|| format!("{} is not a playlist file", path.display()))?;
+        for item in &mut items {
+            item.id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        }
+        let count = items.len();
+        *self.playlist.lock().unwrap() = items;
+        Ok(count)
+    }
+
+    /// Writes the playlist to `path` as JSON, in the format read by [`Streamer::load_playlist`].
+    pub fn save_playlist(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(&*self.playlist.lock().unwrap())?;
+        fs::write(path, data).with_context(|| format!("Could not write playlist {}", path.display()))?;
+        Ok(())
+    }
+
     pub fn get_playlist_clone(&self) -> Vec<PlaylistItem> {
         self.playlist.lock().unwrap().clone()
     }
+
+    /// Runs `f` on the playlist as it is, without copying it, and returns what `f` returns.
+    /// The playlist is locked meanwhile, so `f` must not call back into the `Streamer`.
+    pub fn with_playlist<R>(&self, f: impl FnOnce(&[PlaylistItem]) -> R) -> R {
+        f(&self.playlist.lock().unwrap())
+    }
+
+    pub fn playlist_len(&self) -> usize {
+        self.playlist.lock().unwrap().len()
+    }
+
+    /// The playlist entry with `id`, if it is still in the playlist.
+    pub fn get_item(&self, id: u64) -> Option<PlaylistItem> {
+        self.playlist.lock().unwrap().iter().find(|item| item.id == id).cloned()
+    }
+
+    /// The id of the first entry playing `uri`, if any, e.g. to avoid adding a file twice.
+    pub fn find_by_uri(&self, uri: &str) -> Option<u64> {
+        self.playlist.lock().unwrap().iter().find(|item| item.uri == uri).map(|item| item.id)
+    }
     
     pub fn get_currently_playing_id(&self) -> Option<u64> {
         *self.currently_playing_id.lock().unwrap()
     }
-}
 
-fn create_processing_bin(rtmp_url: &str, settings: &EncodingSettings) -> Result<gst::Bin> {
-    let bin = gst::Bin::with_name("processing_bin");
-    let vconv = gst::ElementFactory::make("videoconvert").build()?;
-    let vrate = gst::ElementFactory::make("videorate").build()?;
-    let venc = gst::ElementFactory::make(&settings.video_encoder).name("video_encoder").build()?;
-    let aconv = gst::ElementFactory::make("audioconvert").build()?;
-    let aresample = gst::ElementFactory::make("audioresample").build()?;
-    let aenc = gst::ElementFactory::make(&settings.audio_encoder).build()?;
-    let mux = gst::ElementFactory::make("flvmux").name("mux").property("streamable", true).build()?;
-    let sink = gst::ElementFactory::make("rtmpsink").build()?;
-    
-    // Configure encoders
-    if venc.has_property("tune") { venc.set_property_from_str("tune", "zerolatency"); }
-    if venc.has_property("bitrate") { venc.set_property("bitrate", settings.bitrate_kbps); }
-    if venc.has_property("speed-preset") { venc.set_property_from_str("speed-preset", &settings.speed_preset); }
-    if venc.has_property("key-int-max") { venc.set_property("key-int-max", 60u32); }
-    if aenc.has_property("bitrate") { aenc.set_property("bitrate", 128000_i32); }
-    sink.set_property("location", rtmp_url);
-    sink.set_property("sync", false);
-    sink.set_property("qos", true);
-    
-    if settings.scale_enabled {
-        let vscale = gst::ElementFactory::make("videoscale").build()?;
-        let capsfilter = gst::ElementFactory::make("capsfilter").build()?;
-        let caps = gst::Caps::builder("video/x-raw")
-            .field("width", settings.scale_width as i32)
-            .field("height", settings.scale_height as i32)
-            .build();
-        capsfilter.set_property("caps", caps);
-        bin.add_many(&[&vconv, &vrate, &vscale, &capsfilter, &venc, &aconv, &aresample, &aenc, &mux, &sink])?;
-        gst::Element::link_many(&[&vconv, &vrate, &vscale, &capsfilter, &venc, &mux])?;
-    } else {
-        bin.add_many(&[&vconv, &vrate, &venc, &aconv, &aresample, &aenc, &mux, &sink])?;
-        gst::Element::link_many(&[&vconv, &vrate, &venc, &mux])?;
-    }
-    gst::Element::link_many(&[&aconv, &aresample, &aenc, &mux])?;
-    mux.link(&sink)?;
-    
-    // Create ghost pads
-    let vpad = gst::GhostPad::with_target(&vconv.static_pad("sink").unwrap())?;
-    vpad.set_property("name", "video_sink");
-    bin.add_pad(&vpad)?;
-    let apad = gst::GhostPad::with_target(&aconv.static_pad("sink").unwrap())?;
-    apad.set_property("name", "audio_sink");
-    bin.add_pad(&apad)?;
+    /// The playlist entry currently on air, or `None` if nothing is playing or it was removed.
+    pub fn current_item(&self) -> Option<PlaylistItem> {
+        let playlist = self.playlist.lock().unwrap();
+        let id = (*self.currently_playing_id.lock().unwrap())?;
+        playlist.iter().find(|item| item.id == id).cloned()
+    }
 
-    Ok(bin)
-}
+    /// Title of the entry on air: the title tag of its file, or else its file name. `None`
+    /// when nothing is on air.
+    pub fn current_title(&self) -> Option<String> {
+        let on_air = self.on_air.lock().unwrap();
+        let on_air = on_air.as_ref()?;
+        Some(on_air.title.clone().unwrap_or_else(|| title_from_uri(&on_air.uri)))
+    }
 
-fn play_next(
-    p: &gst::Pipeline,
-    vs: &gst::Element,
-    as_: &gst::Element,
-    pl_arc: &Arc<Mutex<Vec<PlaylistItem>>>,
-    pid_arc: &Arc<Mutex<Option<u64>>>,
-    element_to_remove: Option<gst::Element>,
-) -> Result<()> {
-    println!("[DEBUG] play_next: Starting transition.");
-    let playlist = pl_arc.lock().unwrap();
-    let mut playing_id = pid_arc.lock().unwrap();
+    /// Bytes sent, output bitrate, dropped frames and uptime of the running stream; all zero
+    /// when nothing was started. Cheap enough to poll for a status display.
+    pub fn get_stats(&self) -> StreamStats {
+        let counter = match self.stats.lock().unwrap().clone() {
+            Some(counter) => counter,
+            None => return StreamStats::default(),
+        };
+        let rate_dropped = self.pipeline.as_ref()
+            .and_then(|p| p.by_name(FRAME_RATE_NAME))
+            .map_or(0, |rate| rate.property::<u64>("drop"));
+        counter.snapshot(rate_dropped)
+    }
 
-    println!("[DEBUG] play_next: Current playlist state: {:?}", playlist);
-    println!("[DEBUG] play_next: Currently playing ID: {:?}", *playing_id);
+    /// Position and, if known, duration of the playlist item on air. `None` when nothing is
+    /// playing or standby/a frozen frame is on air. Only queries one pad, so it is cheap to poll.
+    pub fn get_position(&self) -> Option<(gst::ClockTime, Option<gst::ClockTime>)> {
+        let pipeline = self.pipeline.as_ref()?;
+        self.currently_playing_id.lock().unwrap().as_ref()?;
+        // Pad queries travel upstream to the demuxer; the uridecodebin itself has no sinks to ask.
+        let pad = ["video_selector", "audio_selector"].iter()
+            .filter_map(|name| pipeline.by_name(name))
+            .filter_map(|selector| selector::active_source_pad(&selector))
+            .find(|pad| pad.parent_element().map_or(false, |elem| elem.name().starts_with(SOURCE_NAME_PREFIX)))?;
+        let position = pad.query_position::<gst::ClockTime>()?;
+        Some((position, pad.query_duration::<gst::ClockTime>()))
+    }
 
-    if playlist.is_empty() { 
-        println!("[ERROR] play_next: Playlist is empty, cannot play next item.");
-        return Err(anyhow!("Playlist is empty")); 
+    /// Also sends the program to `target`, next to the output the stream was started with,
+    /// without interrupting it. Each destination is muxed and sent on its own, so one that
+    /// drops is taken out (with [`PlayoutEvent::OutputFailed`]) while the others carry on.
+    ///
+    /// Outputs are added to the running stream only, and go when it stops.
+    pub fn add_output(&self, target: OutputTarget) -> Result<()> {
+        let pipeline = self.pipeline.as_ref().filter(|_| self.is_running()).ok_or(StreamerError::NotRunning)?;
+        let bin = pipeline.by_name(PROCESSING_BIN_NAME)
+            .and_then(|bin| bin.downcast::<gst::Bin>().ok())
+            .ok_or_else(|| anyhow!("Processing bin not found in pipeline"))?;
+        let mut extra_outputs = self.extra_outputs.lock().unwrap();
+        if extra_outputs.iter().any(|(existing, _)| *existing == target) {
+            return Err(anyhow!("Already streaming to {}", redact_url(&target.url())));
+        }
+        let branch = outputs::add_branch(&bin, &target)?;
+        info!(target: LOG_OUTPUT, "Also streaming to {}", redact_url(&target.url()));
+        extra_outputs.push((target, branch));
+        Ok(())
     }
 
-    let mut next_index = 0;
-    if let Some(id) = *playing_id {
-        if let Some(current_index) = playlist.iter().position(|item| item.id == id) {
-            next_index = (current_index + 1) % playlist.len();
+    /// Stops sending to `target`, added with [`Streamer::add_output`]. The rest of the stream
+    /// is left alone.
+    pub fn remove_output(&self, target: &OutputTarget) -> Result<()> {
+        let pipeline = self.pipeline.as_ref().ok_or(StreamerError::NotRunning)?;
+        let mut extra_outputs = self.extra_outputs.lock().unwrap();
+        let index = extra_outputs.iter().position(|(existing, _)| existing == target)
+            .ok_or_else(|| anyhow!("Not streaming to {}", redact_url(&target.url())))?;
+        let (_, branch) = extra_outputs.remove(index);
+        if let Some(bin) = pipeline.by_name(PROCESSING_BIN_NAME).and_then(|bin| bin.downcast::<gst::Bin>().ok()) {
+            outputs::remove_branch(&bin, &branch);
         }
+        info!(target: LOG_OUTPUT, "Stopped streaming to {}", redact_url(&target.url()));
+        Ok(())
     }
 
-    let next_item = playlist[next_index].clone();
-    let new_id = next_item.id;
-    println!("[DEBUG] play_next: Next item to play: (index {}) {}", next_index, next_item.uri);
-    drop(playlist);
+    /// Destinations added with [`Streamer::add_output`] that are still being sent to.
+    pub fn extra_outputs(&self) -> Vec<OutputTarget> {
+        self.extra_outputs.lock().unwrap().iter().map(|(target, _)| target.clone()).collect()
+    }
 
-    switch_source(p, vs, as_, &next_item, element_to_remove)?;
-    *playing_id = Some(new_id);
-    println!("[DEBUG] play_next: Transition complete. New playing ID: {:?}", *playing_id);
-    Ok(())
-}
+    /// Shows the picture going out in `sink` as well, such as a `gtk4paintablesink` for a
+    /// preview in a UI; `None` takes the preview away. It can be set before or while
+    /// streaming and stays for the streams after.
+    ///
+    /// The preview is fed just before the video encoder and drops frames it has no time for,
+    /// so it never holds up the stream.
+    pub fn set_preview_sink(&self, sink: Option<gst::Element>) -> Result<()> {
+        let bin = self.pipeline.as_ref()
+            .and_then(|pipeline| pipeline.by_name(PROCESSING_BIN_NAME))
+            .and_then(|bin| bin.downcast::<gst::Bin>().ok());
+        let mut preview_sink = self.preview_sink.lock().unwrap();
+        if let Some(bin) = &bin {
+            preview::detach(bin);
+        }
+        *preview_sink = None;
+        if let (Some(bin), Some(sink)) = (&bin, &sink) {
+            preview::attach(bin, sink)?;
+        }
+        *preview_sink = sink;
+        Ok(())
+    }
 
-fn switch_source(
-    pipeline: &gst::Pipeline,
-    v_selector: &gst::Element,
-    a_selector: &gst::Element,
-    item: &PlaylistItem,
+    /// Asks the video encoder to emit a keyframe (with stream headers) as soon as possible.
+    pub fn force_keyframe(&self) -> Result<()> {
+        let pipeline = self.pipeline.as_ref().ok_or(StreamerError::NotRunning)?;
+        let encoder = pipeline.by_name(VIDEO_ENCODER_NAME)
+            .ok_or_else(|| anyhow!("Video encoder not found in pipeline"))?;
+        let src_pad = encoder.static_pad("src")
+            .ok_or_else(|| anyhow!("Video encoder has no src pad"))?;
+        if !src_pad.send_event(force_key_unit_event()) {
+            return Err(anyhow!("Video encoder did not handle the force-key-unit event"));
+        }
+        Ok(())
+    }
+
+    /// Sets the master volume as a linear factor, 1.0 (the default) leaving the audio as it
+    /// is. Clamped to 0.0 to [`Self::MAX_VOLUME`]; NaN is ignored. Takes effect at once while
+    /// streaming.
+    pub fn set_volume(&self, linear: f64) {
+        if linear.is_nan() {
+            warn!(target: LOG_PLAYBACK, "Ignoring a volume of NaN.");
+            return;
+        }
+        let linear = linear.clamp(0.0, Self::MAX_VOLUME);
+        *self.volume.lock().unwrap() = linear;
+        if let Some(volume) = self.pipeline.as_ref().and_then(|p| p.by_name(VOLUME_NAME)) {
+            volume.set_property("volume", linear);
+        }
+    }
+
+    pub fn get_volume(&self) -> f64 {
+        *self.volume.lock().unwrap()
+    }
+
+    /// A clip looped in place of the playlist while nothing in it can be played: when it is
+    /// empty or everything is disabled, including at start, or when the next item cannot be
+    /// played. The stream, and the connection to the server, stay up meanwhile, and the
+    /// playlist picks up again as soon as it has something to play. With `None` (the default)
+    /// the stream ends instead. The end of the playlist with [`RepeatMode::None`] and
+    /// [`Streamer::stop_after_current`] still end it.
+    pub fn set_fallback_uri(&self, uri: Option<String>) {
+        self.options.lock().unwrap().fallback_uri = uri;
+    }
+
+    /// How many items in a row may fail to play (5 by default, at least 1) before the stream
+    /// gives up on the playlist: it goes to the fallback clip if one is set, and stops otherwise.
+    pub fn set_max_consecutive_failures(&self, count: u32) {
+        self.options.lock().unwrap().max_consecutive_failures = count.max(1);
+    }
+
+    /// Whether the fallback clip is on air.
+    pub fn is_on_fallback(&self) -> bool {
+        self.fallback.lock().unwrap().is_some()
+    }
+
+    /// What items without video, such as music beds, show instead: black (the default),
+    /// color bars, or a still image, which is decoded here. Applies from the next item on.
+    pub fn set_video_fill(&self, fill: VideoFill) -> Result<()> {
+        let picture = FillPicture::load(&fill)?;
+        self.options.lock().unwrap().video_fill = picture;
+        Ok(())
+    }
+
+    /// Lays the image at `path` over the program, `margin` pixels in from `position`, or
+    /// removes it with `None`. PNG transparency is respected. Takes effect at once while
+    /// streaming, otherwise when the stream starts.
+    pub fn set_logo(&self, path: Option<&Path>, position: Corner, margin: u32) -> Result<()> {
+        let logo = match path {
+            Some(path) => {
+                if !path.is_file() {
+                    return Err(anyhow!("Logo image {} not found", path.display()));
+                }
+                Some(Logo { path: path.to_path_buf(), corner: position, margin })
+            }
+            None => None,
+        };
+        if let Some(pipeline) = &self.pipeline {
+            let overlay = pipeline.by_name(LOGO_OVERLAY_NAME)
+                .ok_or_else(|| StreamerError::MissingPlugin { element: "gdkpixbufoverlay".to_string() })?;
+            logo::apply(&overlay, logo.as_ref())?;
+        }
+        *self.logo.lock().unwrap() = logo;
+        Ok(())
+    }
+
+    /// Burns a caption and/or clock into the video. Takes effect at once while streaming,
+    /// otherwise when the stream starts. Fails with [`StreamerError::MissingPlugin`] while
+    /// streaming without the pango plugin that provides `textoverlay`.
+    pub fn set_text_overlay(&self, config: TextOverlayConfig) -> Result<()> {
+        if let Some(pipeline) = &self.pipeline {
+            let text = pipeline.by_name(TEXT_OVERLAY_NAME);
+            let clock = pipeline.by_name(CLOCK_OVERLAY_NAME);
+            match (text, clock) {
+                (Some(text), Some(clock)) => {
+                    text_overlay::apply_text(&text, &config);
+                    text_overlay::apply_clock(&clock, &config);
+                }
+                _ => return Err(StreamerError::MissingPlugin { element: "textoverlay".to_string() }.into()),
+            }
+        }
+        *self.text_overlay.lock().unwrap() = config;
+        Ok(())
+    }
+
+    /// Changes just the caption of the text overlay, e.g. to the title now playing.
+    pub fn set_overlay_text(&self, text: Option<&str>) -> Result<()> {
+        let mut config = self.text_overlay.lock().unwrap().clone();
+        config.text = text.map(str::to_string);
+        self.set_text_overlay(config)
+    }
+
+    /// Holds the video on the last frame (`true`) or returns to the program (`false`).
+    /// Audio keeps playing either way.
+    pub fn freeze_video(&self, frozen: bool) -> Result<()> {
+        let pipeline = self.pipeline.as_ref().ok_or(StreamerError::NotRunning)?;
+        let vs = pipeline.by_name("video_selector")
+            .ok_or_else(|| anyhow!("Video selector not found in pipeline"))?;
+        let mut freeze = self.freeze.lock().unwrap();
+        if frozen {
+            if freeze.is_none() {
+                let frame = self.last_frame.lock().unwrap().clone()
+                    .ok_or_else(|| anyhow!("No video frame to freeze on yet"))?;
+                *freeze = Some(Freeze::enter(pipeline, &vs, &frame)?);
+            }
+        } else if let Some(f) = freeze.take() {
+            f.leave(pipeline, &vs);
+        }
+        Ok(())
+    }
+
+    pub fn is_video_frozen(&self) -> bool {
+        self.freeze.lock().unwrap().is_some()
+    }
+}
+
+/// Total of the items' play lengths, or `None` if any is unknown.
+fn sum_play_lengths<'a>(mut items: impl Iterator<Item = &'a PlaylistItem>) -> Option<gst::ClockTime> {
+    items.try_fold(gst::ClockTime::ZERO, |total, item| {
+        Some(total + gst::ClockTime::from_nseconds(item.play_length()?.as_nanos() as u64))
+    })
+}
+
+/// Builds the upstream `GstForceKeyUnit` event understood by GstVideoEncoder-based encoders.
+/// Equivalent to `gst_video_event_new_upstream_force_key_unit(GST_CLOCK_TIME_NONE, TRUE, 0)`.
+pub fn force_key_unit_event() -> gst::Event {
+    let s = gst::Structure::builder("GstForceKeyUnit")
+        // GST_CLOCK_TIME_NONE: as soon as possible.
+        .field("running-time", u64::MAX)
+        .field("all-headers", true)
+        .field("count", 0u32)
+        .build();
+    gst::event::CustomUpstream::new(s)
+}
+
+/// Takes out the extra output `branch` after an error in it; the other outputs carry on.
+/// Its elements usually post several errors, only the first does anything.
+fn drop_failed_output(
+    p: &gst::Pipeline,
+    branch: &str,
+    error: &str,
+    extra_outputs: &Mutex<Vec<(OutputTarget, String)>>,
+    events: &EventBus,
+) {
+    let target = {
+        let mut extra_outputs = extra_outputs.lock().unwrap();
+        match extra_outputs.iter().position(|(_, name)| name == branch) {
+            Some(index) => extra_outputs.remove(index).0,
+            None => return,
+        }
+    };
+    let url = redact_url(&target.url());
+    warn!(target: LOG_OUTPUT, "Output {} failed, carrying on without it: {}", url, error);
+    if let Some(bin) = p.by_name(PROCESSING_BIN_NAME).and_then(|bin| bin.downcast::<gst::Bin>().ok()) {
+        outputs::remove_branch(&bin, branch);
+    }
+    events.emit(PlayoutEvent::OutputFailed { url, error: error.to_string() });
+}
+
+/// Carries out the monitor's decisions; a retry is due at `retry_at`, when the bus thread
+/// restarts the sink. Returns `false` once the stream has been shut down.
+fn apply_reconnect_actions(
+    p: &gst::Pipeline,
+    actions: &[ReconnectAction],
+    retry_delay: Duration,
+    retry_at: &mut Option<Instant>,
+    standby: &mut Option<Standby>,
+    events: &EventBus,
+) -> bool {
+    let (vs, as_) = match (p.by_name("video_selector"), p.by_name("audio_selector")) {
+        (Some(vs), Some(as_)) => (vs, as_),
+        _ => return true,
+    };
+    for action in actions {
+        match *action {
+            ReconnectAction::EnterStandby => match Standby::enter(p, &vs, &as_) {
+                Ok(s) => {
+                    *standby = Some(s);
+                    events.emit(PlayoutEvent::ReconnectStandbyEntered);
+                }
+                Err(e) => error!(target: LOG_OUTPUT, "Failed to switch to standby: {}", e),
+            },
+            ReconnectAction::LeaveStandby => {
+                if let Some(s) = standby.take() {
+                    s.leave(p, &vs, &as_);
+                    events.emit(PlayoutEvent::ReconnectStandbyLeft);
+                }
+            }
+            ReconnectAction::Retry { attempt } => {
+                info!(target: LOG_OUTPUT, "Output dropped, reconnecting in {:?} (attempt {}).", retry_delay, attempt);
+                events.emit(PlayoutEvent::Reconnecting { attempt });
+                *retry_at = Some(Instant::now() + retry_delay);
+            }
+            ReconnectAction::KeepRetryingInStandby => {
+                warn!(target: LOG_OUTPUT, "Reconnection attempts exhausted, staying in standby and retrying every {:?}.", retry_delay);
+                events.emit(PlayoutEvent::ReconnectStandby);
+            }
+            ReconnectAction::GiveUp => {
+                warn!(target: LOG_OUTPUT, "Reconnection attempts exhausted, stopping the stream.");
+                if let Some(s) = standby.take() {
+                    s.leave(p, &vs, &as_);
+                }
+                // Like a stop, so a recording still gets finished.
+                if let Err(e) = teardown::run(p) {
+                    error!(target: LOG_OUTPUT, "Failed to stop the stream: {}", e);
+                }
+                events.emit(PlayoutEvent::ReconnectGaveUp);
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Restarts the output sink for another connection attempt. Getting back to PLAYING proves
+/// nothing, as a connection can fail on the first write, so the output counts as back once a
+/// second buffer reaches the sink: had the first one failed, upstream would have stopped.
+fn restart_output_sink(p: &gst::Pipeline) {
+    let sink = match p.by_name(OUTPUT_SINK_NAME) {
+        Some(sink) => sink,
+        None => return,
+    };
+    if let (Some(pad), Some(bus)) = (sink.sink_pads().into_iter().next(), p.bus()) {
+        let buffers = AtomicU32::new(0);
+        pad.add_probe(gst::PadProbeType::BUFFER | gst::PadProbeType::BUFFER_LIST, move |_, _| {
+            if buffers.fetch_add(1, Ordering::SeqCst) == 0 {
+                return gst::PadProbeReturn::Ok;
+            }
+            let _ = bus.post(gst::message::Application::new(gst::Structure::new_empty("hayai-output-flowing")));
+            gst::PadProbeReturn::Remove
+        });
+    }
+    let _ = sink.set_state(gst::State::Null);
+    let _ = sink.sync_state_with_parent();
+}
+
+/// Where a manual skip goes.
+enum Skip {
+    Next,
+    Previous,
+    Item(u64),
+}
+
+/// What the sequencer picked to go on air next.
+struct Upcoming {
+    item: PlaylistItem,
+    is_interstitial: bool,
+    start_offset: Option<gst::ClockTime>,
+}
+
+enum Decision {
+    Play(Upcoming),
+    /// The playlist is over, or a stop after the current item was requested.
+    Stop { stop_requested: bool },
+    /// Nothing can be played; loop this clip until something can.
+    Fallback(String),
+}
+
+/// The entry decided on ahead of time to follow the source named `follows`, and its source
+/// if that could be built.
+struct Preloaded {
+    follows: String,
+    upcoming: Upcoming,
+    source: Option<(gst::Element, SourceSetup)>,
+}
+
+/// Asks the sequencer what follows the entry on air and, for regular items, how much leading
+/// black/silence to skip. Advances the sequencer, so call it once per transition.
+fn decide_next(state: &PlayoutState) -> Result<Decision> {
+    decide(state, Sequencer::next)
+}
+
+/// Like [`decide_next`], but leaves the sequencer alone, for deciding ahead of time.
+fn peek_next(state: &PlayoutState) -> Result<Decision> {
+    decide(state, |sequencer, playlist, playing_id| sequencer.peek(playlist, playing_id))
+}
+
+fn decide(
+    state: &PlayoutState,
+    pick: impl FnOnce(&mut Sequencer, &[PlaylistItem], Option<u64>) -> Option<NextEntry>,
+) -> Result<Decision> {
+    let playlist = state.playlist.lock().unwrap();
+    let playing_id = *state.currently_playing_id.lock().unwrap();
+
+    debug!(target: LOG_PLAYBACK, "play_next: Current playlist state: {:?}", playlist);
+    debug!(target: LOG_PLAYBACK, "play_next: Currently playing ID: {:?}", playing_id);
+
+    let (next, stop_requested) = {
+        let mut sequencer = state.sequencer.lock().unwrap();
+        let stop_requested = sequencer.stop_after_current();
+        (pick(&mut sequencer, &playlist, playing_id), stop_requested)
+    };
+    let fallback_uri = state.options.lock().unwrap().fallback_uri.clone();
+    let (item, is_interstitial) = match next {
+        Some(NextEntry::Item(next_index)) => {
+            debug!(target: LOG_PLAYBACK, "play_next: Next item to play: (index {}) {}", next_index, redact_url(&playlist[next_index].uri));
+            (playlist[next_index].clone(), false)
+        }
+        Some(NextEntry::Interstitial(uri)) => {
+            debug!(target: LOG_PLAYBACK, "play_next: Next item to play: (interstitial) {}", redact_url(&uri));
+            // Interstitials are not playlist entries, but still need a unique source name.
+            let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+            (PlaylistItem::new(id, &uri), true)
+        }
+        Some(NextEntry::Stop) => return Ok(Decision::Stop { stop_requested }),
+        None if fallback_uri.is_some() => {
+            info!(target: LOG_PLAYBACK, "play_next: Nothing to play, going to the fallback.");
+            return Ok(Decision::Fallback(fallback_uri.unwrap_or_default()));
+        }
+        // Everything was removed or disabled while on air; wind down as at the end of the playlist.
+        None if state.on_air.lock().unwrap().is_some() => {
+            info!(target: LOG_PLAYBACK, "play_next: Nothing left to play.");
+            return Ok(Decision::Stop { stop_requested });
+        }
+        None => {
+            error!(target: LOG_PLAYBACK, "play_next: No enabled items in the playlist, cannot play next item.");
+            return Err(StreamerError::NoPlayableItems.into());
+        }
+    };
+    drop(playlist);
+    Ok(Decision::Play(prepare(state, item, is_interstitial)))
+}
+
+/// Where the `hayai-skip` message `skip` goes: `None` to let the sequencer pick as at the end
+/// of an item, or the entry to cut to.
+fn skip_target(state: &PlayoutState, skip: &gst::StructureRef) -> Result<Option<Upcoming>> {
+    match skip.get::<&str>("to")? {
+        "previous" => previous_entry(state).map(Some).ok_or_else(|| StreamerError::NoPlayableItems.into()),
+        "item" => {
+            let id = skip.get::<u64>("id")?;
+            let item = state.playlist.lock().unwrap().iter().find(|item| item.id == id).cloned()
+                .ok_or(StreamerError::ItemNotFound { id })?;
+            Ok(Some(prepare(state, item, false)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The enabled playlist item before the last one that went on air. Leaves the sequencer alone.
+fn previous_entry(state: &PlayoutState) -> Option<Upcoming> {
+    let item = {
+        let playlist = state.playlist.lock().unwrap();
+        let playing_id = *state.currently_playing_id.lock().unwrap();
+        playlist[compute_previous_index(&playlist, playing_id)?].clone()
+    };
+    Some(prepare(state, item, false))
+}
+
+/// Works out where to start `item`: at its start offset if it has one, otherwise past its
+/// leading black/silence if trimming is on.
+fn prepare(state: &PlayoutState, item: PlaylistItem, is_interstitial: bool) -> Upcoming {
+    if let Some(offset) = item.start_offset.filter(|offset| !offset.is_zero()) {
+        if item.media_duration.map_or(false, |media| offset >= media) {
+            warn!(target: LOG_PLAYBACK, "Start offset {:?} is past the end of {}, playing from the start.",
+                  offset, redact_url(&item.uri));
+            return Upcoming { item, is_interstitial, start_offset: None };
+        }
+        let start_offset = Some(gst::ClockTime::from_nseconds(offset.as_nanos() as u64));
+        return Upcoming { item, is_interstitial, start_offset };
+    }
+    let trim_thresholds = state.options.lock().unwrap().trim_leading_blanks.clone();
+    let start_offset = match trim_thresholds {
+        Some(thresholds) if !is_interstitial => {
+            match analysis::detect_leading_blanks(&item.uri, &thresholds) {
+                Ok(offset) => offset,
+                Err(e) => {
+                    warn!(target: LOG_PLAYBACK, "Could not analyse leading blanks, playing from the start: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    if let Some(offset) = start_offset {
+        info!(target: LOG_PLAYBACK, "Trimming {} of leading black/silence from {}", offset, redact_url(&item.uri));
+    }
+    Upcoming { item, is_interstitial, start_offset }
+}
+
+/// Records `upcoming`, now playing from `source`, as the entry on air.
+fn put_on_air(p: &gst::Pipeline, state: &PlayoutState, upcoming: &Upcoming, source: &gst::Element, fader: Option<Fader>) {
+    let mut playing_id = state.currently_playing_id.lock().unwrap();
+    // The playing id keeps pointing at the last regular item while an interstitial is on air,
+    // so the playlist resumes from the right place afterwards.
+    if !upcoming.is_interstitial {
+        *playing_id = Some(upcoming.item.id);
+    }
+    *state.on_air.lock().unwrap() = Some(OnAir {
+        source: source.name().to_string(),
+        id: upcoming.item.id,
+        uri: upcoming.item.uri.clone(),
+        title: None,
+        generation: state.transitions.lock().unwrap().finish(),
+        cut_after: upcoming.item.cut_after(),
+        started: p.current_running_time(),
+        fader,
+    });
+    debug!(target: LOG_PLAYBACK, "play_next: Transition complete. New playing ID: {:?}", *playing_id);
+    drop(playing_id);
+    if state.fallback.lock().unwrap().take().is_some() {
+        info!(target: LOG_PLAYBACK, "Back from the fallback.");
+        state.events.emit(PlayoutEvent::FallbackEnded);
+    }
+    state.events.emit(PlayoutEvent::TitleChanged { title: title_from_uri(&upcoming.item.uri) });
+}
+
+/// Notes `title`, found in the tags of `source_name`. A source not on air yet, i.e. a
+/// preloaded one, has it kept in `early` until it is.
+fn record_title(state: &PlayoutState, source_name: &str, title: &str, early: &mut HashMap<String, String>) {
+    let changed = {
+        let mut on_air = state.on_air.lock().unwrap();
+        match on_air.as_mut().filter(|on_air| on_air.source == source_name) {
+            Some(on_air) => {
+                let changed = on_air.title.as_deref() != Some(title);
+                on_air.title = Some(title.to_string());
+                changed
+            }
+            None => {
+                early.insert(source_name.to_string(), title.to_string());
+                false
+            }
+        }
+    };
+    if changed {
+        info!(target: LOG_PLAYBACK, "Now playing: {}", title);
+        state.events.emit(PlayoutEvent::TitleChanged { title: title.to_string() });
+    }
+}
+
+/// Hands a title read while its source was preloaded to the entry on air, and forgets those
+/// of sources that are gone.
+fn claim_early_title(p: &gst::Pipeline, state: &PlayoutState, early: &mut HashMap<String, String>) {
+    let source = state.on_air.lock().unwrap().as_ref()
+        .filter(|on_air| on_air.title.is_none())
+        .map(|on_air| on_air.source.clone());
+    if let Some(source) = source {
+        if let Some(title) = early.remove(&source) {
+            record_title(state, &source, &title, early);
+        }
+    }
+    early.retain(|source, _| p.by_name(source).is_some());
+}
+
+fn play_next(
+    p: &gst::Pipeline,
+    vs: &gst::Element,
+    as_: &gst::Element,
+    state: &PlayoutState,
+    element_to_remove: Option<gst::Element>,
+) -> Result<()> {
+    debug!(target: LOG_PLAYBACK, "play_next: Starting transition.");
+    match decide_next(state)? {
+        Decision::Play(upcoming) => play_upcoming(p, vs, as_, state, &upcoming, element_to_remove),
+        Decision::Stop { stop_requested } => {
+            if stop_requested {
+                info!(target: LOG_PLAYBACK, "Current item finished, stopping as requested.");
+            } else {
+                info!(target: LOG_PLAYBACK, "Playlist finished, stopping.");
+            }
+            *state.currently_playing_id.lock().unwrap() = None;
+            *state.on_air.lock().unwrap() = None;
+            teardown::run(p)?;
+            state.events.emit(if stop_requested { PlayoutEvent::StoppedAfterCurrent } else { PlayoutEvent::PlaylistEnded });
+            Ok(())
+        }
+        Decision::Fallback(uri) => enter_fallback(p, vs, as_, state, &uri, element_to_remove),
+    }
+}
+
+/// Loops `uri` in place of `element_to_remove`, with nothing on air as far as the playlist is
+/// concerned. Does nothing if the fallback is on air already.
+fn enter_fallback(
+    p: &gst::Pipeline,
+    vs: &gst::Element,
+    as_: &gst::Element,
+    state: &PlayoutState,
+    uri: &str,
+    element_to_remove: Option<gst::Element>,
+) -> Result<()> {
+    if state.fallback.lock().unwrap().is_some() {
+        return Ok(());
+    }
+    info!(target: LOG_PLAYBACK, "Playing the fallback {} until the playlist can go on.", redact_url(uri));
+    let item = PlaylistItem::new(NEXT_ID.fetch_add(1, Ordering::SeqCst), uri);
+    let source = switch_source(p, vs, as_, &item, &SourceSetup::new(SourceStart::Loop), &state.events, element_to_remove)?;
+    *state.fallback.lock().unwrap() = Some(source.name().to_string());
+    *state.currently_playing_id.lock().unwrap() = None;
+    *state.on_air.lock().unwrap() = None;
+    state.events.emit(PlayoutEvent::FallbackStarted);
+    Ok(())
+}
+
+/// Goes back to the playlist from the fallback once it has something that may be playable.
+fn leave_fallback_if_ready(p: &gst::Pipeline, state: &PlayoutState) {
+    let fallback = match state.fallback.lock().unwrap().clone() {
+        Some(fallback) => fallback,
+        None => return,
+    };
+    // Items that failed are left out, so a playlist of broken files doesn't keep coming back.
+    if !state.playlist.lock().unwrap().iter().any(|item| item.enabled && item.last_error.is_none()) {
+        return;
+    }
+    let (vs, as_) = match (p.by_name("video_selector"), p.by_name("audio_selector")) {
+        (Some(vs), Some(as_)) => (vs, as_),
+        _ => return,
+    };
+    // Still the fallback if the tag filter leaves nothing; then this tries again next time.
+    if let Err(e) = play_next(p, &vs, &as_, state, p.by_name(&fallback)) {
+        warn!(target: LOG_PLAYBACK, "Cannot leave the fallback: {}", e);
+    }
+}
+
+fn play_upcoming(
+    p: &gst::Pipeline,
+    vs: &gst::Element,
+    as_: &gst::Element,
+    state: &PlayoutState,
+    upcoming: &Upcoming,
+    element_to_remove: Option<gst::Element>,
+) -> Result<()> {
+    let setup = SourceSetup {
+        fader: new_fader(state),
+        video_fill: state.options.lock().unwrap().video_fill.clone(),
+        ..SourceSetup::new(upcoming.start_offset.map_or(SourceStart::Beginning, SourceStart::At))
+    };
+    let source = switch_source(p, vs, as_, &upcoming.item, &setup, &state.events, element_to_remove)?;
+    put_on_air(p, state, upcoming, &source, setup.fader);
+    Ok(())
+}
+
+/// A fader for a new source, if audio fades are on.
+fn new_fader(state: &PlayoutState) -> Option<Fader> {
+    let fade = state.options.lock().unwrap().audio_fade;
+    (!fade.is_zero()).then(|| Fader::new(gst::ClockTime::from_nseconds(fade.as_nanos() as u64)))
+}
+
+/// Fades out the entry on air once it is within the fade duration of its end, so it is
+/// silent by the time the next one cuts in.
+fn fade_out_if_due(p: &gst::Pipeline, state: &PlayoutState) {
+    let on_air = match state.on_air.lock().unwrap().clone() {
+        Some(on_air) => on_air,
+        None => return,
+    };
+    let fader = match on_air.fader {
+        Some(ref fader) if !fader.is_fading_out() => fader,
+        _ => return,
+    };
+    // A held or repeated item starts again from the top, so it still fades like any other.
+    if time_left(p, &on_air).map_or(false, |left| left <= fader.duration()) {
+        fader.fade_out();
+    }
+}
+
+/// Decides on and builds the entry after the one on air once that is within the preload lead
+/// of its end. Each source on air is only considered once, tracked in `checked`.
+fn preload_if_due(p: &gst::Pipeline, state: &PlayoutState, checked: &mut Option<String>) -> Option<Preloaded> {
+    let lead = state.options.lock().unwrap().preload_lead?;
+    let on_air = state.on_air.lock().unwrap().clone()?;
+    if checked.as_deref() == Some(on_air.source.as_str()) {
+        return None;
+    }
+    {
+        // Both are decided when the item ends, not ahead of time.
+        let sequencer = state.sequencer.lock().unwrap();
+        if sequencer.stop_after_current() || sequencer.is_held() {
+            return None;
+        }
+    }
+    if time_left(p, &on_air)?.nseconds() > lead.as_nanos() as u64 {
+        return None;
+    }
+    *checked = Some(on_air.source.clone());
+
+    // Only peeked at: the sequencer moves on once the entry really goes on air.
+    let upcoming = match peek_next(state) {
+        Ok(Decision::Play(upcoming)) => upcoming,
+        // Left to the end of the item, which decides the same way.
+        Ok(Decision::Stop { .. }) | Ok(Decision::Fallback(_)) => return None,
+        Err(e) => {
+            warn!(target: LOG_PLAYBACK, "Cannot preload the next item: {}", e);
+            return None;
+        }
+    };
+    info!(target: LOG_PLAYBACK, "Preloading {}", redact_url(&upcoming.item.uri));
+    let vs = p.by_name("video_selector")?;
+    let as_ = p.by_name("audio_selector")?;
+    let setup = SourceSetup {
+        blocker: Some(Blocker::default()),
+        fader: new_fader(state),
+        video_fill: state.options.lock().unwrap().video_fill.clone(),
+        ..SourceSetup::new(upcoming.start_offset.map_or(SourceStart::Beginning, SourceStart::At))
+    };
+    let source = add_source(p, &vs, &as_, &upcoming.item, &setup, &state.events)
+        .and_then(|source| {
+            source.sync_state_with_parent()?;
+            Ok((source, setup))
+        });
+    let source = match source {
+        Ok(source) => Some(source),
+        Err(e) => {
+            // The decision stands; the source is built the usual way at the end of the item.
+            error!(target: LOG_PLAYBACK, "Failed to preload {}: {}", redact_url(&upcoming.item.uri), e);
+            None
+        }
+    };
+    Some(Preloaded { follows: on_air.source, upcoming, source })
+}
+
+/// How long until `on_air` ends: at the end of the file or at its set duration, whichever
+/// comes first. `None` if neither is known yet.
+fn time_left(p: &gst::Pipeline, on_air: &OnAir) -> Option<gst::ClockTime> {
+    let until_end = p.by_name(&on_air.source)
+        .and_then(|source| source.src_pads().into_iter().find(|pad| pad.is_linked()))
+        .and_then(|pad| {
+            let position = pad.query_position::<gst::ClockTime>()?;
+            Some(pad.query_duration::<gst::ClockTime>()?.saturating_sub(position))
+        });
+    let until_cut = match (on_air.cut_after, on_air.started, p.current_running_time()) {
+        (Some(cut_after), Some(started), Some(now)) => {
+            let elapsed = now.saturating_sub(started).nseconds();
+            Some(gst::ClockTime::from_nseconds((cut_after.as_nanos() as u64).saturating_sub(elapsed)))
+        }
+        _ => None,
+    };
+    match (until_end, until_cut) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// The generation of `source_name` if it is the source on air.
+fn on_air_generation(state: &PlayoutState, source_name: &str) -> Option<u64> {
+    state.on_air.lock().unwrap().as_ref()
+        .filter(|on_air| on_air.source == source_name)
+        .map(|on_air| on_air.generation)
+}
+
+/// Name of the playlist source `object` is part of, if any.
+fn source_containing(object: &gst::Object) -> Option<String> {
+    let mut current = Some(object.clone());
+    while let Some(object) = current {
+        if object.name().starts_with(SOURCE_NAME_PREFIX) {
+            return Some(object.name().to_string());
+        }
+        current = object.parent();
+    }
+    None
+}
+
+/// The entry on air played to its end or its set duration: it is no longer marked as failed,
+/// and the run of failures is over.
+fn played_through(state: &PlayoutState, failures: &mut u32) {
+    *failures = 0;
+    let id = match state.on_air.lock().unwrap().as_ref() {
+        Some(on_air) => on_air.id,
+        None => return,
+    };
+    if let Some(item) = state.playlist.lock().unwrap().iter_mut().find(|item| item.id == id) {
+        item.last_error = None;
+    }
+}
+
+// The input selectors of a running stream's pipeline; `None`, with a warning, should they be gone.
+fn selectors(p: &gst::Pipeline) -> Option<(gst::Element, gst::Element)> {
+    let selectors = p.by_name("video_selector").zip(p.by_name("audio_selector"));
+    if selectors.is_none() {
+        warn!(target: LOG_PLAYBACK, "The pipeline has no input selectors, leaving the sources as they are.");
+    }
+    selectors
+}
+
+/// Handles `error` from the playlist source named `source`. A failed preload is dropped, to be
+/// built again when it is due; an entry on air is marked as failed and skipped, unless too
+/// many have failed in a row. Returns whether the stream is still running.
+fn on_source_error(
+    p: &gst::Pipeline,
+    state: &PlayoutState,
+    source: &str,
+    error: &str,
+    failures: &mut u32,
+    preloaded: &mut Option<Preloaded>,
+    dissolver: &mut Option<Dissolver>,
+) -> bool {
+    let (vs, as_) = match selectors(p) {
+        Some(selectors) => selectors,
+        None => return true,
+    };
+    if let Some(next) = preloaded.as_mut() {
+        if next.source.as_ref().map_or(false, |(elem, _)| elem.name() == source) {
+            warn!(target: LOG_PLAYBACK, "Preloaded {} failed, building it again when due.", redact_url(&next.upcoming.item.uri));
+            if let Some((elem, _)) = next.source.take() {
+                remove_source(p, &vs, &as_, elem);
+            }
+            return true;
+        }
+    }
+    let (generation, id, uri) = match state.on_air.lock().unwrap().as_ref().filter(|on_air| on_air.source == source) {
+        Some(on_air) => (on_air.generation, on_air.id, on_air.uri.clone()),
+        None => {
+            debug!(target: LOG_PLAYBACK, "Ignoring error of {}, it is not on air.", source);
+            return true;
+        }
+    };
+    if let Some(item) = state.playlist.lock().unwrap().iter_mut().find(|item| item.id == id) {
+        item.last_error = Some(error.to_string());
+    }
+    state.events.emit(PlayoutEvent::ItemFailed { id, uri: redact_url(&uri), error: error.to_string() });
+    *failures += 1;
+
+    let (limit, fallback_uri) = {
+        let options = state.options.lock().unwrap();
+        (options.max_consecutive_failures, options.fallback_uri.clone())
+    };
+    if *failures < limit {
+        warn!(target: LOG_PLAYBACK, "{} failed, skipping it ({} in a row).", redact_url(&uri), failures);
+        advance(p, state, generation, None, preloaded, dissolver);
+        return p.current_state() != gst::State::Null;
+    }
+
+    if !state.transitions.lock().unwrap().try_begin(generation) {
+        return true;
+    }
+    warn!(target: LOG_PLAYBACK, "{} items in a row failed to play, giving up on the playlist.", failures);
+    state.events.emit(PlayoutEvent::TooManyFailures { count: *failures });
+    *failures = 0;
+    if let Some(stale) = preloaded.take() {
+        discard_preloaded(p, &vs, &as_, stale);
+    }
+    if let Some(uri) = fallback_uri {
+        let playing_id = *state.currently_playing_id.lock().unwrap();
+        match enter_fallback(p, &vs, &as_, state, &uri, p.by_name(source)) {
+            Ok(()) => {
+                // Carry on after the failed run, not from the top, once the fallback is left.
+                *state.currently_playing_id.lock().unwrap() = playing_id;
+                return true;
+            }
+            Err(e) => error!(target: LOG_PLAYBACK, "Cannot play the fallback either: {}", e),
+        }
+    }
+    *state.currently_playing_id.lock().unwrap() = None;
+    *state.on_air.lock().unwrap() = None;
+    if let Err(e) = teardown::run(p) {
+        error!(target: LOG_PLAYBACK, "Failed to stop: {}", e);
+    }
+    false
+}
+
+/// The generation on air, if its item has played for its set duration.
+fn cutover_due(p: &gst::Pipeline, state: &PlayoutState) -> Option<u64> {
+    let mut on_air = state.on_air.lock().unwrap();
+    let on_air = on_air.as_mut()?;
+    let cut_after = on_air.cut_after?;
+    let now = p.current_running_time()?;
+    let started = *on_air.started.get_or_insert(now);
+    (now.saturating_sub(started).nseconds() >= cut_after.as_nanos() as u64).then_some(on_air.generation)
+}
+
+/// Moves on from the entry of `generation`, which ended, was cut or skipped: to `target` if
+/// given, else to the preloaded entry if that still holds, otherwise to whatever the sequencer
+/// picks now. Does nothing if another trigger already moved on from it.
+fn advance(
+    p: &gst::Pipeline,
+    state: &PlayoutState,
+    generation: u64,
+    target: Option<Upcoming>,
+    preloaded: &mut Option<Preloaded>,
+    dissolver: &mut Option<Dissolver>,
+) {
+    // Looked up before claiming the transition, which nothing would hand back otherwise.
+    let (vs, as_) = match selectors(p) {
+        Some(selectors) => selectors,
+        None => return,
+    };
+    if !state.transitions.lock().unwrap().try_begin(generation) {
+        debug!(target: LOG_PLAYBACK, "Transition from entry {} already handled, ignoring.", generation);
+        return;
+    }
+    // Taken before the switch, so it is still the outgoing picture.
+    let outgoing = dissolver.as_ref().and_then(|dissolver| dissolver.outgoing_frame());
+    let old_src_name = match state.on_air.lock().unwrap().as_ref() {
+        Some(on_air) => on_air.source.clone(),
+        None => return,
+    };
+    let old_src = p.by_name(&old_src_name);
+
+    let result = match (target, preloaded.take()) {
+        (Some(target), stale) => {
+            if let Some(stale) = stale {
+                discard_preloaded(p, &vs, &as_, stale);
+            }
+            play_upcoming(p, &vs, &as_, state, &target, old_src)
+        }
+        (None, Some(next)) if next.follows == old_src_name && preload_still_valid(state, &next) => {
+            play_preloaded(p, &vs, &as_, state, next, old_src)
+        }
+        (None, stale) => {
+            if let Some(stale) = stale {
+                discard_preloaded(p, &vs, &as_, stale);
+            }
+            play_next(p, &vs, &as_, state, old_src)
+        }
+    };
+    if let Err(e) = result {
+        error!(target: LOG_PLAYBACK, "Failed to play next: {}", e);
+        state.transitions.lock().unwrap().cancel();
+        let fallback_uri = state.options.lock().unwrap().fallback_uri.clone();
+        if let Some(uri) = fallback_uri {
+            if let Err(e) = enter_fallback(p, &vs, &as_, state, &uri, p.by_name(&old_src_name)) {
+                error!(target: LOG_PLAYBACK, "Cannot play the fallback either: {}", e);
+            }
+        }
+        return;
+    }
+    if let (Some(dissolver), Some(frame)) = (dissolver.as_mut(), outgoing) {
+        dissolve_if_chosen(p, state, dissolver, &frame);
+    }
+}
+
+/// Dissolves from `frame` into the entry that just went on air, if dissolves are still chosen.
+fn dissolve_if_chosen(p: &gst::Pipeline, state: &PlayoutState, dissolver: &mut Dissolver, frame: &gst::Sample) {
+    let duration = match state.options.lock().unwrap().transition_style {
+        TransitionStyle::Dissolve { duration } if !duration.is_zero() => duration,
+        _ => return,
+    };
+    if state.on_air.lock().unwrap().is_none() {
+        return;
+    }
+    if let Err(e) = dissolver.begin(p, frame, duration) {
+        warn!(target: LOG_PLAYBACK, "Cannot dissolve, cutting instead: {}", e);
+    }
+}
+
+/// Whether a preload decided earlier still holds now that its predecessor has ended.
+fn preload_still_valid(state: &PlayoutState, preloaded: &Preloaded) -> bool {
+    {
+        let sequencer = state.sequencer.lock().unwrap();
+        if sequencer.stop_after_current() || sequencer.is_held() {
+            return false;
+        }
+    }
+    let playlist = state.playlist.lock().unwrap();
+    let playing_id = *state.currently_playing_id.lock().unwrap();
+    let upcoming = &preloaded.upcoming;
+    match state.sequencer.lock().unwrap().peek(&playlist, playing_id) {
+        Some(NextEntry::Item(index)) => !upcoming.is_interstitial && playlist[index].id == upcoming.item.id,
+        Some(NextEntry::Interstitial(uri)) => upcoming.is_interstitial && uri == upcoming.item.uri,
+        Some(NextEntry::Stop) | None => false,
+    }
+}
+
+/// Puts a preloaded entry on air in place of `old_source`, which has just ended.
+fn play_preloaded(
+    p: &gst::Pipeline,
+    vs: &gst::Element,
+    as_: &gst::Element,
+    state: &PlayoutState,
+    preloaded: Preloaded,
     old_source: Option<gst::Element>,
 ) -> Result<()> {
-    println!("[DEBUG] switch_source: Creating new source for: {}", item.uri);
+    {
+        // Decided with a peek, which still holds; only now does the sequencer move on.
+        let playlist = state.playlist.lock().unwrap();
+        let playing_id = *state.currently_playing_id.lock().unwrap();
+        state.sequencer.lock().unwrap().next(&playlist, playing_id);
+    }
+    let (source, setup) = match preloaded.source {
+        Some(source) => source,
+        None => return play_upcoming(p, vs, as_, state, &preloaded.upcoming, old_source),
+    };
+    info!(target: LOG_PLAYBACK, "Switching to preloaded {}", redact_url(&preloaded.upcoming.item.uri));
+    for selector in [vs, as_] {
+        if standby::is_on_air(selector) {
+            continue;
+        }
+        if let Some(pad) = selector::pads_fed_by(selector, &source).into_iter().next() {
+            selector.set_property("active-pad", &pad);
+        }
+    }
+    if let Some(blocker) = &setup.blocker {
+        blocker.release();
+    }
+    if let Some(old_elem) = old_source {
+        remove_source(p, vs, as_, old_elem);
+    }
+    put_on_air(p, state, &preloaded.upcoming, &source, setup.fader);
+    Ok(())
+}
+
+/// Throws away a preloaded source that is no longer wanted, without letting it play.
+fn discard_preloaded(p: &gst::Pipeline, vs: &gst::Element, as_: &gst::Element, preloaded: Preloaded) {
+    if let Some((source, _)) = preloaded.source {
+        info!(target: LOG_PLAYBACK, "Dropping preloaded {}, the playout changed.", redact_url(&preloaded.upcoming.item.uri));
+        remove_source(p, vs, as_, source);
+    }
+}
+
+/// Where a new source starts, and whether it ever ends.
+#[derive(Clone, Copy)]
+enum SourceStart {
+    Beginning,
+    At(gst::ClockTime),
+    /// From the beginning, looping forever instead of reporting EOS to the playlist.
+    Loop,
+}
+
+/// How a new source starts, and what is attached to its pads as they appear.
+struct SourceSetup {
+    start: SourceStart,
+    /// Holds the new inputs back, inactive, until released.
+    blocker: Option<Blocker>,
+    /// Fades the audio in from silence.
+    fader: Option<Fader>,
+    /// Shown if the source has no video.
+    video_fill: FillPicture,
+}
+
+impl SourceSetup {
+    fn new(start: SourceStart) -> Self {
+        Self { start, blocker: None, fader: None, video_fill: FillPicture::default() }
+    }
+}
+
+fn switch_source(
+    pipeline: &gst::Pipeline,
+    v_selector: &gst::Element,
+    a_selector: &gst::Element,
+    item: &PlaylistItem,
+    setup: &SourceSetup,
+    events: &Arc<EventBus>,
+    old_source: Option<gst::Element>,
+) -> Result<gst::Element> {
+    let source_elem = add_source(pipeline, v_selector, a_selector, item, setup, events)?;
+
+    // Clean up old source
+    if let Some(old_elem) = old_source {
+        remove_source(pipeline, v_selector, a_selector, old_elem);
+    }
     
-    // FIXED: Use uridecodebin instead of rtmpsink
-    let source_elem = gst::ElementFactory::make("uridecodebin")
-        .name(&format!("source_elem_{}", item.id))
-        .build()?;
-    source_elem.set_property("uri", &item.uri);  // FIXED: Use "uri" property
+    source_elem.sync_state_with_parent()?;
+    debug!(target: LOG_SOURCE, "switch_source: New source '{}' is now synchronized.", redact_url(&item.uri));
+    Ok(source_elem)
+}
+
+/// Adds the source for `item`, linking its pads to the selectors as they appear. With a
+/// blocker in `setup` the new inputs are held back and left inactive until it is released.
+/// The caller brings the source to the pipeline's state.
+fn add_source(
+    pipeline: &gst::Pipeline,
+    v_selector: &gst::Element,
+    a_selector: &gst::Element,
+    item: &PlaylistItem,
+    setup: &SourceSetup,
+    events: &Arc<EventBus>,
+) -> Result<gst::Element> {
+    debug!(target: LOG_SOURCE, "switch_source: Creating new source for: {}", redact_url(&item.uri));
+    
+    let source_elem = decode::create_source(item)?;
 
     pipeline.add(&source_elem)?;
+    let start = setup.start;
+    match start {
+        SourceStart::Beginning => (),
+        SourceStart::At(offset) => seek::start_at_offset(&source_elem, offset),
+        SourceStart::Loop => seek::loop_from_start(&source_elem),
+    }
     
     let v_selector_clone = v_selector.clone();
     let a_selector_clone = a_selector.clone();
     let bus = pipeline.bus().unwrap();
     let source_name = source_elem.name().to_string();
-    
+    let events = events.clone();
+    let uri = redact_url(&item.uri);
+    let blocker = setup.blocker.clone();
+    let fader = setup.fader.clone();
+    let has_video = Arc::new(AtomicBool::new(false));
+    let has_audio = Arc::new(AtomicBool::new(false));
+
+    {
+        let v_selector = v_selector.clone();
+        let a_selector = a_selector.clone();
+        let blocker = blocker.clone();
+        let fader = fader.clone();
+        let video_fill = setup.video_fill.clone();
+        let has_video = has_video.clone();
+        let has_audio = has_audio.clone();
+        let events = events.clone();
+        let uri = uri.clone();
+        source_elem.connect_no_more_pads(move |src| {
+            let mut filled = Ok(());
+            if !has_video.load(Ordering::SeqCst) {
+                info!(target: LOG_SOURCE, "{} has no video, filling in a picture.", uri);
+                filled = fill::add_picture(src, &video_fill)
+                    .and_then(|fill| connect_fill(fill, &v_selector, blocker.as_ref(), None));
+            }
+            if !has_audio.load(Ordering::SeqCst) {
+                info!(target: LOG_SOURCE, "{} has no audio, filling in silence.", uri);
+                filled = filled.and(fill::add_silence(src)
+                    .and_then(|fill| connect_fill(fill, &a_selector, blocker.as_ref(), fader.as_ref())));
+            }
+            if let Err(e) = filled {
+                error!(target: LOG_SOURCE, "Failed to fill in for {}: {}", src.name(), e);
+                events.emit(PlayoutEvent::SourceLinkFailed { uri: uri.clone(), error: e.to_string() });
+            }
+        });
+    }
+
     source_elem.connect_pad_added(move |_src, pad| {
-        println!("[DEBUG] pad-added: Fired for pad '{}'", pad.name());
-        if let Some(caps) = pad.current_caps() {
-            if let Some(s) = caps.structure(0) {
-                let media_type = s.name();
-                println!("[DEBUG] pad-added: Media type is '{}'", media_type);
-                
-                if media_type.starts_with("video/") {
-                    let sink_pad = v_selector_clone.request_pad_simple("sink_%u").unwrap();
-                    println!("[DEBUG] pad-added: Linking video pad to selector pad '{}'", sink_pad.name());
-                    if let Err(e) = pad.link(&sink_pad) { 
-                        eprintln!("[hayai] Failed to link video pad: {}", e); 
-                    } else { 
-                        v_selector_clone.set_property("active-pad", &sink_pad); 
+        debug!(target: LOG_SOURCE, "pad-added: Fired for pad '{}'", pad.name());
+        if pad.current_caps().map_or(false, |caps| caps.structure(0).is_some()) {
+            let held_back = blocker.as_ref().map_or(false, |blocker| blocker.block(pad));
+            match selector::link_source_to_selectors(pad, &v_selector_clone, &a_selector_clone) {
+                Ok(Some((selector, sink_pad))) => {
+                    if selector == v_selector_clone {
+                        has_video.store(true, Ordering::SeqCst);
+                    }
+                    if selector == a_selector_clone {
+                        has_audio.store(true, Ordering::SeqCst);
+                        if let Some(fader) = fader.as_ref() {
+                            fader.attach(pad);
+                        }
                     }
-                } else if media_type.starts_with("audio/") {
-                    let sink_pad = a_selector_clone.request_pad_simple("sink_%u").unwrap();
-                    println!("[DEBUG] pad-added: Linking audio pad to selector pad '{}'", sink_pad.name());
-                    if let Err(e) = pad.link(&sink_pad) { 
-                        eprintln!("[hayai] Failed to link audio pad: {}", e); 
-                    } else { 
-                        a_selector_clone.set_property("active-pad", &sink_pad); 
+                    if !held_back && !standby::is_on_air(&selector) {
+                        selector.set_property("active-pad", &sink_pad);
                     }
                 }
-                
-                // CRITICAL: Add EOS detection probe
-                let bus_clone = bus.clone();
-                let source_name_clone = source_name.clone();
-                pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |_, probe_info| {
-                    if let Some(gst::PadProbeData::Event(event)) = &probe_info.data {
-                        if event.type_() == gst::EventType::Eos {
-                            println!("[hayai] Pad probe detected EOS for {}!", source_name_clone);
-                            let s = gst::Structure::builder("hayai-playlist-eos")
+                Ok(None) => (),
+                Err(e) => {
+                    error!(target: LOG_SOURCE, "Failed to link {} of {}: {}", pad.name(), source_name, e);
+                    events.emit(PlayoutEvent::SourceLinkFailed { uri: uri.clone(), error: e.to_string() });
+                }
+            }
+
+            // A looping source has no next item to hand over to.
+            if matches!(start, SourceStart::Loop) {
+                return;
+            }
+
+            // CRITICAL: Add EOS detection probe
+            let bus_clone = bus.clone();
+            let source_name_clone = source_name.clone();
+            pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |_, probe_info| {
+                if let Some(gst::PadProbeData::Event(event)) = &probe_info.data {
+                    if event.type_() == gst::EventType::Eos {
+                        info!(target: LOG_SOURCE, "Pad probe detected EOS for {}!", source_name_clone);
+                        let s = gst::Structure::builder("hayai-playlist-eos")
+                            .field("source-name", &source_name_clone)
+                            .build();
+                        let msg = gst::message::Application::new(s);
+                        let _ = bus_clone.post(msg);
+                    } else if let gst::EventView::Tag(tag) = event.view() {
+                        // Tag messages on the bus come from the output, not from the source
+                        // that carried them, so the title is picked up here instead.
+                        if let Some(title) = tag.tag().get::<gst::tags::Title>() {
+                            let s = gst::Structure::builder("hayai-title")
                                 .field("source-name", &source_name_clone)
+                                .field("title", title.get())
                                 .build();
-                            let msg = gst::message::Application::new(s);
-                            let _ = bus_clone.post(msg);
+                            let _ = bus_clone.post(gst::message::Application::new(s));
                         }
                     }
-                    gst::PadProbeReturn::Ok
-                });
-            }
+                }
+                gst::PadProbeReturn::Ok
+            });
         }
     });
+    Ok(source_elem)
+}
 
-    // Clean up old source
-    if let Some(old_elem) = old_source {
-        println!("[DEBUG] switch_source: Scheduling cleanup for old element: {}", old_elem.name());
-        let pipeline_clone = pipeline.clone();
-        let v_selector_clone = v_selector.clone();
-        let a_selector_clone = a_selector.clone();
-        
-        pipeline.call_async(move |_| {
-            println!("[DEBUG] call_async: Now cleaning up old element '{}'", old_elem.name());
-            
-            let _ = old_elem.set_state(gst::State::Null);
-            
-            // Release selector pads
-            let release_pads = |selector: &gst::Element, element_to_remove: &gst::Element| {
-                for pad in selector.sink_pads() {
-                    if let Some(peer) = pad.peer() {
-                        if peer.parent_element().as_ref() == Some(element_to_remove) {
-                            println!("[DEBUG] call_async: Releasing selector pad '{}'", pad.name());
-                            selector.release_request_pad(&pad);
-                        }
-                    }
-                }
-            };
-            release_pads(&v_selector_clone, &old_elem);
-            release_pads(&a_selector_clone, &old_elem);
-            
-            let _ = pipeline_clone.remove(&old_elem);
-        });
+/// Links a fill made by [`fill::add_silence`] or [`fill::add_picture`] to `selector`, set up
+/// like the stream the source could have had, and starts it.
+fn connect_fill(
+    (fill, pad): (gst::Element, gst::Pad),
+    selector: &gst::Element,
+    blocker: Option<&Blocker>,
+    fader: Option<&Fader>,
+) -> Result<()> {
+    let sink_pad = selector::link_to_selector(&pad, selector)?;
+    let held_back = blocker.map_or(false, |blocker| blocker.block(&pad));
+    if let Some(fader) = fader {
+        fader.attach(&pad);
     }
-    
-    source_elem.sync_state_with_parent()?;
-    println!("[DEBUG] switch_source: New source '{}' is now synchronized.", item.uri);
+    if !held_back && !standby::is_on_air(selector) {
+        selector.set_property("active-pad", &sink_pad);
+    }
+    fill.sync_state_with_parent()?;
     Ok(())
 }
 
+/// Takes `old_elem` out of the pipeline and frees its selector inputs, from a safe context.
+fn remove_source(pipeline: &gst::Pipeline, v_selector: &gst::Element, a_selector: &gst::Element, old_elem: gst::Element) {
+    debug!(target: LOG_SOURCE, "switch_source: Scheduling cleanup for old element: {}", old_elem.name());
+    let pipeline_clone = pipeline.clone();
+    let v_selector_clone = v_selector.clone();
+    let a_selector_clone = a_selector.clone();
+    
+    pipeline.call_async(move |_| {
+        debug!(target: LOG_SOURCE, "call_async: Now cleaning up old element '{}'", old_elem.name());
+        
+        let _ = old_elem.set_state(gst::State::Null);
+        selector::release_source_pads(&v_selector_clone, &old_elem);
+        selector::release_source_pads(&a_selector_clone, &old_elem);
+        
+        let _ = pipeline_clone.remove(&old_elem);
+    });
+}
+
 impl Drop for Streamer {
     fn drop(&mut self) {
         if self.pipeline.is_some() { 
             let _ = self.stop(); 
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_item_is_the_entry_on_air() {
+        let streamer = Streamer::new().unwrap();
+        streamer.add_item("A");
+        let id = streamer.add_item("B");
+        *streamer.currently_playing_id.lock().unwrap() = Some(id);
+
+        let current = streamer.current_item().expect("The entry on air");
+        assert_eq!(current.id, id);
+        assert_eq!(current.uri, "B");
+
+        streamer.remove_item(id);
+        assert!(streamer.current_item().is_none(), "A removed entry is not reported");
+    }
+}
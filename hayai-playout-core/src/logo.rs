@@ -0,0 +1,72 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::LOG_OUTPUT;
+
+/// Where on the picture the logo sits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Corner {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    fn is_right(self) -> bool {
+        matches!(self, Self::TopRight | Self::BottomRight)
+    }
+
+    fn is_bottom(self) -> bool {
+        matches!(self, Self::BottomLeft | Self::BottomRight)
+    }
+}
+
+// Offset from the left or top edge as (fraction of the picture, fraction of the logo, sign of
+// the margin); `far` puts the logo against the right or bottom edge instead.
+fn placement(far: bool) -> (f64, f64, i32) {
+    if far { (1.0, -1.0, -1) } else { (0.0, 0.0, 1) }
+}
+
+/// An image laid over the program, `margin` pixels in from `corner`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Logo {
+    pub(crate) path: PathBuf,
+    pub(crate) corner: Corner,
+    pub(crate) margin: u32,
+}
+
+/// Shows `logo` on the `gdkpixbufoverlay` in the processing bin, or hides it with `None`.
+/// The overlay stays in place either way, so this works while streaming. The image's own
+/// alpha channel is kept, so transparent PNGs blend as drawn.
+pub(crate) fn apply(overlay: &gst::Element, logo: Option<&Logo>) -> Result<()> {
+    let logo = match logo {
+        Some(logo) => logo,
+        None => {
+            overlay.set_property("alpha", 0.0f64);
+            return Ok(());
+        }
+    };
+    let location = logo.path.to_str()
+        .ok_or_else(|| anyhow!("Logo path {} is not valid UTF-8", logo.path.display()))?;
+    let margin = i32::try_from(logo.margin).map_err(|_| anyhow!("Logo margin {} is too large", logo.margin))?;
+    let (relative_x, coef_x, sign_x) = placement(logo.corner.is_right());
+    let (relative_y, coef_y, sign_y) = placement(logo.corner.is_bottom());
+
+    overlay.set_property("location", location);
+    overlay.set_property_from_str("positioning-mode", "pixels-absolute");
+    overlay.set_property("relative-x", relative_x);
+    overlay.set_property("coef-x", coef_x);
+    overlay.set_property("offset-x", sign_x * margin);
+    overlay.set_property("relative-y", relative_y);
+    overlay.set_property("coef-y", coef_y);
+    overlay.set_property("offset-y", sign_y * margin);
+    overlay.set_property("alpha", 1.0f64);
+    info!(target: LOG_OUTPUT, "Showing logo {} ({:?}, {} px margin).", logo.path.display(), logo.corner, logo.margin);
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use gstreamer as gst;
+
+use crate::PlayoutEvent;
+
+/// The lowest reading the audio meter reports, in dBFS. Digital silence comes out as this
+/// rather than minus infinity.
+pub const LEVEL_FLOOR_DB: f64 = -100.0;
+
+/// Reads a message of the `level` element into [`PlayoutEvent::AudioLevels`]; `None` for
+/// anything else.
+pub fn audio_levels(structure: &gst::StructureRef) -> Option<PlayoutEvent> {
+    if structure.name() != "level" {
+        return None;
+    }
+    let channels = |field: &str| -> Option<Vec<f64>> {
+        let values = structure.get::<gst::glib::ValueArray>(field).ok()?;
+        // `max` also takes NaN to the floor.
+        Some(values.iter().map(|value| value.get::<f64>().map_or(LEVEL_FLOOR_DB, |db| db.max(LEVEL_FLOOR_DB))).collect())
+    };
+    Some(PlayoutEvent::AudioLevels { peak_db: channels("peak")?, rms_db: channels("rms")? })
+}
@@ -1,30 +1,278 @@
+use anyhow::Result;
+use gstreamer as gst;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::pipeline::require_factory;
+use crate::{LatencyProfile, StreamerError};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct PlaylistItem {
-    pub id: u64,
+pub struct PlaylistItem { 
+    pub id: u64, 
     pub uri: String,
+    /// Disabled items stay in the playlist but are skipped.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// Free-form categories, e.g. "ident" or "music", for tag-filtered playout.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Decode without hardware decoders, for files that crash them.
+    #[serde(default)]
+    pub force_software_decode: bool,
+    /// Cuts to the next item after this long even if the item hasn't ended, e.g. for live
+    /// URLs that never do. `None` plays the item to its end.
+    #[serde(default)]
+    pub duration: Option<Duration>,
+    /// Plays at most this much of the item, counted from `start_offset`, e.g. the first few
+    /// seconds of each clip in a highlight reel. Shorter files still end by themselves; with
+    /// `duration` also set, the earlier cut wins.
+    #[serde(default)]
+    pub max_duration: Option<Duration>,
+    /// Starts the item this far in, e.g. past a slate or countdown, instead of at the
+    /// beginning. With `duration`, plays just a segment of the media. An offset past the end
+    /// of the media plays it from the beginning.
+    #[serde(default)]
+    pub start_offset: Option<Duration>,
+    /// Length of the media, found by [`Streamer::probe_all`](crate::Streamer::probe_all).
+    /// `None` until probed, and for media of unknown length such as live streams.
+    #[serde(default)]
+    pub media_duration: Option<Duration>,
+    /// Why the item last failed to play, if it did; cleared once it plays through.
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub kind: ItemKind,
+}
+
+/// What sort of source a playlist item is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemKind {
+    /// A file or stream that ends by itself.
+    #[default]
+    Media,
+    /// A live feed that never ends, such as an RTSP camera; it needs a `duration` to move on.
+    ///
+    /// `latency_ms` is how long the source buffers to even out network jitter and reorder
+    /// late packets (`latency` on `rtspsrc`). Packets later than that are dropped, so a
+    /// shaky network wants more, at the cost of the picture being that much behind.
+    Live { latency_ms: u32 },
+}
+
+impl ItemKind {
+    /// Buffering for [`ItemKind::Live`] items that works for cameras on a local network.
+    pub const DEFAULT_LIVE_LATENCY_MS: u32 = 200;
+}
+
+impl PlaylistItem {
+    pub fn new(id: u64, uri: &str) -> Self {
+        Self {
+            id,
+            uri: uri.to_string(),
+            enabled: true,
+            tags: Vec::new(),
+            force_software_decode: false,
+            duration: None,
+            max_duration: None,
+            start_offset: None,
+            media_duration: None,
+            last_error: None,
+            kind: ItemKind::Media,
+        }
+    }
+
+    /// How long the item stays on air: its set duration or what the media has after the
+    /// start offset, whichever is shorter. `None` if neither is known.
+    pub fn play_length(&self) -> Option<Duration> {
+        let media_left = self.media_duration.map(|media| match self.start_offset {
+            Some(offset) if offset < media => media - offset,
+            _ => media,
+        });
+        match (self.cut_after(), media_left) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// How long the item plays before it is cut to the next one: the shorter of `duration`
+    /// and `max_duration`. `None` if it plays to its end.
+    pub fn cut_after(&self) -> Option<Duration> {
+        match (self.duration, self.max_duration) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+fn enabled_by_default() -> bool {
+    true
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EncodingSettings {
-    pub encoder: String,
+    /// Also read as `encoder`, its name in older settings files.
+    #[serde(alias = "encoder")]
+    pub video_encoder: String,
+    pub audio_encoder: String,
     pub bitrate_kbps: u32,
+    #[serde(default = "default_audio_bitrate_kbps")]
+    pub audio_bitrate_kbps: u32,
     pub speed_preset: String,
     pub scale_enabled: bool,
     pub scale_width: u32,
     pub scale_height: u32,
+    /// Output frame rate numerator; 0 keeps each source's own rate.
+    #[serde(default)]
+    pub framerate_num: u32,
+    /// Output frame rate denominator, e.g. 1001 for 30000/1001.
+    #[serde(default = "default_framerate_den")]
+    pub framerate_den: u32,
+    /// Frames between keyframes (`key-int-max` and its equivalents).
+    #[serde(default = "default_keyframe_interval")]
+    pub keyframe_interval: u32,
+    /// Keyframe interval in seconds; overrides `keyframe_interval` when set.
+    #[serde(default)]
+    pub keyframe_interval_seconds: Option<f64>,
+    #[serde(default)]
+    pub latency_profile: LatencyProfile,
+    /// Sends the program out in real time, paced by the pipeline clock (`sync` on the output
+    /// sink). Needed whenever the playlist holds files, which would otherwise be sent as fast
+    /// as they encode and run ahead of the viewers, so on by default. Turning it off removes
+    /// the pacing wait, which only suits sources that are live themselves.
+    #[serde(default = "default_live_sync")]
+    pub live_sync: bool,
+    /// With `live_sync`, how late in milliseconds a buffer may reach the output before it is
+    /// dropped rather than sent. `None` (the default) never drops: a slow machine falls behind
+    /// and sends late, keeping every frame, where a limit keeps the pace at the cost of gaps.
+    #[serde(default)]
+    pub max_lateness_ms: Option<u32>,
+    /// Also writes the encoded program to this file while streaming. `.mp4` files are
+    /// written with `mp4mux`, anything else as Matroska.
+    #[serde(default)]
+    pub record_path: Option<PathBuf>,
+    /// How often the MPEG-TS outputs (SRT and UDP) carry a PCR, in milliseconds. `None` keeps
+    /// `mpegtsmux`'s 40 ms; some hardware decoders want it more often.
+    #[serde(default)]
+    pub ts_pcr_interval_ms: Option<u32>,
+    /// Fades the audio out at the end of each item and in at the start of the next, in
+    /// milliseconds, so cuts don't pop. Skipping by hand fades the next item in but leaves
+    /// the current one straight away. 0 cuts straight over.
+    #[serde(default)]
+    pub audio_fade_ms: u32,
+    /// Evens out the loudness of the items toward `loudness_target_lufs`, following EBU R128,
+    /// with a true-peak limit so the cuts don't clip. Needs `audioloudnorm` from
+    /// gst-plugins-rs; without it only a peak limiter is added.
+    #[serde(default)]
+    pub normalize_loudness: bool,
+    /// Loudness to normalize to, in LUFS, clamped to -70 to -5. -23 (the default) is the
+    /// EBU R128 broadcast level; streaming services mostly play back at -14 to -16.
+    #[serde(default = "default_loudness_target_lufs")]
+    pub loudness_target_lufs: f64,
+}
+
+fn default_audio_bitrate_kbps() -> u32 {
+    128
+}
+
+fn default_live_sync() -> bool {
+    true
+}
+
+fn default_loudness_target_lufs() -> f64 {
+    -23.0
+}
+
+fn default_keyframe_interval() -> u32 {
+    60
+}
+
+fn default_framerate_den() -> u32 {
+    1
 }
 
 impl Default for EncodingSettings {
     fn default() -> Self {
         Self {
-            encoder: "x264enc".to_string(),
+            video_encoder: "x264enc".to_string(),
+            audio_encoder: "faac".to_string(),
             bitrate_kbps: 4000,
+            audio_bitrate_kbps: default_audio_bitrate_kbps(),
             speed_preset: "ultrafast".to_string(),
             scale_enabled: false,
             scale_width: 1920,
             scale_height: 1080,
+            framerate_num: 0,
+            framerate_den: default_framerate_den(),
+            keyframe_interval: default_keyframe_interval(),
+            keyframe_interval_seconds: None,
+            latency_profile: LatencyProfile::default(),
+            live_sync: default_live_sync(),
+            max_lateness_ms: None,
+            record_path: None,
+            ts_pcr_interval_ms: None,
+            audio_fade_ms: 0,
+            normalize_loudness: false,
+            loudness_target_lufs: default_loudness_target_lufs(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl EncodingSettings {
+    /// Lowest video bitrate accepted by [`EncodingSettings::validate`], in kbps.
+    pub const MIN_BITRATE_KBPS: u32 = 100;
+    /// Highest video bitrate accepted by [`EncodingSettings::validate`], in kbps.
+    pub const MAX_BITRATE_KBPS: u32 = 100_000;
+    /// Frame rate assumed when turning `keyframe_interval_seconds` into frames and no output
+    /// frame rate is set.
+    pub const ASSUMED_FRAMERATE: f64 = 30.0;
+
+    /// The output frame rate, or `None` to pass each source's rate through. Also `None` for a
+    /// zero denominator, which [`EncodingSettings::validate`] rejects.
+    pub fn framerate(&self) -> Option<gst::Fraction> {
+        (self.framerate_num > 0 && self.framerate_den > 0)
+            .then(|| gst::Fraction::new(self.framerate_num as i32, self.framerate_den as i32))
+    }
+
+    /// The keyframe interval in frames, from `keyframe_interval_seconds` if it is set.
+    pub fn keyframe_interval_frames(&self) -> u32 {
+        match self.keyframe_interval_seconds {
+            Some(seconds) => {
+                let fps = match self.framerate() {
+                    Some(_) => self.framerate_num as f64 / self.framerate_den as f64,
+                    None => Self::ASSUMED_FRAMERATE,
+                };
+                (seconds * fps).round() as u32
+            }
+            None => self.keyframe_interval,
+        }
+    }
+
+    /// Checks the settings before anything is built: a bitrate within range, non-zero even
+    /// scale dimensions (many encoders reject odd sizes), a usable frame rate, a keyframe
+    /// interval of at least one frame, and installed encoders.
+    ///
+    /// [`Streamer::start`](crate::Streamer::start) runs this after substituting the fallback
+    /// video encoder, so there an unknown video encoder is not an error.
+    pub fn validate(&self) -> Result<()> {
+        if !(Self::MIN_BITRATE_KBPS..=Self::MAX_BITRATE_KBPS).contains(&self.bitrate_kbps) {
+            return Err(StreamerError::InvalidBitrate { kbps: self.bitrate_kbps }.into());
+        }
+        if self.scale_enabled
+            && [self.scale_width, self.scale_height].iter().any(|&d| d == 0 || d % 2 != 0)
+        {
+            return Err(StreamerError::InvalidScale { width: self.scale_width, height: self.scale_height }.into());
+        }
+        if self.framerate_num > 0
+            && (self.framerate_den == 0 || self.framerate_num > i32::MAX as u32 || self.framerate_den > i32::MAX as u32)
+        {
+            return Err(StreamerError::InvalidFramerate { num: self.framerate_num, den: self.framerate_den }.into());
+        }
+        if self.keyframe_interval_frames() == 0 {
+            return Err(StreamerError::InvalidKeyframeInterval.into());
+        }
+        gst::init()?;
+        require_factory(&self.video_encoder)?;
+        require_factory(&self.audio_encoder)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::pipeline::{
+    check_output, link_through_mux, link_to_muxing_sink, make_mux, OutputTarget, AUDIO_SINK_PAD, AUDIO_TEE_NAME,
+    MUX_NAME, OUTPUT_SINK_NAME, VIDEO_SINK_PAD, VIDEO_TEE_NAME,
+};
+use crate::force_key_unit_event;
+
+/// Names of the bins added by [`add_branch`] start with this.
+pub(crate) const EXTRA_OUTPUT_PREFIX: &str = "extra_output_";
+
+static NEXT_BRANCH: AtomicU64 = AtomicU64::new(1);
+
+/// Hangs a muxer and sink for `target` off the tees of `bin`, a running processing bin, and
+/// returns the name of the branch.
+///
+/// The branch takes whatever the tees give it and never hands a failure back, so a
+/// destination going down cannot stop the others; its error still reaches the bus, where
+/// the branch is found with [`branch_containing`] and taken out with [`remove_branch`].
+pub(crate) fn add_branch(bin: &gst::Bin, target: &OutputTarget) -> Result<String> {
+    let sink_factory = check_output(target)?;
+    let tees = [VIDEO_TEE_NAME, AUDIO_TEE_NAME].map(|name| bin.by_name(name));
+    let (video_tee, audio_tee) = match tees {
+        [Some(video_tee), Some(audio_tee)] => (video_tee, audio_tee),
+        _ => return Err(anyhow!("This output cannot send to more than one destination")),
+    };
+    let name = format!("{}{}", EXTRA_OUTPUT_PREFIX, NEXT_BRANCH.fetch_add(1, Ordering::Relaxed));
+
+    let sink = gst::ElementFactory::make(sink_factory).name("branch_sink").build()?;
+    target.configure_sink(&sink)?;
+    // Paced like the main output, where both sinks can be.
+    if let Some(main_sink) = bin.by_name(OUTPUT_SINK_NAME).filter(|main_sink| main_sink.has_property("sync")) {
+        if sink.has_property("sync") {
+            sink.set_property("sync", main_sink.property::<bool>("sync"));
+            sink.set_property("max-lateness", main_sink.property::<i64>("max-lateness"));
+        }
+    }
+
+    let branch = gst::Bin::with_name(&name);
+    branch.add(&sink)?;
+    let queues = [gst::ElementFactory::make("queue").build()?, gst::ElementFactory::make("queue").build()?];
+    branch.add_many(&queues)?;
+    match target.mux_factory() {
+        Some(factory) => {
+            let latency = bin.by_name(MUX_NAME).map_or(0, |mux| mux.property::<u64>("latency"));
+            let mux = make_mux(factory, latency, "branch_mux")?;
+            // The PCR interval carries over between MPEG-TS outputs.
+            let both_ts = |main_mux: &gst::Element| main_mux.has_property("pcr-interval") && mux.has_property("pcr-interval");
+            if let Some(main_mux) = bin.by_name(MUX_NAME).filter(both_ts) {
+                mux.set_property("pcr-interval", main_mux.property::<u32>("pcr-interval"));
+            }
+            branch.add(&mux)?;
+            link_through_mux(&queues, &mux, &sink)?;
+        }
+        None => link_to_muxing_sink(&queues, &sink)?,
+    }
+    for (queue, pad_name) in queues.iter().zip([VIDEO_SINK_PAD, AUDIO_SINK_PAD]) {
+        let pad = gst::GhostPad::builder_with_target(&queue.static_pad("sink").unwrap())?
+            .name(pad_name)
+            .chain_function(|pad, parent, buffer| {
+                Ok(gst::ProxyPad::chain_default(pad, parent, buffer).unwrap_or(gst::FlowSuccess::Ok))
+            })
+            .chain_list_function(|pad, parent, list| {
+                Ok(gst::ProxyPad::chain_list_default(pad, parent, list).unwrap_or(gst::FlowSuccess::Ok))
+            })
+            .build();
+        branch.add_pad(&pad)?;
+    }
+
+    bin.add(&branch)?;
+    let linked = link_to_tee(&video_tee, &branch, VIDEO_SINK_PAD)
+        .and_then(|_| link_to_tee(&audio_tee, &branch, AUDIO_SINK_PAD));
+    if let Err(e) = linked {
+        remove_branch(bin, &name);
+        return Err(e);
+    }
+    branch.sync_state_with_parent()?;
+    // The new destination can only start decoding at a keyframe.
+    if let Some(pad) = branch.static_pad(VIDEO_SINK_PAD).and_then(|pad| pad.peer()) {
+        pad.send_event(force_key_unit_event());
+    }
+    Ok(name)
+}
+
+fn link_to_tee(tee: &gst::Element, branch: &gst::Bin, pad_name: &str) -> Result<()> {
+    let tee_pad = tee.request_pad_simple("src_%u")
+        .ok_or_else(|| anyhow!("{} did not provide a src pad", tee.name()))?;
+    if let Err(e) = tee_pad.link(&branch.static_pad(pad_name).unwrap()) {
+        tee.release_request_pad(&tee_pad);
+        return Err(anyhow!("linking {} to {} failed: {}", tee.name(), branch.name(), e));
+    }
+    Ok(())
+}
+
+/// Unhooks the branch `name` from the tees and removes it. Safe to call from the bus thread
+/// while the stream runs; does nothing if the branch is already gone.
+pub(crate) fn remove_branch(bin: &gst::Bin, name: &str) {
+    let branch = match bin.by_name(name) {
+        Some(branch) => branch,
+        None => return,
+    };
+    let bin = bin.clone();
+    // Releasing a tee pad waits for the buffer it is pushing, so not on a streaming thread.
+    branch.call_async(move |branch| {
+        for pad_name in [VIDEO_SINK_PAD, AUDIO_SINK_PAD] {
+            let tee_pad = branch.static_pad(pad_name).and_then(|pad| pad.peer());
+            if let Some(tee) = tee_pad.as_ref().and_then(|pad| pad.parent_element()) {
+                tee.release_request_pad(tee_pad.as_ref().unwrap());
+            }
+        }
+        let _ = branch.set_state(gst::State::Null);
+        let _ = bin.remove(branch);
+    });
+}
+
+/// Name of the extra output `object` belongs to, if any.
+pub(crate) fn branch_containing(object: &gst::Object) -> Option<String> {
+    let mut current = Some(object.clone());
+    while let Some(obj) = current {
+        if obj.name().starts_with(EXTRA_OUTPUT_PREFIX) {
+            return Some(obj.name().to_string());
+        }
+        current = obj.parent();
+    }
+    None
+}
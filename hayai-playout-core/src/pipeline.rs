@@ -1,41 +1,464 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use gstreamer as gst;
 use gst::prelude::*;
+use log::{info, warn};
 
-use super::models::EncodingSettings;
+use crate::{redact_url, EncoderProfile, EncodingSettings, StartReport, StreamerError, LOG_OUTPUT};
 
-pub(crate) fn create_processing_bin(rtmp_url: &str, settings: &EncodingSettings) -> Result<gst::Bin> {
-    let bin = gst::Bin::with_name("processing_bin");
+/// Name of the bin built by [`create_processing_bin`].
+pub const PROCESSING_BIN_NAME: &str = "processing_bin";
+/// Ghost pad taking raw video into the processing bin.
+pub const VIDEO_SINK_PAD: &str = "video_sink";
+/// Ghost pad taking raw audio into the processing bin.
+pub const AUDIO_SINK_PAD: &str = "audio_sink";
+pub const VIDEO_ENCODER_NAME: &str = "video_encoder";
+pub const AUDIO_ENCODER_NAME: &str = "audio_encoder";
+/// `videorate` bringing every source to the output frame rate.
+pub const FRAME_RATE_NAME: &str = "frame_rate";
+/// `volume` setting the output level, in front of the audio encoder.
+pub const VOLUME_NAME: &str = "master_volume";
+/// `tee` sharing the encoded video between the outputs and the recording.
+pub const VIDEO_TEE_NAME: &str = "video_tee";
+/// `tee` sharing the encoded audio between the outputs and the recording.
+pub const AUDIO_TEE_NAME: &str = "audio_tee";
+pub const MUX_NAME: &str = "mux";
+pub const OUTPUT_SINK_NAME: &str = "output_sink";
+/// Muxer of the recording branch, present when `record_path` is set.
+pub const RECORD_MUX_NAME: &str = "record_mux";
+/// File sink of the recording branch, present when `record_path` is set.
+pub const RECORD_SINK_NAME: &str = "record_sink";
+/// `gdkpixbufoverlay` drawing the logo, present when that element is installed.
+pub const LOGO_OVERLAY_NAME: &str = "logo_overlay";
+/// `textoverlay` drawing the caption, present when that element is installed.
+pub const TEXT_OVERLAY_NAME: &str = "text_overlay";
+/// `clockoverlay` drawing the clock, present when that element is installed.
+pub const CLOCK_OVERLAY_NAME: &str = "clock_overlay";
+/// Name of the `level` element metering the program audio.
+pub const AUDIO_LEVEL_NAME: &str = "audio_level";
+/// How often the audio meter reports.
+const AUDIO_LEVEL_INTERVAL: Duration = Duration::from_millis(100);
+/// Name of the `tee` in front of the video encoder that feeds the preview.
+pub const PREVIEW_TEE_NAME: &str = "preview_tee";
 
-    let vqueue = gst::ElementFactory::make("queue").build()?;
+// Tried in order; the first one that is installed is used.
+const RTMP_SINK_FACTORIES: &[&str] = &["rtmpsink", "rtmp2sink"];
+// rtmpsink only does TLS if librtmp happened to be built with it, which can't be queried.
+const RTMPS_SINK_FACTORIES: &[&str] = &["rtmp2sink"];
+const SRT_SINK_FACTORIES: &[&str] = &["srtsink"];
+const HLS_SINK_FACTORIES: &[&str] = &["hlssink2", "hlssink3"];
+const UDP_SINK_FACTORIES: &[&str] = &["udpsink"];
+/// Segment file names in the HLS directory, numbered from 0.
+const HLS_SEGMENT_PATTERN: &str = "segment%05d.ts";
+/// The playlist in the HLS directory.
+pub const HLS_PLAYLIST_NAME: &str = "playlist.m3u8";
+// Segments kept on disk after they drop out of the playlist, for players still fetching them.
+const HLS_SPARE_SEGMENTS: u32 = 2;
+const FALLBACK_VIDEO_ENCODER: &str = "x264enc";
+
+/// Where the stream is sent. A bare URL converts by its scheme: `srt://` is SRT,
+/// `udp://host:port` UDP, anything else RTMP.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputTarget {
+    /// FLV over RTMP(S), e.g. `rtmp://server/app/key`.
+    Rtmp(String),
+    /// MPEG-TS over SRT, e.g. `srt://server:9000?streamid=key`.
+    Srt(String),
+    /// HLS for playback straight from a web server: MPEG-TS segments of about
+    /// `segment_duration` (whole seconds, at least one) and [`HLS_PLAYLIST_NAME`], written to
+    /// `dir`, which is created if need be.
+    ///
+    /// The playlist lists the last `playlist_length` segments and is replaced as a whole each
+    /// time, so a player never reads it half written; older segments are deleted shortly after
+    /// they drop out of it. A `playlist_length` of zero lists and keeps every segment.
+    Hls { dir: PathBuf, segment_duration: Duration, playlist_length: u32 },
+    /// MPEG-TS over plain UDP, for decoders and other broadcast gear. `host` may be a
+    /// multicast group, e.g. `239.1.1.1`, which is sent to with a TTL of 1, so it stays on
+    /// the local network.
+    UdpTs { host: String, port: u16 },
+}
+
+impl OutputTarget {
+    /// HLS to `dir` with 4 second segments and a playlist of the last 5.
+    pub fn hls(dir: impl Into<PathBuf>) -> Self {
+        Self::Hls { dir: dir.into(), segment_duration: Duration::from_secs(4), playlist_length: 5 }
+    }
+
+    /// The URL, or for HLS the directory.
+    pub fn url(&self) -> Cow<'_, str> {
+        match self {
+            Self::Rtmp(url) | Self::Srt(url) => Cow::Borrowed(url),
+            Self::Hls { dir, .. } => dir.to_string_lossy(),
+            Self::UdpTs { host, port } if host.contains(':') => Cow::Owned(format!("udp://[{}]:{}", host, port)),
+            Self::UdpTs { host, port } => Cow::Owned(format!("udp://{}:{}", host, port)),
+        }
+    }
+
+    fn scheme(&self) -> Option<String> {
+        self.url().split_once("://").map(|(scheme, _)| scheme.to_ascii_lowercase())
+    }
+
+    /// Checks that the URL scheme matches the protocol.
+    pub fn validate(&self) -> Result<(), StreamerError> {
+        let schemes: &[&str] = match self {
+            Self::Rtmp(_) => &["rtmp", "rtmps"],
+            Self::Srt(_) => &["srt"],
+            Self::Hls { dir, .. } => {
+                return match dir.to_str() {
+                    Some(_) => Ok(()),
+                    None => Err(StreamerError::InvalidUrl { url: dir.display().to_string() }),
+                };
+            }
+            Self::UdpTs { host, port } => {
+                let usable = !host.is_empty() && !host.contains(char::is_whitespace) && *port != 0;
+                return if usable { Ok(()) } else { Err(StreamerError::InvalidUrl { url: self.url().into_owned() }) };
+            }
+        };
+        if self.scheme().map_or(false, |scheme| schemes.contains(&scheme.as_str())) {
+            Ok(())
+        } else {
+            Err(StreamerError::InvalidUrl { url: redact_url(&self.url()) })
+        }
+    }
+
+    /// `None` for HLS, whose sink does its own muxing.
+    pub(crate) fn mux_factory(&self) -> Option<&'static str> {
+        match self {
+            Self::Rtmp(_) => Some("flvmux"),
+            Self::Srt(_) | Self::UdpTs { .. } => Some("mpegtsmux"),
+            Self::Hls { .. } => None,
+        }
+    }
+
+    fn sink_factories(&self) -> &'static [&'static str] {
+        match self {
+            Self::Rtmp(_) if self.scheme().as_deref() == Some("rtmps") => RTMPS_SINK_FACTORIES,
+            Self::Rtmp(_) => RTMP_SINK_FACTORIES,
+            Self::Srt(_) => SRT_SINK_FACTORIES,
+            Self::Hls { .. } => HLS_SINK_FACTORIES,
+            Self::UdpTs { .. } => UDP_SINK_FACTORIES,
+        }
+    }
+
+    /// Points `sink`, made from one of the factories for this target, at it.
+    pub(crate) fn configure_sink(&self, sink: &gst::Element) -> Result<()> {
+        match self {
+            Self::Rtmp(url) => sink.set_property("location", url),
+            Self::Srt(url) => sink.set_property("uri", url),
+            Self::Hls { dir, segment_duration, playlist_length } => {
+                fs::create_dir_all(dir)?;
+                let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+                sink.set_property("location", path(HLS_SEGMENT_PATTERN));
+                sink.set_property("playlist-location", path(HLS_PLAYLIST_NAME));
+                sink.set_property("target-duration", segment_duration.as_secs().clamp(1, u32::MAX.into()) as u32);
+                sink.set_property("playlist-length", *playlist_length);
+                let max_files = match playlist_length {
+                    0 => 0,
+                    length => length.saturating_add(HLS_SPARE_SEGMENTS),
+                };
+                sink.set_property("max-files", max_files);
+            }
+            Self::UdpTs { host, port } => {
+                sink.set_property("host", host);
+                sink.set_property("port", i32::from(*port));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for OutputTarget {
+    fn from(url: &str) -> Self {
+        let has_scheme = |scheme: &str| url.get(..scheme.len()).map_or(false, |prefix| prefix.eq_ignore_ascii_case(scheme));
+        if has_scheme("srt://") {
+            Self::Srt(url.to_string())
+        } else if has_scheme("udp://") {
+            // An address that doesn't parse stays an invalid RTMP URL, so `validate` refuses it.
+            match parse_udp_address(&url[6..]) {
+                Some((host, port)) => Self::UdpTs { host, port },
+                None => Self::Rtmp(url.to_string()),
+            }
+        } else {
+            Self::Rtmp(url.to_string())
+        }
+    }
+}
+
+// `host:port`, with IPv6 hosts in brackets.
+fn parse_udp_address(address: &str) -> Option<(String, u16)> {
+    let (host, port) = address.trim_end_matches('/').rsplit_once(':')?;
+    let host = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host);
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+impl From<String> for OutputTarget {
+    fn from(url: String) -> Self {
+        Self::from(url.as_str())
+    }
+}
+
+pub(crate) fn factory_exists(name: &str) -> bool {
+    gst::ElementFactory::find(name).is_some()
+}
+
+pub(crate) fn require_factory(name: &str) -> Result<(), StreamerError> {
+    if factory_exists(name) {
+        Ok(())
+    } else {
+        Err(StreamerError::MissingPlugin { element: name.to_string() })
+    }
+}
+
+/// Substitutes the fallback video encoder if the requested one is not installed.
+pub(crate) fn resolve_encoder(settings: &EncodingSettings) -> (EncodingSettings, bool) {
+    let mut effective = settings.clone();
+    let fallback_applied = !factory_exists(&settings.video_encoder);
+    if fallback_applied {
+        warn!(target: LOG_OUTPUT, "Video encoder {} is not available, falling back to {}.",
+                  settings.video_encoder, FALLBACK_VIDEO_ENCODER);
+        effective.video_encoder = FALLBACK_VIDEO_ENCODER.to_string();
+    }
+    (effective, fallback_applied)
+}
+
+/// Builds the output chain used by [`crate::Streamer::start`], for embedding in another pipeline.
+///
+/// The returned bin takes raw video and audio on its [`VIDEO_SINK_PAD`] and [`AUDIO_SINK_PAD`]
+/// ghost pads, encodes them according to `settings` and sends them to `target`: FLV for RTMP,
+/// MPEG-TS for SRT. It is named [`PROCESSING_BIN_NAME`] and contains, among unnamed queues
+/// and converters:
+///
+/// - [`VIDEO_ENCODER_NAME`] and [`AUDIO_ENCODER_NAME`], the encoders,
+/// - [`VIDEO_TEE_NAME`] and [`AUDIO_TEE_NAME`], splitting the encoded streams between the
+///   outputs and the recording,
+/// - [`FRAME_RATE_NAME`], the frame rate converter,
+/// - [`VOLUME_NAME`], the master volume,
+/// - [`MUX_NAME`], the `flvmux` or `mpegtsmux`,
+/// - [`OUTPUT_SINK_NAME`], the RTMP or SRT sink,
+/// - [`RECORD_MUX_NAME`] and [`RECORD_SINK_NAME`], only when `settings.record_path` is set,
+/// - [`LOGO_OVERLAY_NAME`], the logo overlay, only when `gdkpixbufoverlay` is installed,
+/// - [`TEXT_OVERLAY_NAME`] and [`CLOCK_OVERLAY_NAME`], the caption and clock, only when
+///   `textoverlay` and `clockoverlay` are installed,
+/// - [`PREVIEW_TEE_NAME`], where the preview is fed from,
+/// - [`AUDIO_LEVEL_NAME`], the audio meter, only when `level` is installed.
+///
+/// These names are a stable contract. As with `start`, an unavailable video encoder is
+/// replaced by the fallback encoder.
+///
+/// ```no_run
+/// use gstreamer as gst;
+/// use gst::prelude::*;
+/// use hayai_playout_core::{create_processing_bin, EncodingSettings, VIDEO_ENCODER_NAME, VIDEO_SINK_PAD};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// gst::init()?;
+/// let bin = create_processing_bin("rtmp://localhost/live/key", &EncodingSettings::default())?;
+/// let encoder = bin.by_name(VIDEO_ENCODER_NAME).unwrap();
+/// encoder.set_property("bitrate", 2500u32);
+/// let video_in = bin.static_pad(VIDEO_SINK_PAD).unwrap();
+/// # let _ = video_in;
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_processing_bin(target: impl Into<OutputTarget>, settings: &EncodingSettings) -> Result<gst::Bin> {
+    create_output(&target.into(), settings).map(|(bin, _)| bin)
+}
+
+/// Checks the URL scheme and that the muxer and sink for `target` are installed, and returns
+/// the sink factory to use.
+pub(crate) fn check_output(target: &OutputTarget) -> Result<&'static str, StreamerError> {
+    target.validate()?;
+    let sink_factories = target.sink_factories();
+    let sink_factory = sink_factories.iter().copied().find(|f| factory_exists(f))
+        .ok_or_else(|| {
+            if sink_factories == RTMPS_SINK_FACTORIES && factory_exists("rtmpsink") {
+                StreamerError::TlsUnsupported { url: redact_url(&target.url()) }
+            } else {
+                StreamerError::MissingPlugin { element: sink_factories.join(" or ") }
+            }
+        })?;
+    if let Some(mux_factory) = target.mux_factory() {
+        require_factory(mux_factory)?;
+    }
+    Ok(sink_factory)
+}
+
+/// [`create_processing_bin`], along with a report of what was actually set up.
+pub(crate) fn create_output(target: &OutputTarget, settings: &EncodingSettings) -> Result<(gst::Bin, StartReport)> {
+    let sink_factory = check_output(target)?;
+    let (effective, fallback_applied) = resolve_encoder(settings);
+    effective.validate()?;
+    let sink = gst::ElementFactory::make(sink_factory).name(OUTPUT_SINK_NAME).build()?;
+    target.configure_sink(&sink)?;
+    // The HLS sinks are bins, paced by the clock inside.
+    if sink.has_property("sync") {
+        sink.set_property("sync", settings.live_sync);
+        if let Some(ms) = settings.max_lateness_ms {
+            sink.set_property("max-lateness", i64::from(ms) * 1_000_000);
+        }
+        sink.set_property("qos", true);
+    }
+
+    let bin = build_processing_bin(&effective, target.mux_factory(), &sink)?;
+    Ok((bin, StartReport {
+        output_url: redact_url(&target.url()),
+        encoder_used: effective.video_encoder,
+        fallback_applied,
+        sink_element: sink_factory.to_string(),
+    }))
+}
+
+/// Builds the encode/mux chain feeding `sink`, exposing `video_sink`/`audio_sink` ghost pads.
+/// Without `mux_factory`, `sink` muxes itself and takes the encoded streams on its `video` and
+/// `audio` pads.
+pub(crate) fn build_processing_bin(settings: &EncodingSettings, mux_factory: Option<&str>, sink: &gst::Element) -> Result<gst::Bin> {
+    let bin = gst::Bin::with_name(PROCESSING_BIN_NAME);
+    let (venc, aenc) = add_encoders(&bin, settings)?;
+    let mut tees = Vec::new();
+    for (encoder, name) in [(&venc, VIDEO_TEE_NAME), (&aenc, AUDIO_TEE_NAME)] {
+        // Outputs can come and go while live, leaving a tee with nothing linked for a moment.
+        let tee = gst::ElementFactory::make("tee").name(name).property("allow-not-linked", true).build()?;
+        bin.add(&tee)?;
+        encoder.link(&tee)?;
+        tees.push(tee);
+    }
+    bin.add(sink)?;
+    let queues = queues_after(&bin, &tees)?;
+    match mux_factory {
+        Some(factory) => {
+            let mux = make_mux(factory, settings.latency_profile.params().mux_latency.as_nanos() as u64, MUX_NAME)?;
+            if let Some(ms) = settings.ts_pcr_interval_ms.filter(|_| mux.has_property("pcr-interval")) {
+                // In ticks of the 90 kHz MPEG clock.
+                mux.set_property("pcr-interval", ms.saturating_mul(90).max(1));
+            }
+            bin.add(&mux)?;
+            link_through_mux(&queues, &mux, sink)?;
+        }
+        None => link_to_muxing_sink(&queues, sink)?,
+    }
+    if let Some(path) = &settings.record_path {
+        add_recorder(&bin, &tees, path)?;
+    }
+    Ok(bin)
+}
+
+/// Links the video and audio `queues` to `mux`, and `mux` to `sink`.
+pub(crate) fn link_through_mux(queues: &[gst::Element], mux: &gst::Element, sink: &gst::Element) -> Result<()> {
+    for queue in queues {
+        queue.link(mux)?;
+    }
+    mux.link(sink)?;
+    Ok(())
+}
+
+/// Links the video and audio `queues` to the `video` and `audio` pads of a sink that muxes
+/// itself, such as `hlssink2`.
+pub(crate) fn link_to_muxing_sink(queues: &[gst::Element], sink: &gst::Element) -> Result<()> {
+    for (queue, pad) in queues.iter().zip(["video", "audio"]) {
+        queue.link_pads(Some("src"), sink, Some(pad))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn make_mux(factory: &str, latency_ns: u64, name: &str) -> Result<gst::Element> {
+    let mux = gst::ElementFactory::make(factory).name(name).build()?;
+    if mux.has_property("streamable") { mux.set_property("streamable", true); }
+    // Whole 7-packet chunks, the payload size SRT expects.
+    if mux.has_property("alignment") { mux.set_property("alignment", 7i32); }
+    mux.set_property("latency", latency_ns);
+    Ok(mux)
+}
+
+// A queue after each tee, so a slow branch cannot stall the others.
+fn queues_after(bin: &gst::Bin, tees: &[gst::Element]) -> Result<Vec<gst::Element>> {
+    tees.iter().map(|tee| {
+        let queue = gst::ElementFactory::make("queue").build()?;
+        bin.add(&queue)?;
+        tee.link(&queue)?;
+        Ok(queue)
+    }).collect()
+}
+
+fn recording_mux_factory(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("mp4") => "mp4mux",
+        _ => "matroskamux",
+    }
+}
+
+// Adds a muxer writing `path` to the tees.
+fn add_recorder(bin: &gst::Bin, tees: &[gst::Element], path: &Path) -> Result<()> {
+    let location = path.to_str()
+        .ok_or_else(|| anyhow!("Recording path {} is not valid UTF-8", path.display()))?;
+    let record_mux = gst::ElementFactory::make(recording_mux_factory(path)).name(RECORD_MUX_NAME).build()?;
+    let record_sink = gst::ElementFactory::make("filesink")
+        .name(RECORD_SINK_NAME)
+        .property("location", location)
+        .property("sync", false)
+        .build()?;
+    bin.add_many(&[&record_mux, &record_sink])?;
+    link_through_mux(&queues_after(bin, tees)?, &record_mux, &record_sink)?;
+    info!(target: LOG_OUTPUT, "Recording to {}.", path.display());
+    Ok(())
+}
+
+/// Adds the queue/convert/encode chains to `bin` behind `video_sink`/`audio_sink` ghost pads
+/// and returns the video and audio encoders, whose src pads are left for the caller to link.
+pub(crate) fn add_encoders(bin: &gst::Bin, settings: &EncodingSettings) -> Result<(gst::Element, gst::Element)> {
+    let latency = settings.latency_profile.params();
+    let queue_max_time = latency.queue_max_time.as_nanos() as u64;
+    let vqueue = gst::ElementFactory::make("queue")
+        .property("max-size-time", queue_max_time)
+        .property("max-size-buffers", 0u32)
+        .property("max-size-bytes", 0u32)
+        .build()?;
     let vconv = gst::ElementFactory::make("videoconvert").build()?;
-    let vrate = gst::ElementFactory::make("videorate").build()?;
-    let venc = gst::ElementFactory::make(&settings.encoder)
-        .name("video_encoder")
+    let vrate = gst::ElementFactory::make("videorate").name(FRAME_RATE_NAME).build()?;
+    let venc = gst::ElementFactory::make(&settings.video_encoder).name(VIDEO_ENCODER_NAME).build()?;
+    let aqueue = gst::ElementFactory::make("queue")
+        .property("max-size-time", queue_max_time)
+        .property("max-size-buffers", 0u32)
+        .property("max-size-bytes", 0u32)
         .build()?;
-    
-    let aqueue = gst::ElementFactory::make("queue").build()?;
     let aconv = gst::ElementFactory::make("audioconvert").build()?;
+    let volume = gst::ElementFactory::make("volume").name(VOLUME_NAME).build()?;
     let aresample = gst::ElementFactory::make("audioresample").build()?;
-    let aenc = gst::ElementFactory::make("voaacenc").build()?;
-    let mux = gst::ElementFactory::make("flvmux")
-        .name("mux")
-        .property("streamable", true)
-        .build()?;
-    let sink = gst::ElementFactory::make("rtmpsink").build()?;
+    let aenc = gst::ElementFactory::make(&settings.audio_encoder).name(AUDIO_ENCODER_NAME).build()?;
     
-    if venc.has_property("tune") { venc.set_property_from_str("tune", "zerolatency"); }
-    if venc.has_property("bitrate") { venc.set_property("bitrate", settings.bitrate_kbps); }
-    if venc.has_property("speed-preset") { venc.set_property_from_str("speed-preset", &settings.speed_preset); }
-    if venc.has_property("key-int-max") { venc.set_property("key-int-max", 60u32); }
+    // Configure encoders
+    match EncoderProfile::for_encoder(&settings.video_encoder) {
+        Some(profile) => profile.configure(&venc, settings),
+        None => {
+            // Unknown encoder: set whatever looks like it means the same thing.
+            if let Some(tune) = latency.encoder_tune {
+                if venc.has_property("tune") { venc.set_property_from_str("tune", tune); }
+            }
+            if venc.has_property("bitrate") { venc.set_property("bitrate", settings.bitrate_kbps); }
+            if venc.has_property("speed-preset") { venc.set_property_from_str("speed-preset", &settings.speed_preset); }
+            if venc.has_property("key-int-max") { venc.set_property("key-int-max", settings.keyframe_interval_frames()); }
+        }
+    }
+    if aenc.has_property("bitrate") {
+        // lamemp3enc takes kbps, the others bits per second; parsing the string fits whichever
+        // integer type the encoder uses.
+        let bitrate = if settings.audio_encoder == "lamemp3enc" {
+            settings.audio_bitrate_kbps
+        } else {
+            settings.audio_bitrate_kbps * 1000
+        };
+        aenc.set_property_from_str("bitrate", &bitrate.to_string());
+    }
     
-    aenc.set_property("bitrate", 128000_i32);
-    sink.set_property("location", rtmp_url);
-    sink.set_property("sync", true);
-    sink.set_property("qos", true);
-    let max_lateness = gst::ClockTime::from_mseconds(500);
-    sink.set_property("max-lateness", max_lateness.nseconds() as i64);
-
+    let mut video_chain = vec![vqueue.clone(), vconv, vrate];
+    if let Some(framerate) = settings.framerate() {
+        // Makes videorate drop or duplicate frames, so clips of different rates come out even.
+        let rate_filter = gst::ElementFactory::make("capsfilter").build()?;
+        rate_filter.set_property("caps", gst::Caps::builder("video/x-raw").field("framerate", framerate).build());
+        video_chain.push(rate_filter);
+    }
     if settings.scale_enabled {
         let vscale = gst::ElementFactory::make("videoscale").build()?;
         let capsfilter = gst::ElementFactory::make("capsfilter").build()?;
@@ -44,20 +467,77 @@ pub(crate) fn create_processing_bin(rtmp_url: &str, settings: &EncodingSettings)
             .field("height", settings.scale_height as i32)
             .build();
         capsfilter.set_property("caps", caps);
-        bin.add_many(&[&vqueue, &vconv, &vrate, &vscale, &capsfilter, &venc, &aqueue, &aconv, &aresample, &aenc, &mux, &sink])?;
-        gst::Element::link_many(&[&vqueue, &vconv, &vrate, &vscale, &capsfilter, &venc, &mux])?;
-    } else {
-        bin.add_many(&[&vqueue, &vconv, &vrate, &venc, &aqueue, &aconv, &aresample, &aenc, &mux, &sink])?;
-        gst::Element::link_many(&[&vqueue, &vconv, &vrate, &venc, &mux])?;
+        video_chain.push(vscale);
+        video_chain.push(capsfilter);
     }
-    gst::Element::link_many(&[&aqueue, &aconv, &aresample, &aenc, &mux])?;
-    mux.link(&sink)?;
-
+    if factory_exists("gdkpixbufoverlay") {
+        // Always in the chain, hidden until a logo is set, so one can come and go while live.
+        let logo = gst::ElementFactory::make("gdkpixbufoverlay")
+            .name(LOGO_OVERLAY_NAME)
+            .property("alpha", 0.0f64)
+            .build()?;
+        video_chain.push(logo);
+    }
+    for (factory, name) in [("textoverlay", TEXT_OVERLAY_NAME), ("clockoverlay", CLOCK_OVERLAY_NAME)] {
+        if factory_exists(factory) {
+            let overlay = gst::ElementFactory::make(factory).name(name).property("silent", true).build()?;
+            video_chain.push(overlay);
+        }
+    }
+    // Hands the finished picture to the preview as well, once there is one.
+    let preview_tee = gst::ElementFactory::make("tee").name(PREVIEW_TEE_NAME).property("allow-not-linked", true).build()?;
+    video_chain.push(preview_tee);
+    video_chain.push(venc.clone());
+    bin.add_many(&video_chain)?;
+    gst::Element::link_many(&video_chain)?;
+    let mut audio_chain = vec![aqueue.clone(), aconv, volume];
+    if settings.normalize_loudness {
+        audio_chain.extend(loudness_normalizer(settings.loudness_target_lufs)?);
+    }
+    if factory_exists("level") {
+        let level = gst::ElementFactory::make("level")
+            .name(AUDIO_LEVEL_NAME)
+            .property("interval", AUDIO_LEVEL_INTERVAL.as_nanos() as u64)
+            .property("post-messages", true)
+            .build()?;
+        audio_chain.push(level);
+    }
+    audio_chain.push(aresample);
+    audio_chain.push(aenc.clone());
+    bin.add_many(&audio_chain)?;
+    gst::Element::link_many(&audio_chain)?;
+    
+    // Create ghost pads
     let vpad = gst::GhostPad::with_target(&vqueue.static_pad("sink").unwrap())?;
-    vpad.set_property("name", "video_sink");
+    vpad.set_property("name", VIDEO_SINK_PAD);
     bin.add_pad(&vpad)?;
     let apad = gst::GhostPad::with_target(&aqueue.static_pad("sink").unwrap())?;
-    apad.set_property("name", "audio_sink");
+    apad.set_property("name", AUDIO_SINK_PAD);
     bin.add_pad(&apad)?;
-    Ok(bin)
-}
\ No newline at end of file
+
+    Ok((venc, aenc))
+}
+
+/// Elements that bring the audio to `target_lufs`: `audioloudnorm` between the converters it
+/// needs, or a peak limiter if it isn't installed.
+fn loudness_normalizer(target_lufs: f64) -> Result<Vec<gst::Element>> {
+    if factory_exists("audioloudnorm") {
+        // It only takes 64-bit float at 192 kHz.
+        let resample = gst::ElementFactory::make("audioresample").build()?;
+        let loudnorm = gst::ElementFactory::make("audioloudnorm")
+            .property("loudness-target", target_lufs.clamp(-70.0, -5.0))
+            .build()?;
+        let convert = gst::ElementFactory::make("audioconvert").build()?;
+        info!(target: LOG_OUTPUT, "Normalizing loudness to {} LUFS.", target_lufs);
+        return Ok(vec![resample, loudnorm, convert]);
+    }
+    warn!(target: LOG_OUTPUT, "audioloudnorm is not installed, only limiting peaks instead of normalizing loudness.");
+    // A compressor with a ratio of zero flattens everything above about -1 dBFS.
+    let limiter = gst::ElementFactory::make("audiodynamic")
+        .property_from_str("mode", "compressor")
+        .property_from_str("characteristics", "hard-knee")
+        .property("threshold", 0.89f32)
+        .property("ratio", 0.0f32)
+        .build()?;
+    Ok(vec![limiter])
+}
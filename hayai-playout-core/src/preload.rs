@@ -0,0 +1,44 @@
+use gstreamer as gst;
+use gst::prelude::*;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Blocks {
+    released: bool,
+    probes: Vec<(gst::Pad, gst::PadProbeId)>,
+}
+
+/// Holds back a preloaded source at its src pads until it goes on air. The source opens and
+/// decodes up to its first buffer, then waits there, so switching to it is instant.
+#[derive(Clone, Default)]
+pub(crate) struct Blocker {
+    blocks: Arc<Mutex<Blocks>>,
+}
+
+impl Blocker {
+    /// Blocks `pad` at its first buffer and returns `true`, or returns `false` if the source
+    /// was already released. Events still pass, so the selector input is fully set up.
+    pub(crate) fn block(&self, pad: &gst::Pad) -> bool {
+        let mut blocks = self.blocks.lock().unwrap();
+        if blocks.released {
+            return false;
+        }
+        let probe = pad.add_probe(
+            gst::PadProbeType::BLOCK | gst::PadProbeType::BUFFER | gst::PadProbeType::BUFFER_LIST,
+            |_, _| gst::PadProbeReturn::Ok,
+        );
+        if let Some(id) = probe {
+            blocks.probes.push((pad.clone(), id));
+        }
+        true
+    }
+
+    /// Lets the held-back data through. Pads added afterwards are not blocked.
+    pub(crate) fn release(&self) {
+        let mut blocks = self.blocks.lock().unwrap();
+        blocks.released = true;
+        for (pad, id) in blocks.probes.drain(..) {
+            pad.remove_probe(id);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+
+use crate::pipeline::PREVIEW_TEE_NAME;
+
+const PREVIEW_BIN_NAME: &str = "preview";
+
+/// Shows the picture going to the encoder in `sink` as well, fed from the preview tee of
+/// `bin`, a processing bin.
+///
+/// The preview only gets what it has time for: its queue drops old frames rather than hold
+/// the tee up, and the sink takes no part in prerolling, so the stream's timing is the same
+/// with or without it.
+pub(crate) fn attach(bin: &gst::Bin, sink: &gst::Element) -> Result<()> {
+    let tee = bin.by_name(PREVIEW_TEE_NAME)
+        .ok_or_else(|| anyhow!("This output has no preview"))?;
+    // Still in the bin of a stream that has since stopped.
+    if let Some(old) = sink.parent().and_then(|parent| parent.downcast::<gst::Bin>().ok()) {
+        let _ = sink.set_state(gst::State::Null);
+        old.remove(sink)?;
+    }
+    let queue = gst::ElementFactory::make("queue")
+        .property_from_str("leaky", "downstream")
+        .property("max-size-buffers", 2u32)
+        .property("max-size-bytes", 0u32)
+        .property("max-size-time", 0u64)
+        .build()?;
+    let convert = gst::ElementFactory::make("videoconvert").build()?;
+    if sink.has_property("async") {
+        sink.set_property("async", false);
+    }
+
+    let preview = gst::Bin::with_name(PREVIEW_BIN_NAME);
+    preview.add_many(&[&queue, &convert, sink])?;
+    gst::Element::link_many(&[&queue, &convert, sink])?;
+    let pad = gst::GhostPad::with_target(&queue.static_pad("sink").unwrap())?;
+    preview.add_pad(&pad)?;
+    bin.add(&preview)?;
+
+    let tee_pad = tee.request_pad_simple("src_%u")
+        .ok_or_else(|| anyhow!("{} did not provide a src pad", tee.name()))?;
+    if let Err(e) = tee_pad.link(&pad) {
+        tee.release_request_pad(&tee_pad);
+        let _ = bin.remove(&preview);
+        return Err(anyhow!("linking the preview failed: {}", e));
+    }
+    preview.sync_state_with_parent()?;
+    Ok(())
+}
+
+/// Unhooks the preview from `bin` and removes it; does nothing if there is none. Not for
+/// streaming threads: releasing the tee pad waits for the buffer it is pushing.
+pub(crate) fn detach(bin: &gst::Bin) {
+    let preview = match bin.by_name(PREVIEW_BIN_NAME) {
+        Some(preview) => preview,
+        None => return,
+    };
+    if let Some(tee_pad) = preview.static_pads().first().and_then(|pad| pad.peer()) {
+        if let Some(tee) = tee_pad.parent_element() {
+            tee.release_request_pad(&tee_pad);
+        }
+    }
+    let _ = preview.set_state(gst::State::Null);
+    let _ = bin.remove(&preview);
+}
@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use gstreamer as gst;
+use gstreamer_pbutils as gst_pbutils;
+
+use crate::redact_url;
+
+const DISCOVER_TIMEOUT: gst::ClockTime = gst::ClockTime::from_seconds(10);
+
+/// What [`Streamer::probe_item`](crate::Streamer::probe_item) found out about a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaInfo {
+    /// `None` for live sources and files whose length can't be determined.
+    pub duration: Option<gst::ClockTime>,
+    /// The first video stream, if there is one.
+    pub video: Option<VideoStreamInfo>,
+    /// The first audio stream, if there is one.
+    pub audio: Option<AudioStreamInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VideoStreamInfo {
+    pub width: u32,
+    pub height: u32,
+    pub framerate: gst::Fraction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AudioStreamInfo {
+    pub channels: u32,
+    pub sample_rate: u32,
+}
+
+impl MediaInfo {
+    pub fn has_video(&self) -> bool {
+        self.video.is_some()
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.audio.is_some()
+    }
+}
+
+/// Runs the discoverer over `uri`. Unreadable files, unknown formats and missing decoders are
+/// all errors.
+pub(crate) fn discover(uri: &str) -> Result<MediaInfo> {
+    gst::init()?;
+    let discoverer = gst_pbutils::Discoverer::new(DISCOVER_TIMEOUT)?;
+    let info = discoverer.discover_uri(uri)
+        .with_context(|| format!("Could not read {}", redact_url(uri)))?;
+
+    let video = info.video_streams().first().map(|v| VideoStreamInfo {
+        width: v.width(),
+        height: v.height(),
+        framerate: v.framerate(),
+    });
+    let audio = info.audio_streams().first().map(|a| AudioStreamInfo {
+        channels: a.channels(),
+        sample_rate: a.sample_rate(),
+    });
+    Ok(MediaInfo { duration: info.duration(), video, audio })
+}
@@ -0,0 +1,92 @@
+use gstreamer as gst;
+use gst::prelude::*;
+use log::warn;
+
+use crate::encoders::probe_encoder;
+use crate::{EncodingSettings, LOG_OUTPUT};
+
+/// A family of H.264 encoders that is configured the same way. Every family names its
+/// properties differently, so each one spells out what it sets instead of probing for
+/// property names. Encoders outside these families get a best-effort configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncoderProfile {
+    /// Software `x264enc`.
+    X264,
+    /// NVIDIA NVENC.
+    Nvenc,
+    /// VA-API, through the newer `va` or the older `vaapi` plugin.
+    Vaapi,
+    /// Intel Quick Sync, through the `qsv` or `msdk` plugin.
+    QuickSync,
+}
+
+impl EncoderProfile {
+    pub const ALL: [EncoderProfile; 4] = [Self::X264, Self::Nvenc, Self::Vaapi, Self::QuickSync];
+
+    /// Element factories of this family, preferred first.
+    pub fn factories(self) -> &'static [&'static str] {
+        match self {
+            Self::X264 => &["x264enc"],
+            Self::Nvenc => &["nvh264enc", "nvcudah264enc"],
+            Self::Vaapi => &["vah264enc", "vaapih264enc"],
+            Self::QuickSync => &["qsvh264enc", "msdkh264enc"],
+        }
+    }
+
+    /// The family `factory` belongs to, if it is one of the known ones.
+    pub fn for_encoder(factory: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|profile| profile.factories().contains(&factory))
+    }
+
+    /// The first factory of this family that can be opened on this machine.
+    pub fn factory(self) -> Option<&'static str> {
+        self.factories().iter().copied().find(|factory| probe_encoder(factory))
+    }
+
+    /// Whether an encoder of this family is installed and its device can be opened.
+    pub fn is_available(self) -> bool {
+        self.factory().is_some()
+    }
+
+    /// The properties to set on `factory`, a member of this family, as name and value
+    /// strings for `set_property_from_str`. Bitrates are in kbps for every family.
+    pub fn properties(self, factory: &str, settings: &EncodingSettings) -> Vec<(&'static str, String)> {
+        let low_latency = settings.latency_profile.params().encoder_tune.is_some();
+        let bitrate = settings.bitrate_kbps.to_string();
+        let gop = settings.keyframe_interval_frames().to_string();
+        let mut properties = match self {
+            Self::X264 => vec![
+                ("bitrate", bitrate),
+                ("speed-preset", settings.speed_preset.clone()),
+                ("key-int-max", gop),
+            ],
+            Self::Nvenc => vec![("bitrate", bitrate), ("rc-mode", "cbr".to_string()), ("gop-size", gop)],
+            Self::Vaapi if factory == "vaapih264enc" => {
+                vec![("bitrate", bitrate), ("rate-control", "cbr".to_string()), ("keyframe-period", gop)]
+            }
+            Self::Vaapi => vec![("bitrate", bitrate), ("rate-control", "cbr".to_string()), ("key-int-max", gop)],
+            Self::QuickSync => vec![("bitrate", bitrate), ("rate-control", "cbr".to_string()), ("gop-size", gop)],
+        };
+        if low_latency {
+            match self {
+                Self::X264 => properties.push(("tune", "zerolatency".to_string())),
+                Self::Nvenc if factory == "nvh264enc" => properties.push(("zerolatency", "true".to_string())),
+                Self::Nvenc => properties.push(("zero-latency", "true".to_string())),
+                Self::Vaapi | Self::QuickSync => (),
+            }
+        }
+        properties
+    }
+
+    pub(crate) fn configure(self, encoder: &gst::Element, settings: &EncodingSettings) {
+        let factory = encoder.factory().map(|f| f.name().to_string()).unwrap_or_default();
+        for (name, value) in self.properties(&factory, settings) {
+            // Plugin versions occasionally drop a property; that is not worth failing over.
+            if encoder.has_property(name) {
+                encoder.set_property_from_str(name, &value);
+            } else {
+                warn!(target: LOG_OUTPUT, "{} has no '{}' property, leaving it at its default.", factory, name);
+            }
+        }
+    }
+}
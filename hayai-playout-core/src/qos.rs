@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const QOS_WINDOW: Duration = Duration::from_secs(5);
+
+/// Adds up the QoS reports of elements dropping frames under load, so they can be reported
+/// once per window rather than once per frame.
+///
+/// Elements report running totals; only what changed since an element's previous report counts.
+#[derive(Debug)]
+pub struct QosMonitor {
+    window: Duration,
+    /// `(processed, dropped)` so far, by element.
+    totals: HashMap<String, (u64, u64)>,
+    /// When the window with drops in it started.
+    window_start: Option<Instant>,
+    processed: u64,
+    dropped: u64,
+}
+
+impl Default for QosMonitor {
+    fn default() -> Self {
+        Self::new(QOS_WINDOW)
+    }
+}
+
+impl QosMonitor {
+    pub fn new(window: Duration) -> Self {
+        Self { window, totals: HashMap::new(), window_start: None, processed: 0, dropped: 0 }
+    }
+
+    /// Notes a report from `element`, which has processed and dropped this many in all, at `now`.
+    pub fn record(&mut self, element: &str, processed: u64, dropped: u64, now: Instant) {
+        let (last_processed, last_dropped) = self.totals.insert(element.to_string(), (processed, dropped))
+            .unwrap_or_default();
+        // Totals going down mean the element started over.
+        let since = |total: u64, last: u64| if total >= last { total - last } else { total };
+        let (processed, dropped) = (since(processed, last_processed), since(dropped, last_dropped));
+        if dropped > 0 {
+            self.window_start.get_or_insert(now);
+        }
+        if self.window_start.is_some() {
+            self.processed += processed;
+            self.dropped += dropped;
+        }
+    }
+
+    /// `(dropped, processed)` over the window, once a window with drops in it is over; the
+    /// next window starts with the next drop.
+    pub fn poll(&mut self, now: Instant) -> Option<(u64, u64)> {
+        let start = self.window_start?;
+        if now.saturating_duration_since(start) < self.window {
+            return None;
+        }
+        let report = (self.dropped, self.processed);
+        self.window_start = None;
+        self.dropped = 0;
+        self.processed = 0;
+        Some(report)
+    }
+}
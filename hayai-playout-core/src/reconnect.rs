@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Connection state of the output sink.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputState {
+    #[default]
+    Connected,
+    Reconnecting { attempt: u32 },
+}
+
+/// What to do once `max_attempts` reconnection attempts have failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GiveUpPolicy {
+    /// Stop the stream.
+    Stop,
+    /// Keep standby on air and retry every `interval`, forever.
+    StandbyAndRetry { interval: Duration },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconnectPolicy {
+    pub max_attempts: u32,
+    /// Wait before the first attempt; doubles with every further attempt.
+    pub retry_delay: Duration,
+    /// Upper bound for the doubling wait.
+    #[serde(default = "default_max_retry_delay")]
+    pub max_retry_delay: Duration,
+    pub on_give_up: GiveUpPolicy,
+}
+
+fn default_max_retry_delay() -> Duration {
+    Duration::from_secs(30)
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_delay: Duration::from_secs(2),
+            max_retry_delay: default_max_retry_delay(),
+            on_give_up: GiveUpPolicy::Stop,
+        }
+    }
+}
+
+/// Side effects the streamer has to carry out after a connection state change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReconnectAction {
+    /// Put the standby source on air.
+    EnterStandby,
+    /// Take the standby source off air and return to the program.
+    LeaveStandby,
+    /// Restart the output sink after [`ReconnectMonitor::retry_delay`].
+    Retry { attempt: u32 },
+    /// Attempts are exhausted; stop the stream.
+    GiveUp,
+    /// Attempts are exhausted; stay in standby and keep retrying at the slow interval.
+    KeepRetryingInStandby,
+}
+
+/// Tracks sink failures and recoveries and decides what to do about them.
+#[derive(Debug, Default)]
+pub struct ReconnectMonitor {
+    standby_on_reconnect: bool,
+    policy: ReconnectPolicy,
+    state: OutputState,
+    in_standby: bool,
+    retrying_forever: bool,
+}
+
+impl ReconnectMonitor {
+    pub fn new(standby_on_reconnect: bool) -> Self {
+        Self { standby_on_reconnect, ..Self::default() }
+    }
+
+    /// Whether the program is switched to standby while the output reconnects.
+    /// Only affects the next failure; an ongoing standby is left as is.
+    pub fn set_standby_on_reconnect(&mut self, enabled: bool) {
+        self.standby_on_reconnect = enabled;
+    }
+
+    pub fn set_policy(&mut self, policy: ReconnectPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn policy(&self) -> ReconnectPolicy {
+        self.policy
+    }
+
+    pub fn state(&self) -> OutputState {
+        self.state
+    }
+
+    pub fn in_standby(&self) -> bool {
+        self.in_standby
+    }
+
+    /// How long to wait before the next retry: `retry_delay`, doubled for every attempt
+    /// after the first up to `max_retry_delay`, or the slow interval once retrying forever.
+    pub fn retry_delay(&self) -> Duration {
+        match self.policy.on_give_up {
+            GiveUpPolicy::StandbyAndRetry { interval } if self.retrying_forever => interval,
+            _ => {
+                let attempt = match self.state {
+                    OutputState::Connected => 1,
+                    OutputState::Reconnecting { attempt } => attempt,
+                };
+                let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+                self.policy.retry_delay.saturating_mul(factor)
+                    .min(self.policy.max_retry_delay.max(self.policy.retry_delay))
+            }
+        }
+    }
+
+    pub fn on_sink_error(&mut self) -> Vec<ReconnectAction> {
+        let attempt = match self.state {
+            OutputState::Connected => 1,
+            OutputState::Reconnecting { attempt } => attempt + 1,
+        };
+        self.state = OutputState::Reconnecting { attempt };
+
+        let mut actions = Vec::new();
+        if attempt > self.policy.max_attempts {
+            match self.policy.on_give_up {
+                GiveUpPolicy::Stop => {
+                    self.reset();
+                    return vec![ReconnectAction::GiveUp];
+                }
+                GiveUpPolicy::StandbyAndRetry { .. } => {
+                    if !self.in_standby {
+                        self.in_standby = true;
+                        actions.push(ReconnectAction::EnterStandby);
+                    }
+                    if !self.retrying_forever {
+                        self.retrying_forever = true;
+                        actions.push(ReconnectAction::KeepRetryingInStandby);
+                    }
+                }
+            }
+        } else if self.standby_on_reconnect && !self.in_standby {
+            self.in_standby = true;
+            actions.push(ReconnectAction::EnterStandby);
+        }
+        actions.push(ReconnectAction::Retry { attempt });
+        actions
+    }
+
+    pub fn on_sink_recovered(&mut self) -> Vec<ReconnectAction> {
+        if self.state == OutputState::Connected {
+            return Vec::new();
+        }
+        self.state = OutputState::Connected;
+        self.retrying_forever = false;
+
+        let mut actions = Vec::new();
+        if self.in_standby {
+            self.in_standby = false;
+            actions.push(ReconnectAction::LeaveStandby);
+        }
+        actions
+    }
+
+    /// Forgets any ongoing reconnection; called when the stream stops.
+    pub fn reset(&mut self) {
+        self.state = OutputState::Connected;
+        self.in_standby = false;
+        self.retrying_forever = false;
+    }
+}
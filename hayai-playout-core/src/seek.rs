@@ -0,0 +1,122 @@
+use gstreamer as gst;
+use gst::prelude::*;
+use log::{debug, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::LOG_SOURCE;
+
+/// Makes a freshly created `uridecodebin` start from `offset` instead of the beginning.
+/// Must be called before the element is started so every src pad gets the probes. Media
+/// shorter than `offset` plays from the beginning.
+///
+/// Buffers are dropped until the seek has gone through, and the seek's flush is kept off the
+/// source's src pads so it never reaches the selectors, encoders or output.
+pub(crate) fn start_at_offset(source_elem: &gst::Element, offset: gst::ClockTime) {
+    seek_before_start(source_elem, offset, gst::SeekFlags::ACCURATE);
+}
+
+/// Makes a freshly created `uridecodebin` play its media over and over, for a standby loop.
+/// Must be called before the element is started.
+///
+/// Plays in segment mode: instead of EOS the demuxer reports the end of the segment, and a
+/// non-flushing seek back to the start follows on directly. Running time keeps counting
+/// across the boundary for audio and video alike, so neither drifts and nothing is torn down.
+pub(crate) fn loop_from_start(source_elem: &gst::Element) {
+    seek_before_start(source_elem, gst::ClockTime::ZERO, gst::SeekFlags::SEGMENT);
+
+    // Every src pad sees the same segment-done; only the first one seeks.
+    let handled: Arc<Mutex<Option<gst::Seqnum>>> = Arc::new(Mutex::new(None));
+    source_elem.connect_pad_added(move |elem, pad| {
+        let elem = elem.clone();
+        let handled = handled.clone();
+        pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |pad, info| {
+            if let Some(gst::PadProbeData::Event(event)) = &info.data {
+                if event.type_() == gst::EventType::SegmentDone {
+                    let mut handled = handled.lock().unwrap();
+                    if *handled != Some(event.seqnum()) {
+                        *handled = Some(event.seqnum());
+                        let pad = pad.clone();
+                        elem.call_async(move |elem| {
+                            debug!(target: LOG_SOURCE, "seek: Looping '{}' back to the start", elem.name());
+                            let seek = gst::event::Seek::new(
+                                1.0,
+                                gst::SeekFlags::SEGMENT,
+                                gst::SeekType::Set,
+                                gst::ClockTime::ZERO,
+                                gst::SeekType::None,
+                                gst::ClockTime::NONE,
+                            );
+                            if !pad.send_event(seek) {
+                                warn!(target: LOG_SOURCE, "Could not loop '{}', it will end here.", elem.name());
+                            }
+                        });
+                    }
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
+    });
+}
+
+// Sends a flushing seek to `position` with `flags` once the first buffer shows up.
+fn seek_before_start(source_elem: &gst::Element, position: gst::ClockTime, flags: gst::SeekFlags) {
+    let seek_sent = Arc::new(AtomicBool::new(false));
+    let seek_failed = Arc::new(AtomicBool::new(false));
+
+    source_elem.connect_pad_added(move |elem, pad| {
+        let flushed = Arc::new(AtomicBool::new(false));
+
+        let flushed_clone = flushed.clone();
+        pad.add_probe(gst::PadProbeType::EVENT_FLUSH, move |_, info| {
+            if flushed_clone.load(Ordering::SeqCst) {
+                return gst::PadProbeReturn::Ok;
+            }
+            if let Some(gst::PadProbeData::Event(event)) = &info.data {
+                if event.type_() == gst::EventType::FlushStop {
+                    flushed_clone.store(true, Ordering::SeqCst);
+                }
+            }
+            gst::PadProbeReturn::Drop
+        });
+
+        let elem = elem.clone();
+        let seek_sent = seek_sent.clone();
+        let seek_failed = seek_failed.clone();
+        pad.add_probe(gst::PadProbeType::BUFFER, move |pad, _| {
+            if flushed.load(Ordering::SeqCst) || seek_failed.load(Ordering::SeqCst) {
+                return gst::PadProbeReturn::Remove;
+            }
+            // Seeking from the streaming thread would deadlock, so hand it off.
+            if !seek_sent.swap(true, Ordering::SeqCst) {
+                let pad = pad.clone();
+                let seek_failed = seek_failed.clone();
+                elem.call_async(move |elem| {
+                    // Known only now that the media is open.
+                    let too_short = pad.query_duration::<gst::ClockTime>()
+                        .filter(|duration| position > gst::ClockTime::ZERO && *duration <= position);
+                    if let Some(duration) = too_short {
+                        warn!(target: LOG_SOURCE, "'{}' is only {} long, playing from the start instead of {}.",
+                              elem.name(), duration, position);
+                        seek_failed.store(true, Ordering::SeqCst);
+                        return;
+                    }
+                    debug!(target: LOG_SOURCE, "seek: Seeking '{}' to {}", elem.name(), position);
+                    let seek = gst::event::Seek::new(
+                        1.0,
+                        gst::SeekFlags::FLUSH | flags,
+                        gst::SeekType::Set,
+                        Some(position),
+                        gst::SeekType::None,
+                        gst::ClockTime::NONE,
+                    );
+                    if !pad.send_event(seek) {
+                        warn!(target: LOG_SOURCE, "Seek to {} failed for '{}', playing from the start.", position, elem.name());
+                        seek_failed.store(true, Ordering::SeqCst);
+                    }
+                });
+            }
+            gst::PadProbeReturn::Drop
+        });
+    });
+}
@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use log::{debug, warn};
+
+use crate::LOG_SOURCE;
+
+/// Links a new source pad to a fresh selector input. Transitions overlap with the delayed
+/// cleanup of the previous source, so a stale peer still attached to the requested pad is
+/// unlinked first, and the pad is released again if linking fails.
+pub(crate) fn link_to_selector(src_pad: &gst::Pad, selector: &gst::Element) -> Result<gst::Pad> {
+    if src_pad.is_linked() {
+        return Err(anyhow!("source pad '{}' is already linked", src_pad.name()));
+    }
+    let sink_pad = selector.request_pad_simple("sink_%u")
+        .ok_or_else(|| anyhow!("{} did not provide a sink pad", selector.name()))?;
+    debug!(target: LOG_SOURCE, "pad-added: Linking '{}' to selector pad '{}'", src_pad.name(), sink_pad.name());
+
+    if let Some(stale_peer) = sink_pad.peer() {
+        warn!(target: LOG_SOURCE, "Selector pad '{}' still linked to '{}', unlinking stale peer.",
+                sink_pad.name(), stale_peer.name());
+        let _ = stale_peer.unlink(&sink_pad);
+    }
+    debug_assert!(!sink_pad.is_linked(), "selector pad must be free before linking");
+
+    if let Err(e) = src_pad.link(&sink_pad) {
+        selector.release_request_pad(&sink_pad);
+        return Err(anyhow!("linking to '{}' failed: {}", sink_pad.name(), e));
+    }
+    Ok(sink_pad)
+}
+
+/// Links a source pad to the video or audio selector according to its caps and returns the
+/// selector with its new input. `Ok(None)` for pads that are neither video nor audio.
+pub fn link_source_to_selectors(
+    src_pad: &gst::Pad,
+    video_selector: &gst::Element,
+    audio_selector: &gst::Element,
+) -> Result<Option<(gst::Element, gst::Pad)>> {
+    let caps = match src_pad.current_caps() {
+        Some(caps) => caps,
+        None => return Ok(None),
+    };
+    let media_type = match caps.structure(0) {
+        Some(s) => s.name().to_string(),
+        None => return Ok(None),
+    };
+    let selector = if media_type.starts_with("video/") {
+        video_selector
+    } else if media_type.starts_with("audio/") {
+        audio_selector
+    } else {
+        return Ok(None);
+    };
+    let sink_pad = link_to_selector(src_pad, selector)
+        .map_err(|e| anyhow!("{} pad: {}", media_type, e))?;
+    Ok(Some((selector.clone(), sink_pad)))
+}
+
+/// The selector inputs fed by `source`.
+pub(crate) fn pads_fed_by(selector: &gst::Element, source: &gst::Element) -> Vec<gst::Pad> {
+    selector.sink_pads().into_iter()
+        .filter(|pad| pad.peer()
+            .and_then(|peer| peer.parent_element())
+            .map_or(false, |elem| &elem == source))
+        .collect()
+}
+
+/// Releases every selector input fed by `source` and returns how many were released.
+pub fn release_source_pads(selector: &gst::Element, source: &gst::Element) -> usize {
+    let pads = pads_fed_by(selector, source);
+    for pad in &pads {
+        debug!(target: LOG_SOURCE, "call_async: Releasing selector pad '{}'", pad.name());
+        selector.release_request_pad(pad);
+    }
+    pads.len()
+}
+
+/// The source pad feeding the selector's active input.
+pub(crate) fn active_source_pad(selector: &gst::Element) -> Option<gst::Pad> {
+    selector.property::<Option<gst::Pad>>("active-pad").and_then(|pad| pad.peer())
+}
+
+/// The element feeding the selector's active input.
+pub fn active_source_for(selector: &gst::Element) -> Option<gst::Element> {
+    active_source_pad(selector).and_then(|peer| peer.parent_element())
+}
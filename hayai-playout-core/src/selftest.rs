@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::pipeline::{build_processing_bin, AUDIO_SINK_PAD, VIDEO_ENCODER_NAME, VIDEO_SINK_PAD};
+use crate::EncodingSettings;
+
+const TEST_SECONDS: u64 = 5;
+const TEST_FPS: i32 = 30;
+const TEST_AUDIO_RATE: i32 = 48000;
+// Wall-clock limit; well above what even a hopelessly slow encoder needs for TEST_SECONDS.
+const TEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Outcome of [`Streamer::self_test`](crate::Streamer::self_test).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelfTestReport {
+    pub frames_encoded: u64,
+    /// Length of the test signal that was encoded.
+    pub media_duration: Duration,
+    /// Wall-clock time the encode took.
+    pub elapsed: Duration,
+    /// Frames encoded per wall-clock second.
+    pub fps: f64,
+    /// Media seconds encoded per wall-clock second; below 1.0 the settings can't keep up live.
+    pub realtime_factor: f64,
+}
+
+impl SelfTestReport {
+    pub fn keeps_up(&self) -> bool {
+        self.realtime_factor >= 1.0
+    }
+}
+
+pub(crate) fn run(settings: &EncodingSettings) -> Result<SelfTestReport> {
+    let (width, height) = if settings.scale_enabled {
+        (settings.scale_width as i32, settings.scale_height as i32)
+    } else {
+        (1920, 1080)
+    };
+    let num_buffers = (TEST_SECONDS * TEST_FPS as u64) as i32;
+
+    let pipeline = gst::Pipeline::new();
+    let vsrc = gst::ElementFactory::make("videotestsrc")
+        .property("num-buffers", num_buffers)
+        .build()?;
+    let vcaps = gst::ElementFactory::make("capsfilter")
+        .property("caps", gst::Caps::builder("video/x-raw")
+            .field("width", width)
+            .field("height", height)
+            .field("framerate", gst::Fraction::new(TEST_FPS, 1))
+            .build())
+        .build()?;
+    // One audio buffer per video frame, so both sources end at the same time.
+    let asrc = gst::ElementFactory::make("audiotestsrc")
+        .property("num-buffers", num_buffers)
+        .property("samplesperbuffer", TEST_AUDIO_RATE / TEST_FPS)
+        .build()?;
+    let acaps = gst::ElementFactory::make("capsfilter")
+        .property("caps", gst::Caps::builder("audio/x-raw").field("rate", TEST_AUDIO_RATE).build())
+        .build()?;
+    let sink = gst::ElementFactory::make("fakesink").property("sync", false).build()?;
+    // Measures the encoders only; nothing gets recorded.
+    let settings = EncodingSettings { record_path: None, ..settings.clone() };
+    let processing_bin = build_processing_bin(&settings, Some("flvmux"), &sink)?;
+
+    pipeline.add_many(&[&vsrc, &vcaps, &asrc, &acaps, processing_bin.upcast_ref()])?;
+    gst::Element::link_many(&[&vsrc, &vcaps])?;
+    gst::Element::link_many(&[&asrc, &acaps])?;
+    vcaps.link_pads(Some("src"), &processing_bin, Some(VIDEO_SINK_PAD))?;
+    acaps.link_pads(Some("src"), &processing_bin, Some(AUDIO_SINK_PAD))?;
+
+    let frames = Arc::new(AtomicU64::new(0));
+    let encoder = processing_bin.by_name(VIDEO_ENCODER_NAME)
+        .ok_or_else(|| anyhow!("Video encoder not found in processing bin"))?;
+    let frames_clone = frames.clone();
+    encoder.static_pad("src").unwrap().add_probe(gst::PadProbeType::BUFFER, move |_, _| {
+        frames_clone.fetch_add(1, Ordering::Relaxed);
+        gst::PadProbeReturn::Ok
+    });
+
+    let started = Instant::now();
+    pipeline.set_state(gst::State::Playing)?;
+    let bus = pipeline.bus().unwrap();
+    let msg = bus.timed_pop_filtered(
+        gst::ClockTime::from_nseconds(TEST_TIMEOUT.as_nanos() as u64),
+        &[gst::MessageType::Eos, gst::MessageType::Error],
+    );
+    let elapsed = started.elapsed();
+    pipeline.set_state(gst::State::Null)?;
+
+    match msg.as_ref().map(|m| m.view()) {
+        Some(gst::MessageView::Eos(_)) => (),
+        Some(gst::MessageView::Error(err)) => return Err(anyhow!("Self-test failed: {}", err.error())),
+        _ => return Err(anyhow!("Self-test did not finish within {:?}", TEST_TIMEOUT)),
+    }
+
+    let frames_encoded = frames.load(Ordering::Relaxed);
+    let media_duration = Duration::from_secs(TEST_SECONDS);
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    Ok(SelfTestReport {
+        frames_encoded,
+        media_duration,
+        elapsed,
+        fps: frames_encoded as f64 / secs,
+        realtime_factor: media_duration.as_secs_f64() / secs,
+    })
+}
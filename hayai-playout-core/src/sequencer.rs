@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::PlaylistItem;
+
+/// An ident/bumper that is played between regular playlist items.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterstitialConfig {
+    pub uri: String,
+    /// Insert the interstitial after every `every` regular items (1 = between every item).
+    pub every: u32,
+}
+
+/// What happens after the last item of the playlist.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RepeatMode {
+    /// Stop the stream once the last item has played.
+    None,
+    /// Start over from the top.
+    #[default]
+    RepeatAll,
+    /// Play the current item again and again.
+    RepeatOne,
+}
+
+/// What should go on air once the current entry finishes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NextEntry {
+    /// Index of a regular item in the playlist.
+    Item(usize),
+    /// URI of the interstitial to play before the next regular item.
+    Interstitial(String),
+    /// Nothing follows; playout ends cleanly.
+    Stop,
+}
+
+/// Index of the first enabled item following `current_id`, wrapping at the end of the playlist.
+/// Starts from the top when nothing is playing or the current id is no longer in the list.
+/// `None` when no item is enabled.
+pub fn compute_next_index(playlist: &[PlaylistItem], current_id: Option<u64>) -> Option<usize> {
+    next_index_where(playlist, current_id, |item| item.enabled)
+}
+
+/// Like [`compute_next_index`], but only items carrying at least one of `tags` qualify.
+pub fn compute_next_index_with_tags(playlist: &[PlaylistItem], current_id: Option<u64>, tags: &[String]) -> Option<usize> {
+    next_index_where(playlist, current_id, |item| item.enabled && matches_tags(item, tags))
+}
+
+/// Index of the first enabled item before `current_id`, wrapping at the top of the playlist.
+/// Starts from the bottom when nothing is playing or the current id is no longer in the list.
+/// `None` when no item is enabled.
+pub fn compute_previous_index(playlist: &[PlaylistItem], current_id: Option<u64>) -> Option<usize> {
+    let len = playlist.len();
+    let start = current_id
+        .and_then(|id| playlist.iter().position(|item| item.id == id))
+        .unwrap_or(0);
+    (1..=len)
+        .map(|back| (start + len - back) % len)
+        .find(|&i| playlist[i].enabled)
+}
+
+fn matches_tags(item: &PlaylistItem, tags: &[String]) -> bool {
+    item.tags.iter().any(|tag| tags.contains(tag))
+}
+
+fn next_index_where(
+    playlist: &[PlaylistItem],
+    current_id: Option<u64>,
+    playable: impl Fn(&PlaylistItem) -> bool,
+) -> Option<usize> {
+    if playlist.is_empty() {
+        return None;
+    }
+    let start = current_id
+        .and_then(|id| playlist.iter().position(|item| item.id == id))
+        .map_or(0, |current_index| current_index + 1);
+    (start..start + playlist.len())
+        .map(|i| i % playlist.len())
+        .find(|&i| playable(&playlist[i]))
+}
+
+/// Sequencing state that survives between transitions.
+#[derive(Clone, Debug, Default)]
+pub struct Sequencer {
+    interstitial: Option<InterstitialConfig>,
+    tag_filter: Option<Vec<String>>,
+    items_since_interstitial: u32,
+    in_interstitial: bool,
+    stop_after_current: bool,
+    held: bool,
+    repeat_mode: RepeatMode,
+    shuffle: bool,
+    /// Ids already played in the current shuffle round.
+    shuffle_played: HashSet<u64>,
+    rng_state: u64,
+}
+
+impl Sequencer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_interstitial(&mut self, config: Option<InterstitialConfig>) {
+        self.interstitial = config;
+        self.items_since_interstitial = 0;
+    }
+
+    pub fn interstitial(&self) -> Option<&InterstitialConfig> {
+        self.interstitial.as_ref()
+    }
+
+    /// Only plays items tagged with at least one of `tags`; `None` (or no tags) plays everything.
+    pub fn set_tag_filter(&mut self, tags: Option<Vec<String>>) {
+        self.tag_filter = tags.filter(|tags| !tags.is_empty());
+    }
+
+    pub fn tag_filter(&self) -> Option<&[String]> {
+        self.tag_filter.as_deref()
+    }
+
+    pub fn set_repeat_mode(&mut self, mode: RepeatMode) {
+        self.repeat_mode = mode;
+    }
+
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.repeat_mode
+    }
+
+    /// Plays the items in random order, each once per round, without touching the playlist.
+    pub fn set_shuffle(&mut self, enabled: bool) {
+        self.shuffle = enabled;
+        self.shuffle_played.clear();
+    }
+
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    fn playable(&self, item: &PlaylistItem) -> bool {
+        item.enabled && self.tag_filter.as_ref().map_or(true, |tags| matches_tags(item, tags))
+    }
+
+    // The index to play next, and whether getting there starts the playlist over.
+    fn next_index(&mut self, playlist: &[PlaylistItem], current_id: Option<u64>) -> Option<(usize, bool)> {
+        let current_index = current_id.and_then(|id| playlist.iter().position(|item| item.id == id));
+        if self.repeat_mode == RepeatMode::RepeatOne {
+            if let Some(index) = current_index {
+                return Some((index, false));
+            }
+        }
+        if self.shuffle {
+            return self.next_shuffled_index(playlist, current_id);
+        }
+        let next_index = match &self.tag_filter {
+            Some(tags) => compute_next_index_with_tags(playlist, current_id, tags),
+            None => compute_next_index(playlist, current_id),
+        }?;
+        Some((next_index, current_index.map_or(false, |current| next_index <= current)))
+    }
+
+    // Draws from the items not yet played this round; once all have played, a new round
+    // starts, avoiding the item on air so nothing plays twice in a row.
+    fn next_shuffled_index(&mut self, playlist: &[PlaylistItem], current_id: Option<u64>) -> Option<(usize, bool)> {
+        let playable: Vec<usize> = (0..playlist.len()).filter(|&i| self.playable(&playlist[i])).collect();
+        if playable.is_empty() {
+            return None;
+        }
+        let not_current = |&&i: &&usize| Some(playlist[i].id) != current_id;
+        let mut candidates: Vec<usize> = playable.iter()
+            .filter(not_current)
+            .filter(|&&i| !self.shuffle_played.contains(&playlist[i].id))
+            .copied()
+            .collect();
+        let new_round = candidates.is_empty();
+        if new_round {
+            candidates = playable.iter().filter(not_current).copied().collect();
+            if candidates.is_empty() {
+                candidates = playable;
+            }
+        }
+        let pick = candidates[(self.next_random() % candidates.len() as u64) as usize];
+        Some((pick, new_round && !self.shuffle_played.is_empty()))
+    }
+
+    // xorshift64*, seeded from the clock on first use. Plenty for picking the next item.
+    fn next_random(&mut self) -> u64 {
+        if self.rng_state == 0 {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+            self.rng_state = nanos | 1;
+        }
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+        self.rng_state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Arms (or, when already armed, cancels) ending playout once the entry on air finishes.
+    /// Returns whether it is armed afterwards.
+    pub fn toggle_stop_after_current(&mut self) -> bool {
+        self.stop_after_current = !self.stop_after_current;
+        self.stop_after_current
+    }
+
+    pub fn stop_after_current(&self) -> bool {
+        self.stop_after_current
+    }
+
+    /// While held, the entry on air plays again when it finishes instead of advancing.
+    pub fn set_held(&mut self, held: bool) {
+        self.held = held;
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// True while the entry on air is an interstitial rather than a playlist item.
+    pub fn in_interstitial(&self) -> bool {
+        self.in_interstitial
+    }
+
+    /// Decides what follows the current entry. `current_id` is the last regular item that
+    /// went on air; it is `None` before the first item of a run.
+    pub fn next(&mut self, playlist: &[PlaylistItem], current_id: Option<u64>) -> Option<NextEntry> {
+        if self.stop_after_current {
+            self.stop_after_current = false;
+            return Some(NextEntry::Stop);
+        }
+
+        if self.held {
+            if self.in_interstitial {
+                if let Some(config) = &self.interstitial {
+                    return Some(NextEntry::Interstitial(config.uri.clone()));
+                }
+            } else if let Some(index) = current_id.and_then(|id| playlist.iter().position(|item| item.id == id)) {
+                return Some(NextEntry::Item(index));
+            }
+        }
+
+        // Nothing to return to after an interstitial, so don't start one either.
+        let (next_index, wraps) = self.next_index(playlist, current_id)?;
+
+        if self.repeat_mode == RepeatMode::None && wraps {
+            return Some(NextEntry::Stop);
+        }
+
+        // Only a finished regular item counts towards the next interstitial, so an
+        // interstitial can never be followed by another one.
+        if !self.in_interstitial && current_id.is_some() {
+            if let Some(config) = &self.interstitial {
+                self.items_since_interstitial += 1;
+                if self.items_since_interstitial >= config.every.max(1) {
+                    self.items_since_interstitial = 0;
+                    self.in_interstitial = true;
+                    return Some(NextEntry::Interstitial(config.uri.clone()));
+                }
+            }
+        }
+
+        self.in_interstitial = false;
+        if self.shuffle {
+            if wraps {
+                self.shuffle_played.clear();
+            }
+            self.shuffle_played.insert(playlist[next_index].id);
+        }
+        Some(NextEntry::Item(next_index))
+    }
+
+    /// What [`Sequencer::next`] would decide now, leaving the sequencer as it is. As long as
+    /// nothing changes in between, `next` then decides the same.
+    pub fn peek(&self, playlist: &[PlaylistItem], current_id: Option<u64>) -> Option<NextEntry> {
+        self.clone().next(playlist, current_id)
+    }
+
+    /// Forgets per-run state; called when the stream stops.
+    pub fn reset(&mut self) {
+        self.items_since_interstitial = 0;
+        self.in_interstitial = false;
+        self.stop_after_current = false;
+        self.held = false;
+        self.shuffle_played.clear();
+    }
+}
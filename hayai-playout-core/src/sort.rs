@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::PlaylistItem;
+
+/// How [`Streamer::sort_playlist`](crate::Streamer::sort_playlist) reorders the playlist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortOrder {
+    /// By URI, reading numbers by value ([`natural_cmp`]): `ep2` before `ep10`.
+    ByUriNatural,
+    /// By URI, character by character: `ep10` before `ep2`.
+    ByUriAlpha,
+    /// The current order, back to front.
+    Reverse,
+}
+
+/// Sorts `items` in place. Stable, so items that compare equal keep their order.
+pub(crate) fn sort_items(items: &mut [PlaylistItem], order: SortOrder) {
+    match order {
+        SortOrder::ByUriNatural => items.sort_by(|a, b| natural_cmp(&a.uri, &b.uri)),
+        SortOrder::ByUriAlpha => items.sort_by(|a, b| a.uri.cmp(&b.uri)),
+        SortOrder::Reverse => items.reverse(),
+    }
+}
+
+/// Compares the way people read file names: runs of digits by their value, so `clip2` comes
+/// before `clip10`, and everything else case-insensitively. Ties fall back to a plain
+/// comparison so the order is total.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_rest, mut b_rest) = (a, b);
+    loop {
+        match (next_chunk(a_rest), next_chunk(b_rest)) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some((a_chunk, a_tail)), Some((b_chunk, b_tail))) => {
+                let order = compare_chunks(a_chunk, b_chunk);
+                if order != Ordering::Equal {
+                    return order;
+                }
+                a_rest = a_tail;
+                b_rest = b_tail;
+            }
+        }
+    }
+}
+
+// Splits off the leading run of digits or of non-digits.
+fn next_chunk(s: &str) -> Option<(&str, &str)> {
+    let first = s.chars().next()?;
+    let end = s.find(|c: char| c.is_ascii_digit() != first.is_ascii_digit()).unwrap_or(s.len());
+    Some(s.split_at(end))
+}
+
+fn compare_chunks(a: &str, b: &str) -> Ordering {
+    let is_number = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+    if is_number(a) && is_number(b) {
+        // Compare by value without parsing, so any length of number works.
+        let (a_value, b_value) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+        a_value.len().cmp(&b_value.len()).then_with(|| a_value.cmp(b_value))
+    } else {
+        a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase))
+    }
+}
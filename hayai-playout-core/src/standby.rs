@@ -0,0 +1,97 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use log::info;
+
+use crate::freeze::FREEZE_BIN_NAME;
+use crate::selector::{active_source_for, link_to_selector};
+use crate::LOG_OUTPUT;
+
+/// A bars-and-silence source that temporarily replaces the program on the selectors.
+pub(crate) struct Standby {
+    bin: gst::Bin,
+    video_pad: gst::Pad,
+    audio_pad: gst::Pad,
+}
+
+pub(crate) const STANDBY_BIN_NAME: &str = "standby_bin";
+
+fn fed_by(pad: &gst::Pad, bin_name: &str) -> bool {
+    pad.peer()
+        .and_then(|peer| peer.parent_element())
+        .map_or(false, |elem| elem.name() == bin_name)
+}
+
+/// True when the selector's active input is fed by the standby source or a frozen frame
+/// rather than the program.
+pub(crate) fn is_on_air(selector: &gst::Element) -> bool {
+    active_source_for(selector)
+        .map_or(false, |elem| elem.name() == STANDBY_BIN_NAME || elem.name() == FREEZE_BIN_NAME)
+}
+
+/// Picks the input to go back to once `leaving_pad` goes off air: a frozen frame if one is
+/// still held, otherwise the newest program input.
+pub(crate) fn restore_active_pad(selector: &gst::Element, leaving_pad: &gst::Pad) {
+    // Preloaded inputs are blocked until their turn comes.
+    let linked: Vec<gst::Pad> = selector.sink_pads().into_iter()
+        .filter(|pad| pad != leaving_pad && pad.is_linked())
+        .filter(|pad| !pad.peer().map_or(false, |peer| peer.is_blocked()))
+        .collect();
+    // The playlist may have moved on meanwhile, so take the newest program input rather than
+    // the one that was active before.
+    let pad = linked.iter().find(|pad| fed_by(pad, FREEZE_BIN_NAME))
+        .or_else(|| linked.iter().rev().find(|pad| !fed_by(pad, STANDBY_BIN_NAME)));
+    if let Some(pad) = pad {
+        selector.set_property("active-pad", pad);
+    }
+}
+
+fn create_standby_bin() -> Result<gst::Bin> {
+    let bin = gst::Bin::with_name(STANDBY_BIN_NAME);
+    let vsrc = gst::ElementFactory::make("videotestsrc")
+        .property_from_str("pattern", "smpte")
+        .property("is-live", true)
+        .build()?;
+    let asrc = gst::ElementFactory::make("audiotestsrc")
+        .property_from_str("wave", "silence")
+        .property("is-live", true)
+        .build()?;
+    bin.add_many(&[&vsrc, &asrc])?;
+
+    let vpad = gst::GhostPad::with_target(&vsrc.static_pad("src").unwrap())?;
+    vpad.set_property("name", "video_src");
+    bin.add_pad(&vpad)?;
+    let apad = gst::GhostPad::with_target(&asrc.static_pad("src").unwrap())?;
+    apad.set_property("name", "audio_src");
+    bin.add_pad(&apad)?;
+    Ok(bin)
+}
+
+impl Standby {
+    /// Adds the standby source to the pipeline and makes it the active selector input.
+    pub(crate) fn enter(pipeline: &gst::Pipeline, vs: &gst::Element, as_: &gst::Element) -> Result<Self> {
+        info!(target: LOG_OUTPUT, "Switching program to standby.");
+        let bin = create_standby_bin()?;
+        pipeline.add(&bin)?;
+
+        let video_pad = link_to_selector(&bin.static_pad("video_src").unwrap(), vs)?;
+        let audio_pad = link_to_selector(&bin.static_pad("audio_src").unwrap(), as_)?;
+        bin.sync_state_with_parent()?;
+
+        vs.set_property("active-pad", &video_pad);
+        as_.set_property("active-pad", &audio_pad);
+        Ok(Self { bin, video_pad, audio_pad })
+    }
+
+    /// Returns the selectors to the program and removes the standby source.
+    pub(crate) fn leave(self, pipeline: &gst::Pipeline, vs: &gst::Element, as_: &gst::Element) {
+        info!(target: LOG_OUTPUT, "Leaving standby, returning to program.");
+        restore_active_pad(vs, &self.video_pad);
+        restore_active_pad(as_, &self.audio_pad);
+
+        let _ = self.bin.set_state(gst::State::Null);
+        vs.release_request_pad(&self.video_pad);
+        as_.release_request_pad(&self.audio_pad);
+        let _ = pipeline.remove(&self.bin);
+    }
+}
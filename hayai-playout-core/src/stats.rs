@@ -0,0 +1,103 @@
+use gstreamer as gst;
+use gst::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// How far back the bitrate is averaged.
+const BITRATE_WINDOW: Duration = Duration::from_secs(3);
+
+/// How the stream is doing, from [`Streamer::get_stats`](crate::Streamer::get_stats).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StreamStats {
+    /// Bytes handed to the output sink since the stream started.
+    pub bytes_sent: u64,
+    /// What went to the output over the last few seconds, in kbit/s, muxing overhead included.
+    pub bitrate_kbps: f64,
+    /// Frames dropped on the way: by the frame rate conversion, and by elements that report
+    /// dropping them under load.
+    pub dropped_frames: u64,
+    /// How long the stream has been running, time spent paused included.
+    pub uptime: Duration,
+}
+
+/// Average bitrate in kbit/s between two `(time, total bytes)` readings.
+pub fn bitrate_between(earlier: (Instant, u64), later: (Instant, u64)) -> f64 {
+    let elapsed = later.0.saturating_duration_since(earlier.0).as_secs_f64();
+    if elapsed <= 0.0 {
+        return 0.0;
+    }
+    later.1.saturating_sub(earlier.1) as f64 * 8.0 / 1000.0 / elapsed
+}
+
+/// Counts what goes out while a stream runs. The output pad is counted in a probe; the bus
+/// thread takes the readings the bitrate is worked out from and notes what elements drop.
+#[derive(Debug)]
+pub(crate) struct StatsCounter {
+    started: Instant,
+    bytes: Arc<AtomicU64>,
+    readings: Mutex<VecDeque<(Instant, u64)>>,
+    /// Frames each element has dropped so far, by element name.
+    dropped: Mutex<HashMap<String, u64>>,
+}
+
+impl StatsCounter {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            bytes: Arc::default(),
+            readings: Mutex::default(),
+            dropped: Mutex::default(),
+        }
+    }
+
+    /// Counts the bytes going into `sink`, on all its pads for sinks that mux themselves.
+    pub(crate) fn attach(&self, sink: &gst::Element) {
+        for pad in sink.sink_pads() {
+            let bytes = self.bytes.clone();
+            pad.add_probe(gst::PadProbeType::BUFFER | gst::PadProbeType::BUFFER_LIST, move |_, info| {
+                let size = match &info.data {
+                    Some(gst::PadProbeData::Buffer(buffer)) => buffer.size(),
+                    Some(gst::PadProbeData::BufferList(list)) => list.calculate_size(),
+                    _ => 0,
+                };
+                bytes.fetch_add(size as u64, Ordering::Relaxed);
+                gst::PadProbeReturn::Ok
+            });
+        }
+    }
+
+    /// Takes a reading for the bitrate, forgetting those too old to matter.
+    pub(crate) fn sample(&self) {
+        let now = Instant::now();
+        let mut readings = self.readings.lock().unwrap();
+        readings.push_back((now, self.bytes.load(Ordering::Relaxed)));
+        // One reading older than the window stays, so the window is always covered.
+        while readings.len() > 2 && readings[1].0 + BITRATE_WINDOW < now {
+            readings.pop_front();
+        }
+    }
+
+    /// Notes that `element` has dropped `dropped` frames in all so far.
+    pub(crate) fn record_dropped(&self, element: &str, dropped: u64) {
+        self.dropped.lock().unwrap().insert(element.to_string(), dropped);
+    }
+
+    /// The stats now, with `more_dropped` frames dropped besides those recorded.
+    pub(crate) fn snapshot(&self, more_dropped: u64) -> StreamStats {
+        let bitrate_kbps = {
+            let readings = self.readings.lock().unwrap();
+            match (readings.front(), readings.back()) {
+                (Some(&first), Some(&last)) => bitrate_between(first, last),
+                _ => 0.0,
+            }
+        };
+        StreamStats {
+            bytes_sent: self.bytes.load(Ordering::Relaxed),
+            bitrate_kbps,
+            dropped_frames: self.dropped.lock().unwrap().values().sum::<u64>() + more_dropped,
+            uptime: self.started.elapsed(),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+/// Joins an ingest URL and a stream key the way services such as Twitch and YouTube hand
+/// them out, with exactly one `/` between the two. An empty key leaves the URL unchanged.
+pub fn join_stream_key(base_url: &str, stream_key: &str) -> String {
+    let base_url = base_url.trim();
+    let stream_key = stream_key.trim().trim_start_matches('/');
+    if stream_key.is_empty() {
+        return base_url.to_string();
+    }
+    format!("{}/{}", base_url.trim_end_matches('/'), stream_key)
+}
+
+const MASK: &str = "****";
+
+fn is_rtmp(scheme: &str) -> bool {
+    matches!(scheme.to_ascii_lowercase().as_str(), "rtmp" | "rtmps" | "rtmpt" | "rtmpe" | "rtmpte" | "rtmpts")
+}
+
+/// Masks the secrets in a URL before it is logged or reported: user info, the query string,
+/// and for RTMP the stream key (the path segment after the application).
+/// Text that is not a URL is returned unchanged.
+pub fn redact_url(url: &str) -> String {
+    let (scheme, rest) = match url.split_once("://") {
+        Some(parts) => parts,
+        None => return url.to_string(),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, _)) => (rest, format!("?{}", MASK)),
+        None => (rest, String::new()),
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+    let authority = match authority.rsplit_once('@') {
+        Some((_, host)) => format!("{}@{}", MASK, host),
+        None => authority.to_string(),
+    };
+    let path = match path.trim_end_matches('/').rsplit_once('/') {
+        // Only "/app/key" has a key; "/app" alone does not.
+        Some((app, key)) if is_rtmp(scheme) && !app.is_empty() && !key.is_empty() => format!("{}/{}", app, MASK),
+        _ => path.to_string(),
+    };
+    format!("{}://{}{}{}", scheme, authority, path, query)
+}
+
+/// Applies [`redact_url`] to every URL inside free text, such as a GStreamer error message.
+pub fn redact_urls_in(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() || c == '"' || c == '\'' {
+            out.push_str(&redact_url(&word));
+            word.clear();
+            out.push(c);
+        } else {
+            word.push(c);
+        }
+    }
+    out.push_str(&redact_url(&word));
+    out
+}
+
+/// A title to show for `uri` when its file carries none: the file name without extension,
+/// with percent-escapes decoded and the query string and fragment left out.
+pub fn title_from_uri(uri: &str) -> String {
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or(path);
+    let name = percent_decode(name);
+    match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem.to_string(),
+        _ => name,
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
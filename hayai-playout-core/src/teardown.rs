@@ -0,0 +1,265 @@
+use gstreamer as gst;
+use gst::prelude::*;
+use log::warn;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::freeze::FREEZE_BIN_NAME;
+use crate::standby::STANDBY_BIN_NAME;
+use crate::pipeline::{
+    AUDIO_SINK_PAD, MUX_NAME, OUTPUT_SINK_NAME, PROCESSING_BIN_NAME, RECORD_MUX_NAME, RECORD_SINK_NAME, VIDEO_SINK_PAD,
+};
+use crate::{LOG_OUTPUT, SOURCE_NAME_PREFIX};
+
+/// How long stopping waits for the end of stream to drain through the encoders and muxers.
+pub(crate) const EOS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One stage of stopping a running stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TeardownStep {
+    /// Stop the sources from producing more program.
+    PauseSources,
+    /// Push EOS into the output chain and wait (bounded) for it to reach the sinks, which
+    /// also finalizes a recording.
+    SendEos,
+    StopSources,
+    StopMux,
+    StopSink,
+    StopPipeline,
+}
+
+/// Upstream first, so no element is left pushing into one that is already gone.
+pub const TEARDOWN_ORDER: [TeardownStep; 6] = [
+    TeardownStep::PauseSources,
+    TeardownStep::SendEos,
+    TeardownStep::StopSources,
+    TeardownStep::StopMux,
+    TeardownStep::StopSink,
+    TeardownStep::StopPipeline,
+];
+
+/// True for elements that feed the selectors: playlist items, standby and frozen frames.
+pub(crate) fn is_source_name(name: &str) -> bool {
+    name.starts_with(SOURCE_NAME_PREFIX) || name == STANDBY_BIN_NAME || name == FREEZE_BIN_NAME
+}
+
+fn sources(pipeline: &gst::Pipeline) -> Vec<gst::Element> {
+    pipeline.children().into_iter()
+        .filter(|elem| is_source_name(elem.name().as_str()))
+        .collect()
+}
+
+/// Ends the output stream cleanly, then takes the pipeline down in [`TEARDOWN_ORDER`].
+pub(crate) fn run(pipeline: &gst::Pipeline) -> Result<(), gst::StateChangeError> {
+    for step in TEARDOWN_ORDER {
+        match step {
+            TeardownStep::PauseSources => {
+                for source in sources(pipeline) {
+                    let _ = source.set_state(gst::State::Paused);
+                }
+            }
+            TeardownStep::SendEos => drain(pipeline),
+            TeardownStep::StopSources => {
+                for source in sources(pipeline) {
+                    let _ = source.set_state(gst::State::Null);
+                }
+            }
+            TeardownStep::StopMux => {
+                for mux in [MUX_NAME, RECORD_MUX_NAME].iter().filter_map(|name| pipeline.by_name(name)) {
+                    let _ = mux.set_state(gst::State::Null);
+                }
+            }
+            TeardownStep::StopSink => {
+                for sink in sinks(pipeline) {
+                    let _ = sink.set_state(gst::State::Null);
+                }
+            }
+            TeardownStep::StopPipeline => {
+                pipeline.set_state(gst::State::Null)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sinks(pipeline: &gst::Pipeline) -> Vec<gst::Element> {
+    [OUTPUT_SINK_NAME, RECORD_SINK_NAME].iter().filter_map(|name| pipeline.by_name(name)).collect()
+}
+
+// EOS goes straight into the output chain, behind the selectors, so the playlist does not
+// mistake it for the end of an item.
+fn drain(pipeline: &gst::Pipeline) {
+    let bin = match pipeline.by_name(PROCESSING_BIN_NAME) {
+        Some(bin) => bin,
+        None => return,
+    };
+    if pipeline.current_state() != gst::State::Playing {
+        return;
+    }
+    // The HLS sinks have a pad per stream.
+    let sink_pads: Vec<gst::Pad> = sinks(pipeline).iter().flat_map(|sink| sink.sink_pads()).collect();
+    if sink_pads.is_empty() {
+        return;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let probes: Vec<_> = sink_pads.iter().map(|sink_pad| {
+        let tx = tx.clone();
+        let probe = sink_pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |_, info| {
+            if let Some(gst::PadProbeData::Event(event)) = &info.data {
+                if event.type_() == gst::EventType::Eos {
+                    let _ = tx.send(());
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
+        (sink_pad, probe)
+    }).collect();
+
+    for pad_name in [VIDEO_SINK_PAD, AUDIO_SINK_PAD] {
+        if let Some(pad) = bin.static_pad(pad_name) {
+            pad.send_event(gst::event::Eos::new());
+        }
+    }
+    let deadline = Instant::now() + EOS_TIMEOUT;
+    for _ in 0..sink_pads.len() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if rx.recv_timeout(remaining).is_err() {
+            warn!(target: LOG_OUTPUT, "Output did not drain within {:?}, stopping anyway.", EOS_TIMEOUT);
+            break;
+        }
+    }
+    for (sink_pad, probe) in probes {
+        if let Some(probe) = probe {
+            sink_pad.remove_probe(probe);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    // A live source into a processing bin that passes it through a stand-in mux to a fakesink,
+    // named the way teardown looks for them.
+    fn small_pipeline() -> Result<gst::Pipeline> {
+        let bin = gst::parse::bin_from_description(&format!(
+            "queue name=input ! identity name={} ! fakesink name={}",
+            MUX_NAME, OUTPUT_SINK_NAME
+        ), false)?;
+        bin.set_property("name", PROCESSING_BIN_NAME);
+        let input = bin.by_name("input").unwrap().static_pad("sink").unwrap();
+        bin.add_pad(&gst::GhostPad::builder_with_target(&input)?.name(VIDEO_SINK_PAD).build())?;
+
+        let pipeline = gst::Pipeline::new();
+        let source = gst::ElementFactory::make("fakesrc")
+            .name(format!("{}0", SOURCE_NAME_PREFIX))
+            .property("is-live", true)
+            .build()?;
+        pipeline.add_many(&[&source, bin.upcast_ref()])?;
+        source.link_pads(None, &bin, Some(VIDEO_SINK_PAD))?;
+        Ok(pipeline)
+    }
+
+    // Every state an element reaches, as "name State", and "eos" when the end of stream gets
+    // to the output sink, in the order they happen.
+    fn watch(pipeline: &gst::Pipeline) -> Arc<Mutex<Vec<String>>> {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let states = log.clone();
+        pipeline.bus().unwrap().set_sync_handler(move |_, msg| {
+            if let (gst::MessageView::StateChanged(sc), Some(src)) = (msg.view(), msg.src()) {
+                states.lock().unwrap().push(format!("{} {:?}", src.name(), sc.current()));
+            }
+            gst::BusSyncReply::Drop
+        });
+        let eos = log.clone();
+        let sink_pad = pipeline.by_name(OUTPUT_SINK_NAME).unwrap().static_pad("sink").unwrap();
+        sink_pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |_, info| {
+            if let Some(gst::PadProbeData::Event(event)) = &info.data {
+                if event.type_() == gst::EventType::Eos {
+                    eos.lock().unwrap().push("eos".to_string());
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
+        log
+    }
+
+    fn start(pipeline: &gst::Pipeline) -> Result<()> {
+        pipeline.set_state(gst::State::Playing)?;
+        let (result, state, _) = pipeline.state(gst::ClockTime::from_seconds(5));
+        result?;
+        assert_eq!(state, gst::State::Playing);
+        std::thread::sleep(Duration::from_millis(200));
+        Ok(())
+    }
+
+    #[test]
+    fn test_teardown_drains_then_stops_upstream_first() -> Result<()> {
+        gst::init()?;
+        let pipeline = small_pipeline()?;
+        let log = watch(&pipeline);
+        start(&pipeline)?;
+        log.lock().unwrap().clear();
+
+        run(&pipeline)?;
+        assert_eq!(pipeline.current_state(), gst::State::Null);
+
+        let log = log.lock().unwrap();
+        let at = |entry: &str| log.iter().position(|e| e == entry).unwrap_or_else(|| panic!("No {} in {:?}", entry, log));
+        let source = format!("{}0", SOURCE_NAME_PREFIX);
+        assert!(at(&format!("{} Paused", source)) < at("eos"), "Sources pause before the drain");
+        assert!(at("eos") < at(&format!("{} Ready", source)), "The drain finishes before sources stop");
+        assert!(at(&format!("{} Null", source)) < at(&format!("{} Ready", MUX_NAME)));
+        assert!(at(&format!("{} Null", MUX_NAME)) < at(&format!("{} Ready", OUTPUT_SINK_NAME)));
+        Ok(())
+    }
+
+    // Like `small_pipeline`, but a test picture that is also recorded to `record_path` as MP4.
+    fn recording_pipeline(record_path: &Path) -> Result<gst::Pipeline> {
+        let bin = gst::parse::bin_from_description(&format!(
+            "tee name=video_tee ! queue ! x264enc tune=zerolatency ! h264parse ! mp4mux name={} ! filesink name={} \
+             video_tee. ! queue ! fakesink name={}",
+            RECORD_MUX_NAME, RECORD_SINK_NAME, OUTPUT_SINK_NAME
+        ), false)?;
+        bin.set_property("name", PROCESSING_BIN_NAME);
+        bin.by_name(RECORD_SINK_NAME).unwrap().set_property("location", record_path.to_str().unwrap());
+        let tee_pad = bin.by_name("video_tee").unwrap().static_pad("sink").unwrap();
+        bin.add_pad(&gst::GhostPad::builder_with_target(&tee_pad)?.name(VIDEO_SINK_PAD).build())?;
+
+        let pipeline = gst::Pipeline::new();
+        let src = gst::ElementFactory::make("videotestsrc").property("is-live", true).build()?;
+        let caps = gst::ElementFactory::make("capsfilter")
+            .property("caps", gst::Caps::builder("video/x-raw").field("width", 320).field("height", 240).build())
+            .build()?;
+        pipeline.add_many(&[&src, &caps, bin.upcast_ref()])?;
+        src.link(&caps)?;
+        caps.link_pads(None, &bin, Some(VIDEO_SINK_PAD))?;
+        Ok(pipeline)
+    }
+
+    #[test]
+    #[ignore]
+    fn test_teardown_finalizes_the_recording_in_time() -> Result<()> {
+        gst::init()?;
+        let temp_dir = tempfile::tempdir()?;
+        let record_path = temp_dir.path().join("recording.mp4");
+        let pipeline = recording_pipeline(&record_path)?;
+        start(&pipeline)?;
+        std::thread::sleep(Duration::from_secs(1));
+
+        let started = Instant::now();
+        run(&pipeline)?;
+        let took = started.elapsed();
+        assert!(took < EOS_TIMEOUT, "Teardown took {:?}, so the output never drained", took);
+        assert_eq!(pipeline.current_state(), gst::State::Null);
+
+        // mp4mux only writes the index (moov) once the end of stream reaches it.
+        let written = std::fs::read(&record_path)?;
+        assert!(written.windows(4).any(|w| w == b"moov"), "The recording was not finalized");
+        Ok(())
+    }
+}
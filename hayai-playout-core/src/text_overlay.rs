@@ -0,0 +1,120 @@
+use gstreamer as gst;
+use gst::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::logo::Corner;
+
+/// Which edge of the picture the text runs along.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+/// Text and clock burned into the video, set with
+/// [`Streamer::set_text_overlay`](crate::Streamer::set_text_overlay).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextOverlayConfig {
+    /// The caption, e.g. "NOW PLAYING: ..."; `None` shows none.
+    pub text: Option<String>,
+    pub text_position: TextPosition,
+    /// Runs the text right to left as a ticker, this many pixels per frame. Zero keeps it
+    /// still and centered.
+    pub scroll_speed: u32,
+    /// Where the wall clock goes; `None` shows no clock.
+    pub clock: Option<Corner>,
+    /// `strftime` format of the clock.
+    pub clock_format: String,
+    /// Pango font description, e.g. "Sans Bold 24".
+    pub font: String,
+    /// Text color as 0xAARRGGBB.
+    pub color: u32,
+}
+
+impl Default for TextOverlayConfig {
+    fn default() -> Self {
+        Self {
+            text: None,
+            text_position: TextPosition::Bottom,
+            scroll_speed: 0,
+            clock: None,
+            clock_format: "%H:%M:%S".to_string(),
+            font: "Sans 24".to_string(),
+            color: 0xFFFF_FFFF,
+        }
+    }
+}
+
+/// Shows the caption of `config` on `overlay`, a `textoverlay`, or hides it.
+pub(crate) fn apply_text(overlay: &gst::Element, config: &TextOverlayConfig) {
+    overlay.set_property("text", config.text.as_deref().unwrap_or(""));
+    overlay.set_property("font-desc", config.font.as_str());
+    overlay.set_property("color", config.color);
+    overlay.set_property_from_str("halignment", "center");
+    overlay.set_property_from_str("valignment", match config.text_position {
+        TextPosition::Top => "top",
+        TextPosition::Bottom => "bottom",
+    });
+    if config.scroll_speed == 0 {
+        overlay.set_property("deltax", 0i32);
+    }
+    overlay.set_property("silent", config.text.is_none());
+}
+
+/// Shows the clock of `config` on `overlay`, a `clockoverlay`, or hides it.
+pub(crate) fn apply_clock(overlay: &gst::Element, config: &TextOverlayConfig) {
+    let corner = config.clock.unwrap_or_default();
+    overlay.set_property("time-format", config.clock_format.as_str());
+    overlay.set_property("font-desc", config.font.as_str());
+    overlay.set_property("color", config.color);
+    overlay.set_property_from_str("halignment", match corner {
+        Corner::TopLeft | Corner::BottomLeft => "left",
+        Corner::TopRight | Corner::BottomRight => "right",
+    });
+    overlay.set_property_from_str("valignment", match corner {
+        Corner::TopLeft | Corner::TopRight => "top",
+        Corner::BottomLeft | Corner::BottomRight => "bottom",
+    });
+    overlay.set_property("silent", config.clock.is_none());
+}
+
+/// Moves the caption on `overlay` left by the configured speed with every frame, starting
+/// again from the right edge once it has run off the left.
+pub(crate) fn attach_ticker(overlay: &gst::Element, config: Arc<Mutex<TextOverlayConfig>>) {
+    let sink = match overlay.static_pad("video_sink") {
+        Some(pad) => pad,
+        None => return,
+    };
+    let overlay = overlay.downgrade();
+    sink.add_probe(gst::PadProbeType::BUFFER, move |pad, _| {
+        let speed = {
+            let config = config.lock().unwrap();
+            if config.text.is_none() { 0 } else { config.scroll_speed as i32 }
+        };
+        let overlay = match overlay.upgrade() {
+            Some(overlay) => overlay,
+            None => return gst::PadProbeReturn::Remove,
+        };
+        let frame_width = pad.current_caps().as_ref()
+            .and_then(|caps| caps.structure(0).and_then(|s| s.get::<i32>("width").ok()));
+        if let (true, Some(frame_width)) = (speed > 0, frame_width) {
+            // deltax shifts the centered text; it is off screen beyond half of both widths.
+            // Older textoverlays don't report the rendered width; a frame's width is plenty then.
+            let text_width = if overlay.has_property("text-width") {
+                overlay.property::<u32>("text-width") as i32
+            } else {
+                frame_width
+            };
+            let edge = (frame_width + text_width) / 2;
+            let mut deltax = overlay.property::<i32>("deltax") - speed;
+            if deltax < -edge || deltax > edge {
+                deltax = edge;
+            }
+            overlay.set_property("deltax", deltax);
+        }
+        gst::PadProbeReturn::Ok
+    });
+}
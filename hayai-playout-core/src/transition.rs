@@ -0,0 +1,45 @@
+/// Lets exactly one transition end each entry on air.
+///
+/// Every entry gets a generation number when it goes on air. Whatever wants to end it (EOS
+/// from any of its pads, its set duration running out, a manual skip) names that generation;
+/// the first such trigger goes ahead, and later ones for the same or an earlier generation
+/// are ignored, so racing triggers can't advance the playlist twice.
+#[derive(Debug, Default)]
+pub struct TransitionGuard {
+    generation: u64,
+    claimed: bool,
+}
+
+impl TransitionGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generation of the entry on air; 0 before the first one.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Claims the transition away from `generation`. `false` if another trigger already
+    /// claimed it or `generation` is no longer on air.
+    pub fn try_begin(&mut self, generation: u64) -> bool {
+        if generation != self.generation || self.claimed {
+            return false;
+        }
+        self.claimed = true;
+        true
+    }
+
+    /// A new entry is on air: starts and returns its generation.
+    pub fn finish(&mut self) -> u64 {
+        self.generation += 1;
+        self.claimed = false;
+        self.generation
+    }
+
+    /// The claimed transition failed without putting anything new on air; lets the next
+    /// trigger for the current generation try again.
+    pub fn cancel(&mut self) {
+        self.claimed = false;
+    }
+}
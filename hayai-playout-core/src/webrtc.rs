@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gstreamer_sdp as gst_sdp;
+use gstreamer_webrtc as gst_webrtc;
+use gst::prelude::*;
+use log::error;
+use std::sync::Arc;
+
+use crate::pipeline::{add_encoders, PROCESSING_BIN_NAME};
+use crate::{EncodingSettings, LOG_OUTPUT};
+
+const WEBRTC_BIN_NAME: &str = "webrtc";
+const VIDEO_PAYLOAD_TYPE: u32 = 96;
+const AUDIO_PAYLOAD_TYPE: u32 = 97;
+
+/// Hooks through which the local side of the session is handed to the application.
+#[derive(Clone)]
+pub struct WebRtcSignaling {
+    /// Receives the SDP offer to send to the peer.
+    pub on_offer: Arc<dyn Fn(String) + Send + Sync>,
+    /// Receives each local ICE candidate as (m-line index, candidate).
+    pub on_ice_candidate: Arc<dyn Fn(u32, String) + Send + Sync>,
+}
+
+/// RTP payloaders for the configured video and audio encoders.
+pub fn payloaders_for(video_encoder: &str, audio_encoder: &str) -> Result<(&'static str, &'static str)> {
+    let video = if video_encoder.contains("h264") || video_encoder == "x264enc" {
+        "rtph264pay"
+    } else if video_encoder.contains("vp8") {
+        "rtpvp8pay"
+    } else if video_encoder.contains("vp9") {
+        "rtpvp9pay"
+    } else {
+        return Err(anyhow!("Video encoder {} has no WebRTC payloader; use H.264, VP8 or VP9", video_encoder));
+    };
+    let audio = if audio_encoder.contains("opus") {
+        "rtpopuspay"
+    } else {
+        return Err(anyhow!("Audio encoder {} is not usable for WebRTC; use Opus", audio_encoder));
+    };
+    Ok((video, audio))
+}
+
+/// Builds the experimental WebRTC output: the regular encoders feed RTP payloaders into a
+/// single-peer `webrtcbin`. Exposes the same `video_sink`/`audio_sink` ghost pads as the RTMP output.
+pub fn build_webrtc_bin(settings: &EncodingSettings, signaling: WebRtcSignaling) -> Result<gst::Bin> {
+    let (video_pay, audio_pay) = payloaders_for(&settings.video_encoder, &settings.audio_encoder)?;
+
+    let bin = gst::Bin::with_name(PROCESSING_BIN_NAME);
+    let (venc, aenc) = add_encoders(&bin, settings)?;
+    let vpay = gst::ElementFactory::make(video_pay).property("pt", VIDEO_PAYLOAD_TYPE).build()?;
+    if vpay.has_property("config-interval") { vpay.set_property("config-interval", -1i32); }
+    let apay = gst::ElementFactory::make(audio_pay).property("pt", AUDIO_PAYLOAD_TYPE).build()?;
+    let webrtc = gst::ElementFactory::make("webrtcbin").name(WEBRTC_BIN_NAME).build()?;
+    webrtc.set_property_from_str("bundle-policy", "max-bundle");
+
+    bin.add_many(&[&vpay, &apay, &webrtc])?;
+    venc.link(&vpay)?;
+    aenc.link(&apay)?;
+    vpay.link(&webrtc)?;
+    apay.link(&webrtc)?;
+
+    let on_offer = signaling.on_offer.clone();
+    webrtc.connect("on-negotiation-needed", false, move |values| {
+        if let Ok(webrtc) = values[0].get::<gst::Element>() {
+            let webrtc_clone = webrtc.clone();
+            let on_offer = on_offer.clone();
+            let promise = gst::Promise::with_change_func(move |reply| {
+                let offer = match reply {
+                    Ok(Some(reply)) => reply.get::<gst_webrtc::WebRTCSessionDescription>("offer").ok(),
+                    _ => None,
+                };
+                match offer {
+                    Some(offer) => {
+                        webrtc_clone.emit_by_name::<()>("set-local-description", &[&offer, &None::<gst::Promise>]);
+                        match offer.sdp().as_text() {
+                            Ok(text) => on_offer(text),
+                            Err(e) => error!(target: LOG_OUTPUT, "Could not serialize WebRTC offer: {}", e),
+                        }
+                    }
+                    None => error!(target: LOG_OUTPUT, "webrtcbin did not create an offer"),
+                }
+            });
+            webrtc.emit_by_name::<()>("create-offer", &[&None::<gst::Structure>, &promise]);
+        }
+        None
+    });
+
+    let on_ice_candidate = signaling.on_ice_candidate.clone();
+    webrtc.connect("on-ice-candidate", false, move |values| {
+        if let (Ok(mline), Ok(candidate)) = (values[1].get::<u32>(), values[2].get::<String>()) {
+            on_ice_candidate(mline, candidate);
+        }
+        None
+    });
+
+    Ok(bin)
+}
+
+fn webrtcbin(pipeline: &gst::Pipeline) -> Result<gst::Element> {
+    pipeline.by_name(WEBRTC_BIN_NAME).ok_or_else(|| anyhow!("Stream has no WebRTC output"))
+}
+
+pub(crate) fn set_remote_answer(pipeline: &gst::Pipeline, sdp: &str) -> Result<()> {
+    let webrtc = webrtcbin(pipeline)?;
+    let sdp = gst_sdp::SDPMessage::parse_buffer(sdp.as_bytes())
+        .map_err(|_| anyhow!("Invalid SDP answer"))?;
+    let answer = gst_webrtc::WebRTCSessionDescription::new(gst_webrtc::WebRTCSDPType::Answer, sdp);
+    webrtc.emit_by_name::<()>("set-remote-description", &[&answer, &None::<gst::Promise>]);
+    Ok(())
+}
+
+pub(crate) fn add_ice_candidate(pipeline: &gst::Pipeline, mline_index: u32, candidate: &str) -> Result<()> {
+    let webrtc = webrtcbin(pipeline)?;
+    webrtc.emit_by_name::<()>("add-ice-candidate", &[&mline_index, &candidate]);
+    Ok(())
+}
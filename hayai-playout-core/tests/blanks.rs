@@ -0,0 +1,90 @@
+use hayai_playout_core::{mean_luma, peak_dbfs, BlankThresholds, LeadingBlankDetector};
+use std::time::Duration;
+
+fn gray_frame(luma: u8) -> Vec<u8> {
+    vec![luma; 64 * 36]
+}
+
+fn s16_block(amplitude: i16) -> Vec<u8> {
+    (0..480).flat_map(|i| if i % 2 == 0 { amplitude } else { -amplitude }.to_le_bytes()).collect()
+}
+
+fn ms(v: u64) -> Duration {
+    Duration::from_millis(v)
+}
+
+#[test]
+fn test_mean_luma() {
+    assert_eq!(mean_luma(&gray_frame(16)), 16.0);
+    assert_eq!(mean_luma(&[0, 255]), 127.5);
+    assert_eq!(mean_luma(&[]), 0.0);
+}
+
+#[test]
+fn test_peak_dbfs() {
+    assert_eq!(peak_dbfs(&s16_block(0)), f64::NEG_INFINITY);
+    assert!(peak_dbfs(&s16_block(i16::MAX)).abs() < 0.01);
+    let half = peak_dbfs(&s16_block(16384));
+    assert!((half + 6.02).abs() < 0.01, "half scale should be about -6 dBFS, got {}", half);
+}
+
+#[test]
+fn test_black_threshold_is_inclusive() {
+    let thresholds = BlankThresholds::default();
+    let mut detector = LeadingBlankDetector::new(thresholds.clone());
+    detector.push_video_frame(ms(0), &gray_frame(thresholds.black_luma as u8));
+    detector.push_video_frame(ms(40), &gray_frame(thresholds.black_luma as u8 + 1));
+    assert_eq!(detector.trim_point(), Some(ms(40)));
+}
+
+#[test]
+fn test_silence_threshold() {
+    let thresholds = BlankThresholds { silence_dbfs: -40.0, ..BlankThresholds::default() };
+    let mut detector = LeadingBlankDetector::new(thresholds);
+    // -46 dBFS is quiet enough to count as silence, -34 dBFS is not.
+    detector.push_audio(ms(0), &s16_block(164));
+    detector.push_audio(ms(20), &s16_block(655));
+    assert_eq!(detector.trim_point(), Some(ms(20)));
+    assert!(detector.is_done());
+}
+
+#[test]
+fn test_no_trim_when_content_starts_immediately() {
+    let mut detector = LeadingBlankDetector::new(BlankThresholds::default());
+    detector.push_video_frame(ms(0), &gray_frame(128));
+    assert_eq!(detector.trim_point(), None);
+    assert!(detector.is_done());
+}
+
+#[test]
+fn test_earliest_content_wins_across_streams() {
+    let mut detector = LeadingBlankDetector::new(BlankThresholds::default());
+    // Speech over black must not be cut: audio content at 200ms beats video content at 1s.
+    detector.push_video_frame(ms(0), &gray_frame(16));
+    detector.push_audio(ms(0), &s16_block(0));
+    detector.push_audio(ms(200), &s16_block(8000));
+    assert!(!detector.is_done());
+    detector.push_video_frame(ms(1000), &gray_frame(100));
+    assert!(detector.is_done());
+    assert_eq!(detector.trim_point(), Some(ms(200)));
+}
+
+#[test]
+fn test_fully_blank_window_is_not_trimmed() {
+    let thresholds = BlankThresholds { max_trim: ms(500), ..BlankThresholds::default() };
+    let mut detector = LeadingBlankDetector::new(thresholds);
+    for pts in (0..=500).step_by(40) {
+        detector.push_video_frame(ms(pts), &gray_frame(0));
+    }
+    // Content after the window is ignored.
+    detector.push_video_frame(ms(600), &gray_frame(200));
+    assert!(detector.is_done());
+    assert_eq!(detector.trim_point(), None);
+}
+
+#[test]
+fn test_not_done_before_any_stream_is_seen() {
+    let detector = LeadingBlankDetector::new(BlankThresholds::default());
+    assert!(!detector.is_done());
+    assert_eq!(detector.trim_point(), None);
+}
@@ -0,0 +1,75 @@
+use hayai_playout_core::{is_media_file, Streamer, MEDIA_EXTENSIONS};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_add_directory_filters_and_sorts() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    for name in ["clip10.mp4", "clip2.MKV", "clip1.mp4", "notes.txt", ".hidden.mp4"] {
+        fs::write(dir.path().join(name), b"")?;
+    }
+    fs::create_dir(dir.path().join("extras"))?;
+    fs::write(dir.path().join("extras").join("bonus.mov"), b"")?;
+
+    let streamer = Streamer::new()?;
+    let ids = streamer.add_directory(dir.path(), MEDIA_EXTENSIONS, false)?;
+
+    let playlist = streamer.get_playlist_clone();
+    let names: Vec<&str> = playlist.iter().map(|item| item.uri.rsplit('/').next().unwrap()).collect();
+    assert_eq!(names, ["clip1.mp4", "clip2.MKV", "clip10.mp4"]);
+    assert_eq!(ids, playlist.iter().map(|item| item.id).collect::<Vec<_>>());
+    assert!(playlist.iter().all(|item| item.uri.starts_with("file:///")));
+    Ok(())
+}
+
+#[test]
+fn test_add_directory_recursive() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("season 2"))?;
+    fs::create_dir(dir.path().join("season 10"))?;
+    fs::write(dir.path().join("season 10").join("e1.mkv"), b"")?;
+    fs::write(dir.path().join("season 2").join("e1.mkv"), b"")?;
+    fs::write(dir.path().join("intro.mkv"), b"")?;
+
+    let streamer = Streamer::new()?;
+    streamer.add_item("file:///existing.mkv");
+    let ids = streamer.add_directory(dir.path(), &[".mkv"], true)?;
+
+    let playlist = streamer.get_playlist_clone();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(playlist[0].uri, "file:///existing.mkv", "Files are appended");
+    assert!(playlist[1].uri.ends_with("/intro.mkv"));
+    assert!(playlist[2].uri.ends_with("/season%202/e1.mkv"));
+    assert!(playlist[3].uri.ends_with("/season%2010/e1.mkv"));
+    Ok(())
+}
+
+#[test]
+fn test_directory_uris_match_what_is_added() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    for name in ["b.mp4", "a.mp4", "notes.txt"] {
+        fs::write(dir.path().join(name), b"")?;
+    }
+
+    let uris = Streamer::directory_uris(dir.path(), MEDIA_EXTENSIONS, false)?;
+    let streamer = Streamer::new()?;
+    streamer.add_directory(dir.path(), MEDIA_EXTENSIONS, false)?;
+    let added: Vec<String> = streamer.get_playlist_clone().into_iter().map(|item| item.uri).collect();
+    assert_eq!(uris, added);
+    Ok(())
+}
+
+#[test]
+fn test_add_directory_missing_folder() {
+    let streamer = Streamer::new().unwrap();
+    assert!(streamer.add_directory(std::path::Path::new("/nonexistent/folder"), MEDIA_EXTENSIONS, true).is_err());
+    assert!(streamer.get_playlist_clone().is_empty());
+}
+
+#[test]
+fn test_is_media_file() {
+    assert!(is_media_file(Path::new("/media/clip.mp4")));
+    assert!(is_media_file(Path::new("/media/Song.FLAC")));
+    assert!(!is_media_file(Path::new("/media/notes.txt")));
+    assert!(!is_media_file(Path::new("/media/mp4")), "No extension");
+}
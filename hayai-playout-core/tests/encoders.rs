@@ -0,0 +1,37 @@
+use hayai_playout_core::{find_encoder, list_encoders, probe_encoder, EncoderKind, EncoderProfile};
+
+#[test]
+fn test_probe_rejects_bogus_factory() {
+    assert!(!probe_encoder("hayai-no-such-encoder"));
+    assert!(!probe_encoder("hayai-no-such-encoder"), "Cached result is the same");
+}
+
+#[test]
+fn test_list_encoders_is_sorted_by_kind() {
+    let video = list_encoders(EncoderKind::Video);
+    let audio = list_encoders(EncoderKind::Audio);
+    assert!(video.windows(2).all(|w| w[0].name <= w[1].name));
+    assert!(video.iter().all(|v| !audio.iter().any(|a| a.name == v.name)));
+}
+
+#[test]
+fn test_find_encoder_matches_kind() {
+    assert!(find_encoder(EncoderKind::Video, "hayai-no-such-encoder").is_none());
+    for audio in list_encoders(EncoderKind::Audio) {
+        assert!(find_encoder(EncoderKind::Video, &audio.name).is_none());
+        assert_eq!(find_encoder(EncoderKind::Audio, &audio.name), Some(audio));
+    }
+}
+
+#[test]
+#[ignore]
+fn test_probe_marks_x264enc_usable() {
+    assert!(probe_encoder("x264enc"));
+    let x264 = list_encoders(EncoderKind::Video).into_iter()
+        .find(|e| e.name == "x264enc")
+        .expect("x264enc is installed");
+    assert!(x264.usable);
+    assert!(!x264.hardware);
+    assert!(x264.rank > 0, "x264enc is autoplugged");
+    assert_eq!(x264.profile, Some(EncoderProfile::X264));
+}
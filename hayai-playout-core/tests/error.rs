@@ -0,0 +1,61 @@
+use hayai_playout_core::{user_message, StreamerError, Streamer};
+use std::collections::HashSet;
+
+fn all_variants() -> Vec<StreamerError> {
+    vec![
+        StreamerError::MissingPlugin { element: "rtmpsink".to_string() },
+        StreamerError::InvalidUrl { url: "localhost/live".to_string() },
+        StreamerError::TlsUnsupported { url: "rtmps://ingest/live/****".to_string() },
+        StreamerError::AlreadyRunning,
+        StreamerError::NotRunning,
+        StreamerError::ItemNotFound { id: 7 },
+        StreamerError::InvalidPosition { index: 3, len: 2 },
+        StreamerError::NoPlayableItems,
+        StreamerError::UnsupportedUri { uri: "gopher://example.com/clip".to_string() },
+        StreamerError::InvalidBitrate { kbps: 0 },
+        StreamerError::InvalidScale { width: 1279, height: 720 },
+        StreamerError::InvalidKeyframeInterval,
+        StreamerError::InvalidFramerate { num: 30, den: 0 },
+    ]
+}
+
+#[test]
+fn test_every_variant_has_a_distinct_user_message() {
+    let messages: Vec<String> = all_variants().iter().map(StreamerError::user_message).collect();
+    assert!(messages.iter().all(|m| !m.trim().is_empty()));
+    let distinct: HashSet<&String> = messages.iter().collect();
+    assert_eq!(distinct.len(), messages.len(), "Messages must tell the variants apart: {:?}", messages);
+}
+
+#[test]
+fn test_user_message_suggests_a_fix() {
+    let missing = StreamerError::MissingPlugin { element: "rtmpsink".to_string() }.user_message();
+    assert!(missing.contains("rtmpsink") && missing.contains("gst-plugins-bad"));
+    let url = StreamerError::InvalidUrl { url: "localhost/live".to_string() }.user_message();
+    assert!(url.contains("Check the RTMP URL"));
+}
+
+#[test]
+fn test_user_message_for_anyhow_errors() {
+    let err = anyhow::Error::from(StreamerError::NotRunning);
+    assert_eq!(user_message(&err), StreamerError::NotRunning.user_message());
+
+    let wrapped = anyhow::Error::from(StreamerError::NoPlayableItems).context("Failed to prepare first item");
+    assert_eq!(user_message(&wrapped), StreamerError::NoPlayableItems.user_message(), "Context is looked through");
+
+    let other = anyhow::anyhow!("pipeline exploded").context("Self-test failed");
+    assert_eq!(user_message(&other), "Self-test failed: pipeline exploded");
+}
+
+#[test]
+fn test_streamer_returns_typed_errors() {
+    let streamer = Streamer::new().unwrap();
+    let id = streamer.add_item("A");
+
+    let err = streamer.move_item(id, 5).unwrap_err();
+    assert_eq!(err.downcast_ref(), Some(&StreamerError::InvalidPosition { index: 5, len: 1 }));
+    let err = streamer.set_item_enabled(id + 1000, false).unwrap_err();
+    assert_eq!(err.downcast_ref(), Some(&StreamerError::ItemNotFound { id: id + 1000 }));
+    let err = streamer.force_keyframe().unwrap_err();
+    assert_eq!(err.downcast_ref(), Some(&StreamerError::NotRunning));
+}
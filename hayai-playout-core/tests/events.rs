@@ -0,0 +1,42 @@
+use hayai_playout_core::{EventHistory, PlayoutEvent};
+use std::time::{Duration, SystemTime};
+
+fn link_failed(n: u32) -> PlayoutEvent {
+    PlayoutEvent::SourceLinkFailed { uri: format!("file:///{}.mp4", n), error: String::new() }
+}
+
+#[test]
+fn test_events_since_returns_in_order() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    let mut history = EventHistory::new(10, Duration::from_secs(3600));
+    for n in 0..5 {
+        history.record(start + Duration::from_secs(n as u64), link_failed(n));
+    }
+
+    assert_eq!(history.since(start), (0..5).map(link_failed).collect::<Vec<_>>());
+    assert_eq!(history.since(start + Duration::from_secs(3)), [link_failed(3), link_failed(4)]);
+    assert!(history.since(start + Duration::from_secs(10)).is_empty());
+}
+
+#[test]
+fn test_history_evicts_by_count() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    let mut history = EventHistory::new(3, Duration::from_secs(3600));
+    for n in 0..5 {
+        history.record(start + Duration::from_secs(n as u64), link_failed(n));
+    }
+
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.since(start), [link_failed(2), link_failed(3), link_failed(4)]);
+}
+
+#[test]
+fn test_history_evicts_by_age() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    let mut history = EventHistory::new(100, Duration::from_secs(60));
+    history.record(start, PlayoutEvent::ReconnectStandbyEntered);
+    history.record(start + Duration::from_secs(30), PlayoutEvent::ReconnectStandbyLeft);
+    history.record(start + Duration::from_secs(90), PlayoutEvent::ReconnectGaveUp);
+
+    assert_eq!(history.since(start), [PlayoutEvent::ReconnectStandbyLeft, PlayoutEvent::ReconnectGaveUp]);
+}
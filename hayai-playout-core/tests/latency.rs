@@ -0,0 +1,54 @@
+use hayai_playout_core::{EncodingSettings, LatencyParams, LatencyProfile};
+use std::time::Duration;
+
+#[test]
+fn test_ultra_low_latency_params() {
+    assert_eq!(
+        LatencyProfile::UltraLowLatency.params(),
+        LatencyParams {
+            queue_max_time: Duration::from_millis(200),
+            mux_latency: Duration::ZERO,
+            encoder_tune: Some("zerolatency"),
+        }
+    );
+}
+
+#[test]
+fn test_balanced_params() {
+    assert_eq!(
+        LatencyProfile::Balanced.params(),
+        LatencyParams {
+            queue_max_time: Duration::from_secs(1),
+            mux_latency: Duration::from_millis(100),
+            encoder_tune: Some("zerolatency"),
+        }
+    );
+}
+
+#[test]
+fn test_stable_params() {
+    assert_eq!(
+        LatencyProfile::Stable.params(),
+        LatencyParams {
+            queue_max_time: Duration::from_secs(3),
+            mux_latency: Duration::from_millis(500),
+            encoder_tune: None,
+        }
+    );
+}
+
+#[test]
+fn test_profiles_trade_latency_for_buffering() {
+    let ultra = LatencyProfile::UltraLowLatency.params();
+    let balanced = LatencyProfile::Balanced.params();
+    let stable = LatencyProfile::Stable.params();
+    assert!(ultra.queue_max_time < balanced.queue_max_time);
+    assert!(balanced.queue_max_time < stable.queue_max_time);
+    assert!(ultra.mux_latency < balanced.mux_latency);
+    assert!(balanced.mux_latency < stable.mux_latency);
+}
+
+#[test]
+fn test_default_settings_use_balanced_profile() {
+    assert_eq!(EncodingSettings::default().latency_profile, LatencyProfile::Balanced);
+}
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use hayai_playout_core::{create_processing_bin, Corner, EncodingSettings, Streamer, LOGO_OVERLAY_NAME};
+
+#[test]
+fn test_logo_defaults_to_top_right() {
+    assert_eq!(Corner::default(), Corner::TopRight);
+}
+
+#[test]
+fn test_set_logo_rejects_missing_file() {
+    let streamer = Streamer::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.png");
+    assert!(streamer.set_logo(Some(&missing), Corner::BottomRight, 20).is_err());
+}
+
+#[test]
+fn test_set_logo_before_start() {
+    let streamer = Streamer::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("logo.png");
+    std::fs::write(&path, b"not checked until the stream starts").unwrap();
+    streamer.set_logo(Some(&path), Corner::TopLeft, 0).unwrap();
+    streamer.set_logo(None, Corner::TopLeft, 0).unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_logo_overlay_starts_hidden() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    let overlay = bin.by_name(LOGO_OVERLAY_NAME).expect("gdkpixbufoverlay is installed");
+    assert_eq!(overlay.property::<f64>("alpha"), 0.0);
+    Ok(())
+}
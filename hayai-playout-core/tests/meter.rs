@@ -0,0 +1,51 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::glib;
+use gst::prelude::*;
+use hayai_playout_core::{audio_levels, create_processing_bin, EncodingSettings, PlayoutEvent, AUDIO_LEVEL_NAME, LEVEL_FLOOR_DB};
+
+fn level_message(peak: [f64; 2], rms: [f64; 2]) -> gst::Structure {
+    gst::Structure::builder("level")
+        .field("peak", glib::ValueArray::new(peak))
+        .field("rms", glib::ValueArray::new(rms))
+        .build()
+}
+
+#[test]
+fn test_audio_levels_per_channel() -> Result<()> {
+    gst::init()?;
+    let message = level_message([-6.0, -7.5], [-18.0, -20.0]);
+    assert_eq!(audio_levels(&message), Some(PlayoutEvent::AudioLevels {
+        peak_db: vec![-6.0, -7.5],
+        rms_db: vec![-18.0, -20.0],
+    }));
+    Ok(())
+}
+
+#[test]
+fn test_silence_reads_as_the_floor() -> Result<()> {
+    gst::init()?;
+    let message = level_message([f64::NEG_INFINITY, f64::NAN], [-350.0, f64::NEG_INFINITY]);
+    assert_eq!(audio_levels(&message), Some(PlayoutEvent::AudioLevels {
+        peak_db: vec![LEVEL_FLOOR_DB; 2],
+        rms_db: vec![LEVEL_FLOOR_DB; 2],
+    }));
+    Ok(())
+}
+
+#[test]
+fn test_other_messages_are_not_levels() -> Result<()> {
+    gst::init()?;
+    assert_eq!(audio_levels(&gst::Structure::new_empty("GstMultiFileSink")), None);
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_processing_bin_meters_audio() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    let level = bin.by_name(AUDIO_LEVEL_NAME).expect("level in the audio chain");
+    assert!(level.property::<bool>("post-messages"));
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use hayai_playout_core::{
+    create_processing_bin, EncodingSettings, OutputTarget, PlayoutEvent, Streamer, StreamerError, AUDIO_SINK_PAD,
+    AUDIO_TEE_NAME, HLS_PLAYLIST_NAME, MUX_NAME, OUTPUT_SINK_NAME, PREVIEW_TEE_NAME, RECORD_MUX_NAME, RECORD_SINK_NAME,
+    VIDEO_SINK_PAD, VIDEO_TEE_NAME,
+};
+use std::time::Duration;
+
+#[test]
+fn test_output_target_from_url() {
+    assert_eq!(OutputTarget::from("srt://ingest:9000?streamid=key"),
+               OutputTarget::Srt("srt://ingest:9000?streamid=key".to_string()));
+    assert_eq!(OutputTarget::from("SRT://ingest:9000"), OutputTarget::Srt("SRT://ingest:9000".to_string()));
+    assert_eq!(OutputTarget::from("rtmp://ingest/live/key"), OutputTarget::Rtmp("rtmp://ingest/live/key".to_string()));
+    assert_eq!(OutputTarget::from("ingest/live"), OutputTarget::Rtmp("ingest/live".to_string()));
+}
+
+#[test]
+fn test_output_target_validates_scheme() {
+    assert!(OutputTarget::Rtmp("rtmp://ingest/live/key".to_string()).validate().is_ok());
+    assert!(OutputTarget::Rtmp("rtmps://ingest/live/key".to_string()).validate().is_ok());
+    assert!(OutputTarget::Srt("srt://ingest:9000".to_string()).validate().is_ok());
+
+    assert!(matches!(OutputTarget::Rtmp("srt://ingest:9000".to_string()).validate(),
+                     Err(StreamerError::InvalidUrl { .. })));
+    assert!(matches!(OutputTarget::Srt("rtmp://ingest/live".to_string()).validate(),
+                     Err(StreamerError::InvalidUrl { .. })));
+    assert!(OutputTarget::from("ingest/live").validate().is_err(), "No scheme");
+}
+
+#[test]
+fn test_invalid_url_is_masked() {
+    let err = OutputTarget::Srt("rtmp://ingest/live/secret".to_string()).validate().unwrap_err();
+    assert!(!err.to_string().contains("secret"));
+}
+
+#[test]
+#[ignore]
+fn test_srt_output_uses_mpegts() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("srt://127.0.0.1:9000", &EncodingSettings::default())?;
+    let mux = bin.by_name(MUX_NAME).unwrap();
+    assert_eq!(mux.factory().unwrap().name(), "mpegtsmux");
+    let sink = bin.by_name(OUTPUT_SINK_NAME).unwrap();
+    assert_eq!(sink.factory().unwrap().name(), "srtsink");
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_record_path_adds_recording_branch() -> Result<()> {
+    gst::init()?;
+    let dir = tempfile::tempdir()?;
+    let plain = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    assert!(plain.by_name(RECORD_SINK_NAME).is_none());
+
+    for (file, muxer) in [("archive.mkv", "matroskamux"), ("archive.mp4", "mp4mux")] {
+        let settings = EncodingSettings { record_path: Some(dir.path().join(file)), ..EncodingSettings::default() };
+        let bin = create_processing_bin("rtmp://localhost/live/test", &settings)?;
+        assert_eq!(bin.by_name(RECORD_MUX_NAME).unwrap().factory().unwrap().name(), muxer);
+        assert!(bin.by_name(RECORD_SINK_NAME).is_some());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_validate_output_rejects_unknown_scheme() {
+    let err = Streamer::validate_output("http://ingest/live").unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(StreamerError::InvalidUrl { .. })));
+}
+
+#[test]
+fn test_rtmps_needs_tls_capable_sink() {
+    gst::init().unwrap();
+    let result = Streamer::validate_output("rtmps://ingest/live/key");
+    if gst::ElementFactory::find("rtmp2sink").is_some() && gst::ElementFactory::find("flvmux").is_some() {
+        assert!(result.is_ok());
+    } else if gst::ElementFactory::find("rtmpsink").is_some() {
+        assert!(matches!(result.unwrap_err().downcast_ref(), Some(StreamerError::TlsUnsupported { .. })));
+    }
+}
+
+#[test]
+#[ignore]
+fn test_rtmps_output_uses_rtmp2sink() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("rtmps://localhost/live/test", &EncodingSettings::default())?;
+    assert_eq!(bin.by_name(OUTPUT_SINK_NAME).unwrap().factory().unwrap().name(), "rtmp2sink");
+    Ok(())
+}
+
+#[test]
+fn test_add_output_needs_a_running_stream() {
+    let streamer = Streamer::new().unwrap();
+    let err = streamer.add_output(OutputTarget::from("rtmp://ingest/live/key")).unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(StreamerError::NotRunning)));
+    assert!(streamer.extra_outputs().is_empty());
+}
+
+#[test]
+#[ignore]
+fn test_processing_bin_fans_out_through_tees() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    for name in [VIDEO_TEE_NAME, AUDIO_TEE_NAME] {
+        let tee = bin.by_name(name).expect("tee after the encoder");
+        assert_eq!(tee.factory().unwrap().name(), "tee");
+        assert!(tee.property::<bool>("allow-not-linked"));
+    }
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_failed_extra_output_leaves_the_stream_running() -> Result<()> {
+    gst::init()?;
+    let temp_dir = tempfile::tempdir()?;
+    let clip = temp_dir.path().join("clip.mkv");
+    let writer = gst::parse::launch(&format!(
+        "videotestsrc num-buffers=300 ! video/x-raw,framerate=30/1 ! matroskamux name=mux ! filesink location={} \
+         audiotestsrc num-buffers=300 ! mux.",
+        clip.display()
+    ))?;
+    writer.set_state(gst::State::Playing)?;
+    writer.bus().unwrap().timed_pop_filtered(gst::ClockTime::from_seconds(20), &[gst::MessageType::Eos, gst::MessageType::Error]);
+    writer.set_state(gst::State::Null)?;
+
+    let mut streamer = Streamer::new()?;
+    streamer.add_item(&format!("file://{}", clip.display()));
+    let events = streamer.subscribe();
+    streamer.start("rtmp://localhost/live/test", &EncodingSettings::default())?;
+
+    // Nothing listens on this port, so the extra output fails on its own.
+    let unreachable = OutputTarget::from("rtmp://127.0.0.1:1/live/secret");
+    streamer.add_output(unreachable.clone())?;
+    assert!(streamer.add_output(unreachable.clone()).is_err(), "Same destination twice");
+
+    let failed = std::iter::from_fn(|| events.recv_timeout(Duration::from_secs(10)).ok())
+        .find_map(|event| match event {
+            PlayoutEvent::OutputFailed { url, .. } => Some(url),
+            _ => None,
+        });
+    let url = failed.expect("The extra output reports its failure");
+    assert!(!url.contains("secret"));
+    assert!(streamer.extra_outputs().is_empty());
+    assert!(streamer.is_running(), "The main output carries on");
+    assert!(streamer.remove_output(&unreachable).is_err(), "Already taken out");
+    streamer.stop()?;
+    Ok(())
+}
+
+#[test]
+fn test_hls_target() {
+    let target = OutputTarget::hls("/srv/hls/channel1");
+    assert_eq!(target.url(), "/srv/hls/channel1");
+    assert!(target.validate().is_ok(), "Any directory will do");
+    assert_eq!(target, OutputTarget::Hls {
+        dir: "/srv/hls/channel1".into(),
+        segment_duration: Duration::from_secs(4),
+        playlist_length: 5,
+    });
+}
+
+#[test]
+#[ignore]
+fn test_hls_output_writes_playlist_and_prunes_segments() -> Result<()> {
+    gst::init()?;
+    let dir = tempfile::tempdir()?;
+    let hls_dir = dir.path().join("hls");
+    let target = OutputTarget::Hls { dir: hls_dir.clone(), segment_duration: Duration::from_secs(1), playlist_length: 2 };
+    let settings = EncodingSettings { keyframe_interval_seconds: Some(1.0), ..EncodingSettings::default() };
+    let bin = create_processing_bin(target, &settings)?;
+    assert!(bin.by_name(MUX_NAME).is_none(), "The HLS sink muxes itself");
+
+    let pipeline = gst::Pipeline::new();
+    let video = gst::ElementFactory::make("videotestsrc").property("num-buffers", 300i32).build()?;
+    let audio = gst::ElementFactory::make("audiotestsrc").property("num-buffers", 470i32).build()?;
+    pipeline.add_many(&[&video, &audio, bin.upcast_ref()])?;
+    video.link_pads(Some("src"), &bin, Some(VIDEO_SINK_PAD))?;
+    audio.link_pads(Some("src"), &bin, Some(AUDIO_SINK_PAD))?;
+    pipeline.set_state(gst::State::Playing)?;
+    pipeline.bus().unwrap().timed_pop_filtered(gst::ClockTime::from_seconds(30), &[gst::MessageType::Eos, gst::MessageType::Error]);
+    pipeline.set_state(gst::State::Null)?;
+
+    let playlist = std::fs::read_to_string(hls_dir.join(HLS_PLAYLIST_NAME))?;
+    assert!(playlist.starts_with("#EXTM3U"));
+    assert!(playlist.matches(".ts").count() <= 2, "Only the last two segments are listed:\n{}", playlist);
+    let segments = std::fs::read_dir(&hls_dir)?
+        .filter(|entry| entry.as_ref().map_or(false, |e| e.path().extension().map_or(false, |ext| ext == "ts")))
+        .count();
+    assert!(segments > 0 && segments <= 4, "Old segments are pruned, found {}", segments);
+    Ok(())
+}
+
+#[test]
+fn test_udp_target_from_url() {
+    let udp = |host: &str, port| OutputTarget::UdpTs { host: host.to_string(), port };
+    assert_eq!(OutputTarget::from("udp://239.1.1.1:5000"), udp("239.1.1.1", 5000));
+    assert_eq!(OutputTarget::from("UDP://decoder.local:1234/"), udp("decoder.local", 1234));
+    assert_eq!(OutputTarget::from("udp://[ff15::1]:5000"), udp("ff15::1", 5000));
+    assert_eq!(udp("ff15::1", 5000).url(), "udp://[ff15::1]:5000");
+    assert_eq!(udp("239.1.1.1", 5000).url(), "udp://239.1.1.1:5000");
+
+    assert!(OutputTarget::from("udp://239.1.1.1").validate().is_err(), "No port");
+    assert!(OutputTarget::from("udp://239.1.1.1:port").validate().is_err());
+    assert!(udp("239.1.1.1", 0).validate().is_err());
+    assert!(udp("", 5000).validate().is_err());
+    assert!(udp("239.1.1.1", 5000).validate().is_ok());
+}
+
+#[test]
+#[ignore]
+fn test_udp_output_uses_mpegts_with_pcr_interval() -> Result<()> {
+    gst::init()?;
+    let settings = EncodingSettings { ts_pcr_interval_ms: Some(20), ..EncodingSettings::default() };
+    let bin = create_processing_bin("udp://239.1.1.1:5000", &settings)?;
+    let mux = bin.by_name(MUX_NAME).unwrap();
+    assert_eq!(mux.factory().unwrap().name(), "mpegtsmux");
+    assert_eq!(mux.property::<u32>("pcr-interval"), 1800);
+    let sink = bin.by_name(OUTPUT_SINK_NAME).unwrap();
+    assert_eq!(sink.factory().unwrap().name(), "udpsink");
+    assert_eq!(sink.property::<String>("host"), "239.1.1.1");
+    assert_eq!(sink.property::<i32>("port"), 5000);
+    Ok(())
+}
+
+#[test]
+fn test_preview_sink_can_be_set_before_streaming() -> Result<()> {
+    gst::init()?;
+    let streamer = Streamer::new()?;
+    let sink = gst::ElementFactory::make("fakesink").build()?;
+    streamer.set_preview_sink(Some(sink))?;
+    streamer.set_preview_sink(None)?;
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_processing_bin_feeds_preview_before_encoder() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    let tee = bin.by_name(PREVIEW_TEE_NAME).expect("preview tee in the video chain");
+    let encoder = tee.src_pads().first().and_then(|pad| pad.peer()).and_then(|pad| pad.parent_element());
+    assert_eq!(encoder.map(|e| e.name().to_string()).as_deref(), Some("video_encoder"));
+    Ok(())
+}
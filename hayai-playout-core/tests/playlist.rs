@@ -0,0 +1,246 @@
+use gstreamer as gst;
+use hayai_playout_core::{ItemKind, PlaylistItem, Streamer, StreamerError};
+use std::time::Duration;
+
+#[test]
+fn test_old_playlist_json_defaults_new_fields() {
+    let json = r#"[{"id": 1, "uri": "file:///media/a.mp4"}, {"id": 2, "uri": "file:///media/b.mp4"}]"#;
+    let items: Vec<PlaylistItem> = serde_json::from_str(json).unwrap();
+    assert_eq!(items.len(), 2);
+    assert!(items.iter().all(|item| item.enabled), "Items without the field are enabled");
+    assert!(items.iter().all(|item| item.tags.is_empty() && !item.force_software_decode));
+    assert!(items.iter().all(|item| item.duration.is_none()), "Items play to their end by default");
+    assert!(items.iter().all(|item| item.max_duration.is_none()));
+    assert!(items.iter().all(|item| item.kind == ItemKind::Media));
+    assert!(items.iter().all(|item| item.start_offset.is_none()), "Items start at the beginning by default");
+    assert!(items.iter().all(|item| item.media_duration.is_none()));
+    assert!(items.iter().all(|item| item.last_error.is_none()), "Items start out unmarked");
+}
+
+#[test]
+fn test_enabled_flag_round_trips() {
+    let mut item = PlaylistItem::new(1, "file:///media/a.mp4");
+    item.enabled = false;
+    let json = serde_json::to_string(&item).unwrap();
+    let back: PlaylistItem = serde_json::from_str(&json).unwrap();
+    assert!(!back.enabled);
+}
+
+#[test]
+fn test_get_item_and_find_by_uri() {
+    let streamer = Streamer::new().unwrap();
+    let a = streamer.add_item("file:///media/a.mp4");
+    let b = streamer.add_item("file:///media/b.mp4");
+    streamer.add_item("file:///media/a.mp4");
+
+    assert_eq!(streamer.get_item(b).map(|item| item.uri), Some("file:///media/b.mp4".to_string()));
+    assert_eq!(streamer.find_by_uri("file:///media/a.mp4"), Some(a), "The first of the duplicates");
+    assert_eq!(streamer.find_by_uri("file:///media/c.mp4"), None);
+
+    streamer.remove_item(b);
+    assert!(streamer.get_item(b).is_none());
+}
+
+#[test]
+fn test_with_playlist_reads_in_place() {
+    let streamer = Streamer::new().unwrap();
+    assert_eq!(streamer.playlist_len(), 0);
+    let ids: Vec<u64> = ["A", "B", "C"].iter().map(|uri| streamer.add_item(uri)).collect();
+
+    assert_eq!(streamer.playlist_len(), 3);
+    assert_eq!(streamer.with_playlist(|playlist| playlist.iter().map(|item| item.id).collect::<Vec<_>>()), ids);
+    assert_eq!(streamer.with_playlist(|playlist| playlist.last().map(|item| item.uri.clone())), Some("C".to_string()));
+}
+
+#[test]
+fn test_set_item_enabled_keeps_position() {
+    let streamer = Streamer::new().unwrap();
+    for uri in ["A", "B", "C"] {
+        streamer.add_item(uri);
+    }
+    let id = streamer.get_playlist_clone()[1].id;
+    streamer.set_item_enabled(id, false).unwrap();
+    let playlist = streamer.get_playlist_clone();
+    assert_eq!(playlist[1].id, id);
+    assert!(!playlist[1].enabled);
+    assert!(playlist[0].enabled && playlist[2].enabled);
+}
+
+#[test]
+fn test_save_and_load_playlist() {
+    let streamer = Streamer::new().unwrap();
+    for uri in ["file:///media/a.mp4", "file:///media/b.mp4"] {
+        streamer.add_item(uri);
+    }
+    let saved = streamer.get_playlist_clone();
+    streamer.set_item_enabled(saved[1].id, false).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rundown.json");
+    streamer.save_playlist(&path).unwrap();
+
+    let other = Streamer::new().unwrap();
+    other.add_item("file:///media/old.mp4");
+    assert_eq!(other.load_playlist(&path).unwrap(), 2);
+    let loaded = other.get_playlist_clone();
+    let uris: Vec<&str> = loaded.iter().map(|item| item.uri.as_str()).collect();
+    assert_eq!(uris, ["file:///media/a.mp4", "file:///media/b.mp4"], "Loading replaces the playlist");
+    assert!(loaded[0].enabled && !loaded[1].enabled);
+    assert!(loaded.iter().all(|item| saved.iter().all(|old| old.id != item.id)), "Loaded items get fresh ids");
+}
+
+#[test]
+fn test_load_playlist_rejects_garbage() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("notes.json");
+    std::fs::write(&path, "not json").unwrap();
+    let streamer = Streamer::new().unwrap();
+    streamer.add_item("A");
+    assert!(streamer.load_playlist(&path).is_err());
+    assert!(streamer.load_playlist(&dir.path().join("missing.json")).is_err());
+    assert_eq!(streamer.get_playlist_clone().len(), 1, "A failed load leaves the playlist alone");
+}
+
+#[test]
+fn test_set_item_duration() {
+    let streamer = Streamer::new().unwrap();
+    let id = streamer.add_item("https://example.com/live.m3u8");
+    streamer.set_item_duration(id, Some(std::time::Duration::from_secs(90))).unwrap();
+    assert_eq!(streamer.get_playlist_clone()[0].duration, Some(std::time::Duration::from_secs(90)));
+    assert!(streamer.set_item_duration(id + 1000, None).is_err());
+}
+
+#[test]
+fn test_play_length_is_the_shorter_known_length() {
+    let mut item = PlaylistItem::new(1, "file:///media/a.mp4");
+    assert_eq!(item.play_length(), None);
+    item.media_duration = Some(Duration::from_secs(60));
+    assert_eq!(item.play_length(), Some(Duration::from_secs(60)));
+    item.duration = Some(Duration::from_secs(30));
+    assert_eq!(item.play_length(), Some(Duration::from_secs(30)));
+    item.media_duration = None;
+    assert_eq!(item.play_length(), Some(Duration::from_secs(30)));
+}
+
+#[test]
+fn test_total_duration_sums_enabled_items() {
+    let streamer = Streamer::new().unwrap();
+    for _ in 0..3 {
+        streamer.add_item("https://example.com/live.m3u8");
+    }
+    let ids: Vec<u64> = streamer.get_playlist_clone().iter().map(|item| item.id).collect();
+    streamer.set_item_duration(ids[0], Some(Duration::from_secs(60))).unwrap();
+    streamer.set_item_duration(ids[1], Some(Duration::from_secs(30))).unwrap();
+    assert_eq!(streamer.total_duration(), None, "One item has no known length");
+
+    streamer.set_item_enabled(ids[2], false).unwrap();
+    assert_eq!(streamer.total_duration(), Some(gst::ClockTime::from_seconds(90)));
+}
+
+#[test]
+fn test_time_remaining_needs_a_running_stream() {
+    let streamer = Streamer::new().unwrap();
+    let id = streamer.add_item("https://example.com/live.m3u8");
+    streamer.set_item_duration(id, Some(Duration::from_secs(60))).unwrap();
+    assert_eq!(streamer.time_remaining(), None);
+}
+
+#[test]
+fn test_unsupported_uris_are_refused() {
+    let streamer = Streamer::new().unwrap();
+    assert!(Streamer::is_supported_uri("file:///media/a.mp4"), "filesrc is part of GStreamer itself");
+    assert!(!Streamer::is_supported_uri("gopher://example.com/clip"));
+    assert!(!Streamer::is_supported_uri("/media/a.mp4"), "A path is not a URI");
+
+    let err = streamer.add_uri("gopher://example.com/clip").unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(StreamerError::UnsupportedUri { .. })));
+    assert!(streamer.get_playlist_clone().is_empty());
+
+    let id = streamer.add_uri("file:///media/a.mp4").unwrap();
+    assert_eq!(streamer.get_playlist_clone()[0].id, id);
+}
+
+#[test]
+fn test_live_items_need_a_duration() {
+    let streamer = Streamer::new().unwrap();
+    let uri = "file:///media/camera.mkv";
+    assert!(streamer.add_live_item(uri, Duration::ZERO, 200).is_err(), "It would never end");
+    assert!(streamer.add_live_item("gopher://camera/stream", Duration::from_secs(60), 200).is_err());
+    assert!(streamer.get_playlist_clone().is_empty());
+
+    let id = streamer.add_live_item(uri, Duration::from_secs(60), 500).unwrap();
+    let item = streamer.get_playlist_clone().into_iter().find(|item| item.id == id).unwrap();
+    assert_eq!(item.kind, ItemKind::Live { latency_ms: 500 });
+    assert_eq!(item.duration, Some(Duration::from_secs(60)));
+
+    let json = serde_json::to_string(&item).unwrap();
+    let loaded: PlaylistItem = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.kind, item.kind, "The kind is saved with the playlist");
+}
+
+#[test]
+fn test_max_duration_trims_long_files() {
+    let mut item = PlaylistItem::new(1, "file:///media/a.mp4");
+    item.media_duration = Some(Duration::from_secs(60));
+    item.max_duration = Some(Duration::from_secs(15));
+    assert_eq!(item.cut_after(), Some(Duration::from_secs(15)));
+    assert_eq!(item.play_length(), Some(Duration::from_secs(15)));
+
+    item.max_duration = Some(Duration::from_secs(90));
+    assert_eq!(item.play_length(), Some(Duration::from_secs(60)), "A shorter file still ends by itself");
+
+    item.duration = Some(Duration::from_secs(10));
+    assert_eq!(item.cut_after(), Some(Duration::from_secs(10)), "The earlier cut wins");
+}
+
+#[test]
+fn test_set_item_max_duration() {
+    let streamer = Streamer::new().unwrap();
+    streamer.add_item("file:///media/a.mp4");
+    let id = streamer.get_playlist_clone()[0].id;
+    streamer.set_item_max_duration(id, Some(Duration::from_secs(15))).unwrap();
+    assert_eq!(streamer.get_playlist_clone()[0].max_duration, Some(Duration::from_secs(15)));
+    assert!(streamer.set_item_max_duration(id + 1000, None).is_err());
+
+    let json = serde_json::to_string(&streamer.get_playlist_clone()[0]).unwrap();
+    let back: PlaylistItem = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.max_duration, Some(Duration::from_secs(15)), "Saved with the playlist");
+}
+
+#[test]
+fn test_start_offset_shortens_play_length() {
+    let streamer = Streamer::new().unwrap();
+    let id = streamer.add_item("file:///media/a.mp4");
+    streamer.set_item_start_offset(id, Some(Duration::from_secs(10))).unwrap();
+    assert!(streamer.set_item_start_offset(id + 1000, None).is_err());
+
+    let mut item = streamer.get_playlist_clone()[0].clone();
+    assert_eq!(item.start_offset, Some(Duration::from_secs(10)));
+    item.media_duration = Some(Duration::from_secs(60));
+    assert_eq!(item.play_length(), Some(Duration::from_secs(50)));
+    item.duration = Some(Duration::from_secs(30));
+    assert_eq!(item.play_length(), Some(Duration::from_secs(30)), "An in/out segment");
+    item.start_offset = Some(Duration::from_secs(90));
+    item.duration = None;
+    assert_eq!(item.play_length(), Some(Duration::from_secs(60)), "Past the end plays from the start");
+}
+
+#[test]
+fn test_move_items_keeps_the_block_in_order() {
+    let streamer = Streamer::new().unwrap();
+    for uri in ["A", "B", "C", "D", "E"] {
+        streamer.add_item(uri);
+    }
+    let ids: Vec<u64> = streamer.get_playlist_clone().iter().map(|item| item.id).collect();
+    let uris = || streamer.get_playlist_clone().into_iter().map(|item| item.uri).collect::<Vec<_>>();
+
+    // Given out of order; the block keeps its playlist order.
+    streamer.move_items(&[ids[3], ids[1]], 0).unwrap();
+    assert_eq!(uris(), ["B", "D", "A", "C", "E"]);
+    streamer.move_items(&[ids[1], ids[3]], 3).unwrap();
+    assert_eq!(uris(), ["A", "C", "E", "B", "D"]);
+
+    assert!(streamer.move_items(&[ids[0], ids[1]], 4).is_err(), "The block would run past the end");
+    assert!(streamer.move_items(&[ids[0], ids[4] + 1000], 0).is_err());
+    assert_eq!(uris(), ["A", "C", "E", "B", "D"], "Nothing moved");
+}
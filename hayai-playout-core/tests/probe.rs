@@ -0,0 +1,53 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use hayai_playout_core::Streamer;
+
+#[test]
+fn test_probe_item_reports_missing_file() {
+    assert!(Streamer::probe_item("file:///hayai/no/such/file.mp4").is_err());
+}
+
+#[test]
+fn test_probe_all_covers_every_item() {
+    let streamer = Streamer::new().unwrap();
+    streamer.add_item("file:///hayai/no/such/a.mp4");
+    streamer.add_item("file:///hayai/no/such/b.mp4");
+    let results = streamer.probe_all();
+    let uris: Vec<&str> = results.iter().map(|(item, _)| item.uri.as_str()).collect();
+    assert_eq!(uris, ["file:///hayai/no/such/a.mp4", "file:///hayai/no/such/b.mp4"]);
+    assert!(results.iter().all(|(_, info)| info.is_err()));
+}
+
+#[test]
+#[ignore]
+fn test_probe_item_reads_streams() -> Result<()> {
+    gst::init()?;
+    let temp_dir = tempfile::tempdir()?;
+    let path = temp_dir.path().join("clip.mkv");
+    let writer = gst::parse::launch(&format!(
+        "videotestsrc num-buffers=30 ! video/x-raw,width=320,height=240,framerate=30/1 ! matroskamux name=mux \
+         ! filesink location={} audiotestsrc num-buffers=30 ! audio/x-raw,channels=2,rate=48000 ! mux.",
+        path.display()
+    ))?;
+    writer.set_state(gst::State::Playing)?;
+    writer.bus().unwrap().timed_pop_filtered(gst::ClockTime::from_seconds(10), &[gst::MessageType::Eos, gst::MessageType::Error]);
+    writer.set_state(gst::State::Null)?;
+
+    let uri = format!("file://{}", path.display());
+    let info = Streamer::probe_item(&uri)?;
+    let video = info.video.expect("A video stream");
+    assert_eq!((video.width, video.height), (320, 240));
+    assert_eq!(video.framerate, gst::Fraction::new(30, 1));
+    let audio = info.audio.expect("An audio stream");
+    assert_eq!((audio.channels, audio.sample_rate), (2, 48000));
+    assert!(info.duration.is_some());
+
+    let streamer = Streamer::new()?;
+    streamer.add_item(&uri);
+    assert_eq!(streamer.total_duration(), None, "Unknown until probed");
+    streamer.probe_all();
+    let length = streamer.get_playlist_clone()[0].media_duration.expect("Media length recorded by probing");
+    assert_eq!(streamer.total_duration(), Some(gst::ClockTime::from_nseconds(length.as_nanos() as u64)));
+    Ok(())
+}
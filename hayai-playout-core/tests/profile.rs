@@ -0,0 +1,67 @@
+use hayai_playout_core::{EncoderProfile, EncodingSettings, LatencyProfile};
+
+fn property<'a>(properties: &'a [(&'static str, String)], name: &str) -> Option<&'a str> {
+    properties.iter().find(|(n, _)| *n == name).map(|(_, v)| v.as_str())
+}
+
+#[test]
+fn test_every_factory_maps_back_to_its_profile() {
+    for profile in EncoderProfile::ALL {
+        for factory in profile.factories() {
+            assert_eq!(EncoderProfile::for_encoder(factory), Some(profile));
+        }
+    }
+    assert_eq!(EncoderProfile::for_encoder("openh264enc"), None);
+}
+
+#[test]
+fn test_x264_properties_follow_settings() {
+    let settings = EncodingSettings { bitrate_kbps: 2500, speed_preset: "veryfast".to_string(), ..EncodingSettings::default() };
+    let properties = EncoderProfile::X264.properties("x264enc", &settings);
+    assert_eq!(property(&properties, "bitrate"), Some("2500"));
+    assert_eq!(property(&properties, "speed-preset"), Some("veryfast"));
+    assert_eq!(property(&properties, "key-int-max"), Some("60"));
+}
+
+#[test]
+fn test_keyframe_interval_reaches_every_profile() {
+    let settings = EncodingSettings { keyframe_interval_seconds: Some(1.0), ..EncodingSettings::default() };
+    for profile in EncoderProfile::ALL {
+        for factory in profile.factories() {
+            let properties = profile.properties(factory, &settings);
+            let interval = ["key-int-max", "gop-size", "keyframe-period"].iter()
+                .find_map(|name| property(&properties, name));
+            assert_eq!(interval, Some("30"), "{} gets the interval", factory);
+        }
+    }
+}
+
+#[test]
+fn test_hardware_profiles_use_cbr_in_kbps() {
+    let settings = EncodingSettings { bitrate_kbps: 6000, ..EncodingSettings::default() };
+    for profile in [EncoderProfile::Nvenc, EncoderProfile::Vaapi, EncoderProfile::QuickSync] {
+        for factory in profile.factories() {
+            let properties = profile.properties(factory, &settings);
+            assert_eq!(property(&properties, "bitrate"), Some("6000"), "{}", factory);
+            assert!(properties.iter().any(|(_, v)| v == "cbr"), "{} is set to CBR", factory);
+            assert_eq!(property(&properties, "speed-preset"), None, "{} has no x264 presets", factory);
+        }
+    }
+}
+
+#[test]
+fn test_low_latency_only_where_supported() {
+    let low = EncodingSettings { latency_profile: LatencyProfile::UltraLowLatency, ..EncodingSettings::default() };
+    let stable = EncodingSettings { latency_profile: LatencyProfile::Stable, ..EncodingSettings::default() };
+    assert_eq!(property(&EncoderProfile::X264.properties("x264enc", &low), "tune"), Some("zerolatency"));
+    assert_eq!(property(&EncoderProfile::X264.properties("x264enc", &stable), "tune"), None);
+    assert_eq!(property(&EncoderProfile::Nvenc.properties("nvh264enc", &low), "zerolatency"), Some("true"));
+    assert_eq!(property(&EncoderProfile::Nvenc.properties("nvcudah264enc", &low), "zero-latency"), Some("true"));
+}
+
+#[test]
+fn test_unavailable_profile_has_no_factory() {
+    for profile in EncoderProfile::ALL {
+        assert_eq!(profile.is_available(), profile.factory().is_some());
+    }
+}
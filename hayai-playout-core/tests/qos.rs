@@ -0,0 +1,45 @@
+use hayai_playout_core::QosMonitor;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_no_warning_without_drops() {
+    let mut monitor = QosMonitor::new(Duration::from_secs(5));
+    let start = Instant::now();
+    monitor.record("encoder", 100, 0, start);
+    assert_eq!(monitor.poll(start + Duration::from_secs(10)), None);
+}
+
+#[test]
+fn test_drops_are_reported_once_the_window_is_over() {
+    let mut monitor = QosMonitor::new(Duration::from_secs(5));
+    let start = Instant::now();
+    monitor.record("encoder", 100, 0, start);
+    monitor.record("encoder", 110, 2, start + Duration::from_secs(1));
+    monitor.record("sink", 50, 3, start + Duration::from_secs(2));
+    assert_eq!(monitor.poll(start + Duration::from_secs(3)), None, "Window still open");
+    monitor.record("encoder", 120, 4, start + Duration::from_secs(4));
+    assert_eq!(monitor.poll(start + Duration::from_secs(6)), Some((7, 70)));
+    assert_eq!(monitor.poll(start + Duration::from_secs(20)), None, "Reported only once");
+}
+
+#[test]
+fn test_only_new_drops_count_in_the_next_window() {
+    let mut monitor = QosMonitor::new(Duration::from_secs(5));
+    let start = Instant::now();
+    monitor.record("encoder", 100, 5, start);
+    assert_eq!(monitor.poll(start + Duration::from_secs(5)), Some((5, 100)));
+    monitor.record("encoder", 200, 5, start + Duration::from_secs(6));
+    assert_eq!(monitor.poll(start + Duration::from_secs(12)), None);
+    monitor.record("encoder", 210, 6, start + Duration::from_secs(13));
+    assert_eq!(monitor.poll(start + Duration::from_secs(18)), Some((1, 10)));
+}
+
+#[test]
+fn test_restarted_element_counts_from_zero() {
+    let mut monitor = QosMonitor::new(Duration::from_secs(5));
+    let start = Instant::now();
+    monitor.record("encoder", 1000, 50, start);
+    monitor.poll(start + Duration::from_secs(5));
+    monitor.record("encoder", 10, 2, start + Duration::from_secs(6));
+    assert_eq!(monitor.poll(start + Duration::from_secs(11)), Some((2, 10)));
+}
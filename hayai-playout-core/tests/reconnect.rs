@@ -0,0 +1,162 @@
+use hayai_playout_core::{GiveUpPolicy, OutputState, ReconnectAction, ReconnectMonitor, ReconnectPolicy};
+use std::time::Duration;
+
+#[test]
+fn test_standby_entered_on_error_and_left_on_recovery() {
+    let mut monitor = ReconnectMonitor::new(true);
+
+    let actions = monitor.on_sink_error();
+    assert_eq!(actions, [ReconnectAction::EnterStandby, ReconnectAction::Retry { attempt: 1 }]);
+    assert_eq!(monitor.state(), OutputState::Reconnecting { attempt: 1 });
+    assert!(monitor.in_standby());
+
+    let actions = monitor.on_sink_recovered();
+    assert_eq!(actions, [ReconnectAction::LeaveStandby]);
+    assert_eq!(monitor.state(), OutputState::Connected);
+    assert!(!monitor.in_standby());
+}
+
+#[test]
+fn test_repeated_errors_stay_in_standby() {
+    let mut monitor = ReconnectMonitor::new(true);
+    monitor.on_sink_error();
+
+    let actions = monitor.on_sink_error();
+    assert_eq!(actions, [ReconnectAction::Retry { attempt: 2 }]);
+    assert_eq!(monitor.state(), OutputState::Reconnecting { attempt: 2 });
+    assert!(monitor.in_standby());
+
+    assert_eq!(monitor.on_sink_recovered(), [ReconnectAction::LeaveStandby]);
+}
+
+#[test]
+fn test_no_standby_when_disabled() {
+    let mut monitor = ReconnectMonitor::new(false);
+    assert_eq!(monitor.on_sink_error(), [ReconnectAction::Retry { attempt: 1 }]);
+    assert!(!monitor.in_standby());
+    assert!(monitor.on_sink_recovered().is_empty());
+    assert_eq!(monitor.state(), OutputState::Connected);
+}
+
+#[test]
+fn test_recovery_without_error_is_a_no_op() {
+    let mut monitor = ReconnectMonitor::new(true);
+    assert!(monitor.on_sink_recovered().is_empty());
+    assert_eq!(monitor.state(), OutputState::Connected);
+}
+
+#[test]
+fn test_attempts_restart_after_recovery() {
+    let mut monitor = ReconnectMonitor::new(true);
+    monitor.on_sink_error();
+    monitor.on_sink_error();
+    monitor.on_sink_recovered();
+    assert_eq!(monitor.on_sink_error(), [ReconnectAction::EnterStandby, ReconnectAction::Retry { attempt: 1 }]);
+}
+
+fn fail_times(monitor: &mut ReconnectMonitor, times: u32) -> Vec<Vec<ReconnectAction>> {
+    (0..times).map(|_| monitor.on_sink_error()).collect()
+}
+
+#[test]
+fn test_give_up_policy_stop() {
+    let mut monitor = ReconnectMonitor::new(true);
+    monitor.set_policy(ReconnectPolicy {
+        max_attempts: 3,
+        retry_delay: Duration::from_secs(1),
+        max_retry_delay: Duration::from_secs(1),
+        on_give_up: GiveUpPolicy::Stop,
+    });
+
+    let actions = fail_times(&mut monitor, 4);
+    assert_eq!(actions[2], [ReconnectAction::Retry { attempt: 3 }]);
+    assert_eq!(actions[3], [ReconnectAction::GiveUp]);
+    assert_eq!(monitor.state(), OutputState::Connected);
+    assert!(!monitor.in_standby());
+}
+
+#[test]
+fn test_give_up_policy_standby_retries_forever() {
+    let mut monitor = ReconnectMonitor::new(false);
+    monitor.set_policy(ReconnectPolicy {
+        max_attempts: 2,
+        retry_delay: Duration::from_secs(1),
+        max_retry_delay: Duration::from_secs(1),
+        on_give_up: GiveUpPolicy::StandbyAndRetry { interval: Duration::from_secs(30) },
+    });
+
+    let actions = fail_times(&mut monitor, 2);
+    assert_eq!(actions[1], [ReconnectAction::Retry { attempt: 2 }]);
+    assert_eq!(monitor.retry_delay(), Duration::from_secs(1));
+
+    assert_eq!(
+        monitor.on_sink_error(),
+        [
+            ReconnectAction::EnterStandby,
+            ReconnectAction::KeepRetryingInStandby,
+            ReconnectAction::Retry { attempt: 3 },
+        ]
+    );
+    assert!(monitor.in_standby());
+    assert_eq!(monitor.retry_delay(), Duration::from_secs(30));
+
+    for actions in fail_times(&mut monitor, 100) {
+        assert!(matches!(actions[..], [ReconnectAction::Retry { .. }]));
+    }
+    assert_eq!(monitor.state(), OutputState::Reconnecting { attempt: 103 });
+
+    assert_eq!(monitor.on_sink_recovered(), [ReconnectAction::LeaveStandby]);
+    assert_eq!(monitor.retry_delay(), Duration::from_secs(1));
+}
+
+#[test]
+fn test_give_up_standby_keeps_existing_standby() {
+    let mut monitor = ReconnectMonitor::new(true);
+    monitor.set_policy(ReconnectPolicy {
+        max_attempts: 1,
+        retry_delay: Duration::from_secs(1),
+        max_retry_delay: Duration::from_secs(1),
+        on_give_up: GiveUpPolicy::StandbyAndRetry { interval: Duration::from_secs(30) },
+    });
+
+    assert_eq!(monitor.on_sink_error(), [ReconnectAction::EnterStandby, ReconnectAction::Retry { attempt: 1 }]);
+    assert_eq!(
+        monitor.on_sink_error(),
+        [ReconnectAction::KeepRetryingInStandby, ReconnectAction::Retry { attempt: 2 }]
+    );
+}
+
+#[test]
+fn test_retry_delay_backs_off() {
+    let mut monitor = ReconnectMonitor::new(false);
+    monitor.set_policy(ReconnectPolicy {
+        max_attempts: 10,
+        retry_delay: Duration::from_secs(1),
+        max_retry_delay: Duration::from_secs(5),
+        on_give_up: GiveUpPolicy::Stop,
+    });
+
+    let mut delays = Vec::new();
+    for _ in 0..5 {
+        monitor.on_sink_error();
+        delays.push(monitor.retry_delay().as_secs());
+    }
+    assert_eq!(delays, [1, 2, 4, 5, 5]);
+
+    monitor.on_sink_recovered();
+    monitor.on_sink_error();
+    assert_eq!(monitor.retry_delay(), Duration::from_secs(1), "Backoff restarts after a recovery");
+}
+
+#[test]
+fn test_backoff_does_not_overflow() {
+    let mut monitor = ReconnectMonitor::new(false);
+    monitor.set_policy(ReconnectPolicy {
+        max_attempts: u32::MAX,
+        ..ReconnectPolicy::default()
+    });
+    for _ in 0..100 {
+        monitor.on_sink_error();
+    }
+    assert_eq!(monitor.retry_delay(), ReconnectPolicy::default().max_retry_delay);
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use hayai_playout_core::{active_source_for, link_source_to_selectors, release_source_pads};
+
+fn selector() -> Result<gst::Element> {
+    Ok(gst::ElementFactory::make("input-selector").build()?)
+}
+
+// A stand-in source whose src pad is linked straight to a new selector input.
+fn stub_source(selector: &gst::Element) -> Result<(gst::Element, gst::Pad)> {
+    let source = gst::ElementFactory::make("identity").build()?;
+    let sink_pad = selector.request_pad_simple("sink_%u").unwrap();
+    source.static_pad("src").unwrap().link(&sink_pad)?;
+    Ok((source, sink_pad))
+}
+
+#[test]
+fn test_release_source_pads_only_releases_that_source() -> Result<()> {
+    gst::init()?;
+    let selector = selector()?;
+    let (old_source, _) = stub_source(&selector)?;
+    let (new_source, new_pad) = stub_source(&selector)?;
+    stub_source(&selector)?;
+
+    assert_eq!(release_source_pads(&selector, &old_source), 1);
+    assert_eq!(selector.sink_pads().len(), 2);
+    assert!(selector.sink_pads().contains(&new_pad));
+    assert!(!old_source.static_pad("src").unwrap().is_linked());
+    assert!(new_source.static_pad("src").unwrap().is_linked());
+
+    assert_eq!(release_source_pads(&selector, &old_source), 0, "Releasing twice is harmless");
+    Ok(())
+}
+
+#[test]
+fn test_active_source_for() -> Result<()> {
+    gst::init()?;
+    let selector = selector()?;
+    assert!(active_source_for(&selector).is_none());
+
+    let (first, _) = stub_source(&selector)?;
+    let (second, second_pad) = stub_source(&selector)?;
+    selector.set_property("active-pad", &second_pad);
+    assert_eq!(active_source_for(&selector), Some(second.clone()));
+
+    release_source_pads(&selector, &second);
+    assert_eq!(active_source_for(&selector), Some(first), "Selector falls back to the remaining input");
+    Ok(())
+}
+
+#[test]
+fn test_link_ignores_pads_without_caps() -> Result<()> {
+    gst::init()?;
+    let (vs, as_) = (selector()?, selector()?);
+    let source = gst::ElementFactory::make("identity").build()?;
+    assert!(link_source_to_selectors(&source.static_pad("src").unwrap(), &vs, &as_)?.is_none());
+    assert!(vs.sink_pads().is_empty() && as_.sink_pads().is_empty());
+    Ok(())
+}
+
+// A source pad that already carries `caps`, like a decoder pad when pad-added fires.
+fn pad_with_caps(caps: &str) -> Result<(gst::Element, gst::Pad)> {
+    let source = gst::ElementFactory::make("identity").build()?;
+    let pad = source.static_pad("src").unwrap();
+    pad.set_active(true)?;
+    pad.push_event(gst::event::StreamStart::new("test"));
+    pad.push_event(gst::event::Caps::new(&caps.parse::<gst::Caps>()?));
+    Ok((source, pad))
+}
+
+#[test]
+fn test_link_source_to_selectors_by_media_type() -> Result<()> {
+    gst::init()?;
+    let (vs, as_) = (selector()?, selector()?);
+
+    let (_video_src, video_pad) = pad_with_caps("video/x-raw,format=I420,width=64,height=36")?;
+    let (selector, sink_pad) = link_source_to_selectors(&video_pad, &vs, &as_)?.unwrap();
+    assert_eq!(selector, vs);
+    assert_eq!(video_pad.peer(), Some(sink_pad));
+
+    let (_audio_src, audio_pad) = pad_with_caps("audio/x-raw,format=S16LE,rate=48000,channels=2")?;
+    let (selector, _) = link_source_to_selectors(&audio_pad, &vs, &as_)?.unwrap();
+    assert_eq!(selector, as_);
+
+    let (_text_src, text_pad) = pad_with_caps("text/x-raw,format=utf8")?;
+    assert!(link_source_to_selectors(&text_pad, &vs, &as_)?.is_none());
+    assert_eq!((vs.sink_pads().len(), as_.sink_pads().len()), (1, 1));
+
+    assert!(link_source_to_selectors(&video_pad, &vs, &as_).is_err(), "An already linked pad is refused");
+    assert_eq!(vs.sink_pads().len(), 1, "No input is left behind after a refused link");
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_decoded_source_links_and_releases() -> Result<()> {
+    gst::init()?;
+    let temp_dir = tempfile::tempdir()?;
+    let clip_path = temp_dir.path().join("clip.mkv");
+    let writer = gst::parse::launch(&format!(
+        "videotestsrc num-buffers=5 ! matroskamux name=mux ! filesink location={} audiotestsrc num-buffers=5 ! mux.",
+        clip_path.display()
+    ))?;
+    writer.set_state(gst::State::Playing)?;
+    writer.bus().unwrap().timed_pop_filtered(gst::ClockTime::from_seconds(10), &[gst::MessageType::Eos, gst::MessageType::Error]);
+    writer.set_state(gst::State::Null)?;
+
+    let pipeline = gst::Pipeline::new();
+    let (vs, as_) = (selector()?, selector()?);
+    let vsink = gst::ElementFactory::make("fakesink").build()?;
+    let asink = gst::ElementFactory::make("fakesink").build()?;
+    let source = gst::ElementFactory::make("uridecodebin")
+        .property("uri", format!("file://{}", clip_path.display()))
+        .build()?;
+    pipeline.add_many(&[&vs, &as_, &vsink, &asink, &source])?;
+    vs.link(&vsink)?;
+    as_.link(&asink)?;
+    let (vs_clone, as_clone) = (vs.clone(), as_.clone());
+    source.connect_pad_added(move |_, pad| {
+        link_source_to_selectors(pad, &vs_clone, &as_clone).unwrap();
+    });
+
+    pipeline.set_state(gst::State::Playing)?;
+    pipeline.bus().unwrap().timed_pop_filtered(gst::ClockTime::from_seconds(10), &[gst::MessageType::Eos, gst::MessageType::Error]);
+
+    assert_eq!(active_source_for(&vs), Some(source.clone()));
+    assert_eq!(active_source_for(&as_), Some(source.clone()));
+    assert_eq!(release_source_pads(&vs, &source) + release_source_pads(&as_, &source), 2);
+    pipeline.set_state(gst::State::Null)?;
+    Ok(())
+}
@@ -0,0 +1,386 @@
+use hayai_playout_core::{
+    compute_next_index, compute_next_index_with_tags, compute_previous_index, InterstitialConfig, NextEntry, PlaylistItem,
+    RepeatMode, Sequencer,
+};
+
+fn playlist(uris: &[&str]) -> Vec<PlaylistItem> {
+    uris.iter()
+        .enumerate()
+        .map(|(i, uri)| PlaylistItem::new(i as u64 + 1, uri))
+        .collect()
+}
+
+// Drives the sequencer through `steps` transitions and records what went on air.
+fn run(sequencer: &mut Sequencer, playlist: &[PlaylistItem], steps: usize) -> Vec<String> {
+    let mut current_id = None;
+    let mut aired = Vec::new();
+    for _ in 0..steps {
+        match sequencer.next(playlist, current_id).unwrap() {
+            NextEntry::Item(idx) => {
+                current_id = Some(playlist[idx].id);
+                aired.push(playlist[idx].uri.clone());
+            }
+            NextEntry::Interstitial(uri) => aired.push(uri),
+            NextEntry::Stop => aired.push("STOP".to_string()),
+        }
+    }
+    aired
+}
+
+#[test]
+fn test_compute_next_index_wraps() {
+    let playlist = playlist(&["A", "B", "C"]);
+    assert_eq!(compute_next_index(&playlist, None), Some(0));
+    assert_eq!(compute_next_index(&playlist, Some(1)), Some(1));
+    assert_eq!(compute_next_index(&playlist, Some(3)), Some(0));
+    assert_eq!(compute_next_index(&playlist, Some(999)), Some(0));
+    assert_eq!(compute_next_index(&[], None), None);
+}
+
+#[test]
+fn test_no_interstitial_by_default() {
+    let playlist = playlist(&["A", "B"]);
+    let mut sequencer = Sequencer::new();
+    assert_eq!(run(&mut sequencer, &playlist, 4), ["A", "B", "A", "B"]);
+}
+
+#[test]
+fn test_interstitial_every_item() {
+    let playlist = playlist(&["A", "B", "C"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 1 }));
+    assert_eq!(
+        run(&mut sequencer, &playlist, 7),
+        ["A", "bumper", "B", "bumper", "C", "bumper", "A"]
+    );
+}
+
+#[test]
+fn test_interstitial_every_third_item() {
+    let playlist = playlist(&["A", "B", "C", "D"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 3 }));
+    assert_eq!(
+        run(&mut sequencer, &playlist, 9),
+        ["A", "B", "C", "bumper", "D", "A", "B", "bumper", "C"]
+    );
+}
+
+#[test]
+fn test_interstitial_never_follows_interstitial() {
+    let playlist = playlist(&["A"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 1 }));
+    let aired = run(&mut sequencer, &playlist, 10);
+    assert!(aired.windows(2).all(|pair| !(pair[0] == "bumper" && pair[1] == "bumper")));
+    assert!(sequencer.in_interstitial());
+}
+
+#[test]
+fn test_empty_playlist_has_no_next() {
+    let mut sequencer = Sequencer::new();
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 1 }));
+    assert_eq!(sequencer.next(&[], Some(1)), None);
+}
+
+#[test]
+fn test_compute_next_index_skips_disabled() {
+    let mut playlist = playlist(&["A", "B", "C", "D"]);
+    playlist[1].enabled = false;
+    playlist[2].enabled = false;
+    assert_eq!(compute_next_index(&playlist, Some(1)), Some(3));
+    assert_eq!(compute_next_index(&playlist, Some(4)), Some(0));
+
+    playlist[0].enabled = false;
+    assert_eq!(compute_next_index(&playlist, None), Some(3));
+    assert_eq!(compute_next_index(&playlist, Some(4)), Some(3), "A single enabled item repeats");
+}
+
+#[test]
+fn test_compute_previous_index_wraps() {
+    let playlist = playlist(&["A", "B", "C"]);
+    assert_eq!(compute_previous_index(&playlist, Some(2)), Some(0));
+    assert_eq!(compute_previous_index(&playlist, Some(1)), Some(2));
+    assert_eq!(compute_previous_index(&playlist, None), Some(2));
+    assert_eq!(compute_previous_index(&playlist, Some(999)), Some(2));
+    assert_eq!(compute_previous_index(&[], None), None);
+}
+
+#[test]
+fn test_compute_previous_index_skips_disabled() {
+    let mut playlist = playlist(&["A", "B", "C", "D"]);
+    playlist[1].enabled = false;
+    playlist[2].enabled = false;
+    assert_eq!(compute_previous_index(&playlist, Some(4)), Some(0));
+    assert_eq!(compute_previous_index(&playlist, Some(1)), Some(3));
+
+    playlist[0].enabled = false;
+    assert_eq!(compute_previous_index(&playlist, Some(4)), Some(3), "A single enabled item repeats");
+    playlist[3].enabled = false;
+    assert_eq!(compute_previous_index(&playlist, Some(4)), None);
+}
+
+#[test]
+fn test_disabled_current_item_still_advances() {
+    let mut playlist = playlist(&["A", "B", "C"]);
+    playlist[0].enabled = false;
+    assert_eq!(compute_next_index(&playlist, Some(1)), Some(1));
+}
+
+#[test]
+fn test_all_disabled_behaves_like_empty() {
+    let mut playlist = playlist(&["A", "B"]);
+    for item in &mut playlist {
+        item.enabled = false;
+    }
+    assert_eq!(compute_next_index(&playlist, None), None);
+    assert_eq!(compute_next_index(&playlist, Some(1)), None);
+
+    let mut sequencer = Sequencer::new();
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 1 }));
+    assert_eq!(sequencer.next(&playlist, Some(1)), None, "No interstitial without items to return to");
+}
+
+#[test]
+fn test_sequencer_skips_disabled_items() {
+    let mut playlist = playlist(&["A", "B", "C"]);
+    playlist[1].enabled = false;
+    let mut sequencer = Sequencer::new();
+    assert_eq!(run(&mut sequencer, &playlist, 4), ["A", "C", "A", "C"]);
+}
+
+fn tagged(uris_and_tags: &[(&str, &[&str])]) -> Vec<PlaylistItem> {
+    let mut items = playlist(&uris_and_tags.iter().map(|(uri, _)| *uri).collect::<Vec<_>>());
+    for (item, (_, tags)) in items.iter_mut().zip(uris_and_tags) {
+        item.tags = tags.iter().map(|t| t.to_string()).collect();
+    }
+    items
+}
+
+fn tags(tags: &[&str]) -> Vec<String> {
+    tags.iter().map(|t| t.to_string()).collect()
+}
+
+#[test]
+fn test_next_index_with_tags() {
+    let playlist = tagged(&[("A", &["music"]), ("B", &["ident"]), ("C", &["music", "news"]), ("D", &[])]);
+    let music = tags(&["music"]);
+    assert_eq!(compute_next_index_with_tags(&playlist, None, &music), Some(0));
+    assert_eq!(compute_next_index_with_tags(&playlist, Some(1), &music), Some(2));
+    assert_eq!(compute_next_index_with_tags(&playlist, Some(3), &music), Some(0));
+    assert_eq!(compute_next_index_with_tags(&playlist, Some(1), &tags(&["ident", "news"])), Some(1));
+}
+
+#[test]
+fn test_next_index_with_tags_no_match() {
+    let mut playlist = tagged(&[("A", &["music"]), ("B", &["ident"])]);
+    assert_eq!(compute_next_index_with_tags(&playlist, None, &tags(&["ads"])), None);
+    assert_eq!(compute_next_index_with_tags(&playlist, Some(1), &tags(&[])), None);
+
+    playlist[1].enabled = false;
+    assert_eq!(compute_next_index_with_tags(&playlist, None, &tags(&["ident"])), None, "Disabled items never match");
+}
+
+#[test]
+fn test_sequencer_tag_filter() {
+    let playlist = tagged(&[("A", &["music"]), ("B", &["ident"]), ("C", &["music"])]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_tag_filter(Some(tags(&["music"])));
+    assert_eq!(run(&mut sequencer, &playlist, 4), ["A", "C", "A", "C"]);
+
+    sequencer.set_tag_filter(Some(tags(&["ads"])));
+    assert_eq!(sequencer.next(&playlist, None), None);
+
+    sequencer.set_tag_filter(Some(Vec::new()));
+    assert!(sequencer.tag_filter().is_none(), "An empty filter plays everything");
+    assert_eq!(run(&mut sequencer, &playlist, 3), ["A", "B", "C"]);
+}
+
+#[test]
+fn test_stop_after_current_ends_instead_of_advancing() {
+    let playlist = playlist(&["A", "B", "C"]);
+    let mut sequencer = Sequencer::new();
+    assert_eq!(sequencer.next(&playlist, None), Some(NextEntry::Item(0)));
+
+    assert!(sequencer.toggle_stop_after_current());
+    assert_eq!(sequencer.next(&playlist, Some(1)), Some(NextEntry::Stop));
+    assert!(!sequencer.stop_after_current(), "The flag is used up by the stop");
+    assert_eq!(sequencer.next(&playlist, None), Some(NextEntry::Item(0)));
+}
+
+#[test]
+fn test_stop_after_current_toggles_off() {
+    let playlist = playlist(&["A", "B"]);
+    let mut sequencer = Sequencer::new();
+    assert!(sequencer.toggle_stop_after_current());
+    assert!(!sequencer.toggle_stop_after_current());
+    assert_eq!(sequencer.next(&playlist, Some(1)), Some(NextEntry::Item(1)));
+}
+
+#[test]
+fn test_stop_after_current_during_interstitial() {
+    let playlist = playlist(&["A", "B"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 1 }));
+    assert_eq!(run(&mut sequencer, &playlist, 2), ["A", "bumper"]);
+    sequencer.toggle_stop_after_current();
+    assert_eq!(sequencer.next(&playlist, Some(1)), Some(NextEntry::Stop));
+
+    sequencer.toggle_stop_after_current();
+    sequencer.reset();
+    assert!(!sequencer.stop_after_current(), "Stopping the stream clears the request");
+}
+
+#[test]
+fn test_hold_replays_current_item() {
+    let playlist = playlist(&["A", "B", "C"]);
+    let ids: Vec<u64> = playlist.iter().map(|item| item.id).collect();
+    let mut sequencer = Sequencer::new();
+
+    sequencer.set_held(true);
+    assert_eq!(sequencer.next(&playlist, Some(ids[1])), Some(NextEntry::Item(1)));
+    assert_eq!(sequencer.next(&playlist, Some(ids[1])), Some(NextEntry::Item(1)), "Still held");
+    assert_eq!(sequencer.next(&playlist, None), Some(NextEntry::Item(0)), "Nothing on air to hold");
+
+    sequencer.set_held(false);
+    assert_eq!(sequencer.next(&playlist, Some(ids[1])), Some(NextEntry::Item(2)));
+}
+
+#[test]
+fn test_hold_does_not_count_towards_interstitials() {
+    let playlist = playlist(&["A", "B"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 2 }));
+    assert_eq!(run(&mut sequencer, &playlist, 1), ["A"]);
+
+    sequencer.set_held(true);
+    assert_eq!(sequencer.next(&playlist, Some(playlist[0].id)), Some(NextEntry::Item(0)));
+    assert_eq!(sequencer.next(&playlist, Some(playlist[0].id)), Some(NextEntry::Item(0)));
+    sequencer.set_held(false);
+    assert_eq!(sequencer.next(&playlist, Some(playlist[0].id)), Some(NextEntry::Item(1)));
+    assert!(matches!(sequencer.next(&playlist, Some(playlist[1].id)), Some(NextEntry::Interstitial(_))));
+
+    sequencer.set_held(true);
+    assert_eq!(sequencer.next(&playlist, Some(playlist[1].id)), Some(NextEntry::Interstitial("bumper".to_string())),
+               "A held interstitial loops too");
+    sequencer.reset();
+    assert!(!sequencer.is_held(), "Stopping the stream releases the hold");
+}
+
+#[test]
+fn test_repeat_all_wraps_by_default() {
+    let playlist = playlist(&["A", "B"]);
+    let mut sequencer = Sequencer::new();
+    assert_eq!(sequencer.repeat_mode(), RepeatMode::RepeatAll);
+    assert_eq!(run(&mut sequencer, &playlist, 4), ["A", "B", "A", "B"]);
+}
+
+#[test]
+fn test_repeat_none_stops_after_last_item() {
+    let playlist = playlist(&["A", "B", "C"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_repeat_mode(RepeatMode::None);
+    assert_eq!(run(&mut sequencer, &playlist, 4), ["A", "B", "C", "STOP"]);
+
+    let mut sequencer = Sequencer::new();
+    sequencer.set_repeat_mode(RepeatMode::None);
+    let single = self::playlist(&["A"]);
+    assert_eq!(run(&mut sequencer, &single, 2), ["A", "STOP"]);
+}
+
+#[test]
+fn test_repeat_none_skips_disabled_tail_and_interstitial() {
+    let mut playlist = playlist(&["A", "B", "C"]);
+    playlist[2].enabled = false;
+    let mut sequencer = Sequencer::new();
+    sequencer.set_repeat_mode(RepeatMode::None);
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 2 }));
+    assert_eq!(run(&mut sequencer, &playlist, 3), ["A", "B", "STOP"], "No bumper once the playlist is done");
+}
+
+#[test]
+fn test_repeat_one_replays_current_item() {
+    let playlist = playlist(&["A", "B"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_repeat_mode(RepeatMode::RepeatOne);
+    assert_eq!(run(&mut sequencer, &playlist, 3), ["A", "A", "A"]);
+
+    sequencer.set_repeat_mode(RepeatMode::RepeatAll);
+    assert_eq!(sequencer.next(&playlist, Some(playlist[0].id)), Some(NextEntry::Item(1)), "Switching mid-stream");
+}
+
+#[test]
+fn test_shuffle_plays_every_item_once_per_round() {
+    let playlist = playlist(&["A", "B", "C", "D", "E"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_shuffle(true);
+    let aired = run(&mut sequencer, &playlist, 15);
+    for round in aired.chunks(5) {
+        let mut round = round.to_vec();
+        round.sort();
+        assert_eq!(round, ["A", "B", "C", "D", "E"], "Aired: {:?}", aired);
+    }
+    for pair in aired.windows(2) {
+        assert_ne!(pair[0], pair[1], "No item plays twice in a row: {:?}", aired);
+    }
+}
+
+#[test]
+fn test_shuffle_skips_disabled_items() {
+    let mut playlist = playlist(&["A", "B", "C", "D"]);
+    playlist[1].enabled = false;
+    playlist[3].enabled = false;
+    let mut sequencer = Sequencer::new();
+    sequencer.set_shuffle(true);
+    let aired = run(&mut sequencer, &playlist, 10);
+    assert!(aired.iter().all(|uri| uri == "A" || uri == "C"), "Aired: {:?}", aired);
+}
+
+#[test]
+fn test_shuffle_with_repeat_none_stops_after_one_round() {
+    let playlist = playlist(&["A", "B", "C"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_shuffle(true);
+    sequencer.set_repeat_mode(RepeatMode::None);
+    let aired = run(&mut sequencer, &playlist, 4);
+    assert_eq!(aired[3], "STOP");
+    let mut round = aired[..3].to_vec();
+    round.sort();
+    assert_eq!(round, ["A", "B", "C"]);
+}
+
+#[test]
+fn test_peek_leaves_interstitials_alone() {
+    let playlist = playlist(&["A", "B"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_interstitial(Some(InterstitialConfig { uri: "bumper".to_string(), every: 1 }));
+    assert_eq!(sequencer.next(&playlist, None), Some(NextEntry::Item(0)));
+
+    for _ in 0..3 {
+        assert_eq!(sequencer.peek(&playlist, Some(1)), Some(NextEntry::Interstitial("bumper".to_string())));
+    }
+    assert!(!sequencer.in_interstitial(), "A peek does not put the interstitial on air");
+    assert_eq!(sequencer.next(&playlist, Some(1)), Some(NextEntry::Interstitial("bumper".to_string())));
+    assert!(sequencer.in_interstitial());
+}
+
+#[test]
+fn test_peek_matches_next_when_shuffled() {
+    let playlist = playlist(&["A", "B", "C", "D", "E"]);
+    let mut sequencer = Sequencer::new();
+    sequencer.set_shuffle(true);
+    let mut current_id = None;
+    let mut aired = Vec::new();
+    for _ in 0..5 {
+        let peeked = sequencer.peek(&playlist, current_id);
+        assert_eq!(sequencer.peek(&playlist, current_id), peeked, "Peeking draws nothing");
+        let next = sequencer.next(&playlist, current_id);
+        assert_eq!(next, peeked);
+        if let Some(NextEntry::Item(idx)) = next {
+            current_id = Some(playlist[idx].id);
+            aired.push(playlist[idx].uri.clone());
+        }
+    }
+    aired.sort();
+    assert_eq!(aired, ["A", "B", "C", "D", "E"], "No entry was skipped");
+}
@@ -0,0 +1,221 @@
+use hayai_playout_core::{EncodingSettings, StreamerError};
+
+fn validation_error(settings: &EncodingSettings) -> StreamerError {
+    let err = settings.validate().expect_err("Settings should be rejected");
+    err.downcast_ref::<StreamerError>().cloned().expect("A StreamerError")
+}
+
+#[test]
+fn test_validate_rejects_zero_bitrate() {
+    let settings = EncodingSettings { bitrate_kbps: 0, ..EncodingSettings::default() };
+    assert_eq!(validation_error(&settings), StreamerError::InvalidBitrate { kbps: 0 });
+}
+
+#[test]
+fn test_validate_rejects_excessive_bitrate() {
+    let kbps = EncodingSettings::MAX_BITRATE_KBPS + 1;
+    let settings = EncodingSettings { bitrate_kbps: kbps, ..EncodingSettings::default() };
+    assert_eq!(validation_error(&settings), StreamerError::InvalidBitrate { kbps });
+}
+
+#[test]
+fn test_validate_rejects_odd_scale() {
+    let settings = EncodingSettings { scale_enabled: true, scale_width: 1279, scale_height: 720, ..EncodingSettings::default() };
+    assert_eq!(validation_error(&settings), StreamerError::InvalidScale { width: 1279, height: 720 });
+}
+
+#[test]
+fn test_validate_rejects_zero_scale() {
+    let settings = EncodingSettings { scale_enabled: true, scale_width: 1280, scale_height: 0, ..EncodingSettings::default() };
+    assert_eq!(validation_error(&settings), StreamerError::InvalidScale { width: 1280, height: 0 });
+}
+
+#[test]
+fn test_validate_ignores_scale_when_disabled() {
+    let settings = EncodingSettings {
+        video_encoder: "hayai-no-such-encoder".to_string(),
+        scale_width: 0,
+        ..EncodingSettings::default()
+    };
+    // Gets past the scale check to the encoder check.
+    assert!(matches!(validation_error(&settings), StreamerError::MissingPlugin { .. }));
+}
+
+#[test]
+fn test_validate_rejects_unknown_video_encoder() {
+    let settings = EncodingSettings { video_encoder: "hayai-no-such-encoder".to_string(), ..EncodingSettings::default() };
+    assert_eq!(validation_error(&settings), StreamerError::MissingPlugin { element: "hayai-no-such-encoder".to_string() });
+}
+
+#[test]
+#[ignore]
+fn test_validate_rejects_unknown_audio_encoder() {
+    let settings = EncodingSettings { audio_encoder: "hayai-no-such-encoder".to_string(), ..EncodingSettings::default() };
+    assert_eq!(validation_error(&settings), StreamerError::MissingPlugin { element: "hayai-no-such-encoder".to_string() });
+}
+
+#[test]
+#[ignore]
+fn test_validate_accepts_defaults() {
+    assert!(EncodingSettings::default().validate().is_ok());
+}
+
+#[test]
+fn test_audio_bitrate_defaults_for_old_settings() {
+    let mut json = serde_json::to_value(EncodingSettings { audio_bitrate_kbps: 96, ..EncodingSettings::default() }).unwrap();
+    assert_eq!(json["audio_bitrate_kbps"], 96);
+    json.as_object_mut().unwrap().remove("audio_bitrate_kbps");
+    let settings: EncodingSettings = serde_json::from_value(json).unwrap();
+    assert_eq!(settings.audio_bitrate_kbps, 128);
+}
+
+#[test]
+fn test_audio_fade_defaults_to_a_cut() {
+    assert_eq!(EncodingSettings::default().audio_fade_ms, 0);
+    let mut json = serde_json::to_value(EncodingSettings::default()).unwrap();
+    json.as_object_mut().unwrap().remove("audio_fade_ms");
+    let settings: EncodingSettings = serde_json::from_value(json).unwrap();
+    assert_eq!(settings.audio_fade_ms, 0);
+}
+
+#[test]
+fn test_loudness_normalization_defaults_off() {
+    let mut json = serde_json::to_value(EncodingSettings::default()).unwrap();
+    json.as_object_mut().unwrap().remove("normalize_loudness");
+    json.as_object_mut().unwrap().remove("loudness_target_lufs");
+    let settings: EncodingSettings = serde_json::from_value(json).unwrap();
+    assert!(!settings.normalize_loudness);
+    assert_eq!(settings.loudness_target_lufs, -23.0);
+}
+
+#[test]
+fn test_live_sync_defaults_on() {
+    let settings = EncodingSettings::default();
+    assert!(settings.live_sync, "Files must be paced for a live output");
+    assert_eq!(settings.max_lateness_ms, None);
+    let mut json = serde_json::to_value(settings).unwrap();
+    json.as_object_mut().unwrap().remove("live_sync");
+    json.as_object_mut().unwrap().remove("max_lateness_ms");
+    let settings: EncodingSettings = serde_json::from_value(json).unwrap();
+    assert!(settings.live_sync);
+    assert_eq!(settings.max_lateness_ms, None);
+}
+
+#[test]
+#[ignore]
+fn test_live_sync_reaches_output_sink() -> anyhow::Result<()> {
+    use gstreamer::prelude::*;
+    use hayai_playout_core::{create_processing_bin, LatencyProfile, OUTPUT_SINK_NAME};
+
+    gstreamer::init()?;
+    // The latency profile no longer decides the pacing.
+    let settings = EncodingSettings { latency_profile: LatencyProfile::UltraLowLatency, ..EncodingSettings::default() };
+    let sink = create_processing_bin("rtmp://localhost/live/test", &settings)?.by_name(OUTPUT_SINK_NAME).unwrap();
+    assert!(sink.property::<bool>("sync"));
+
+    let settings = EncodingSettings { live_sync: false, max_lateness_ms: Some(40), ..EncodingSettings::default() };
+    let sink = create_processing_bin("rtmp://localhost/live/test", &settings)?.by_name(OUTPUT_SINK_NAME).unwrap();
+    assert!(!sink.property::<bool>("sync"));
+    assert_eq!(sink.property::<i64>("max-lateness"), 40_000_000);
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_loudness_normalization_adds_normalizer() -> anyhow::Result<()> {
+    use gstreamer as gst;
+    use gst::prelude::*;
+    use hayai_playout_core::create_processing_bin;
+
+    gst::init()?;
+    let has = |bin: &gst::Bin, factory: &str| {
+        bin.iterate_recurse().into_iter().flatten()
+            .any(|e| e.factory().map_or(false, |f| f.name() == factory))
+    };
+    let plain = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    assert!(!has(&plain, "audioloudnorm") && !has(&plain, "audiodynamic"));
+
+    let settings = EncodingSettings { normalize_loudness: true, ..EncodingSettings::default() };
+    let bin = create_processing_bin("rtmp://localhost/live/test", &settings)?;
+    assert!(has(&bin, "audioloudnorm") || has(&bin, "audiodynamic"));
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_audio_bitrate_reaches_encoder() -> anyhow::Result<()> {
+    use gstreamer::prelude::*;
+    use hayai_playout_core::{create_processing_bin, AUDIO_ENCODER_NAME};
+
+    gstreamer::init()?;
+    let settings = EncodingSettings { audio_encoder: "voaacenc".to_string(), audio_bitrate_kbps: 96, ..EncodingSettings::default() };
+    let bin = create_processing_bin("rtmp://localhost/live/test", &settings)?;
+    let encoder = bin.by_name(AUDIO_ENCODER_NAME).expect("Audio encoder is named");
+    assert_eq!(encoder.property::<i32>("bitrate"), 96_000);
+    Ok(())
+}
+
+#[test]
+fn test_settings_with_old_encoder_field_load() {
+    let json = r#"{"encoder": "openh264enc", "audio_encoder": "faac", "bitrate_kbps": 3000,
+        "speed_preset": "ultrafast", "scale_enabled": false, "scale_width": 1920, "scale_height": 1080}"#;
+    let settings: EncodingSettings = serde_json::from_str(json).unwrap();
+    assert_eq!(settings.video_encoder, "openh264enc");
+    assert_eq!(settings.bitrate_kbps, 3000);
+    assert_eq!(settings.record_path, None);
+}
+
+#[test]
+fn test_keyframe_interval_seconds_overrides_frames() {
+    let settings = EncodingSettings { keyframe_interval: 90, ..EncodingSettings::default() };
+    assert_eq!(settings.keyframe_interval_frames(), 90);
+    let settings = EncodingSettings { keyframe_interval_seconds: Some(2.0), ..settings };
+    assert_eq!(settings.keyframe_interval_frames(), 60);
+}
+
+#[test]
+fn test_validate_rejects_zero_keyframe_interval() {
+    let settings = EncodingSettings { keyframe_interval: 0, ..EncodingSettings::default() };
+    assert_eq!(validation_error(&settings), StreamerError::InvalidKeyframeInterval);
+    let settings = EncodingSettings { keyframe_interval_seconds: Some(0.01), ..EncodingSettings::default() };
+    assert_eq!(validation_error(&settings), StreamerError::InvalidKeyframeInterval);
+}
+
+#[test]
+fn test_framerate_defaults_to_passthrough() {
+    let settings = EncodingSettings::default();
+    assert!(settings.framerate().is_none());
+    let settings = EncodingSettings { framerate_num: 30000, framerate_den: 1001, ..settings };
+    assert_eq!(settings.framerate(), Some(gstreamer::Fraction::new(30000, 1001)));
+}
+
+#[test]
+fn test_keyframe_interval_seconds_uses_output_framerate() {
+    let settings = EncodingSettings { framerate_num: 25, keyframe_interval_seconds: Some(2.0), ..EncodingSettings::default() };
+    assert_eq!(settings.keyframe_interval_frames(), 50);
+}
+
+#[test]
+fn test_validate_rejects_zero_framerate_denominator() {
+    let settings = EncodingSettings { framerate_num: 30, framerate_den: 0, ..EncodingSettings::default() };
+    assert!(settings.framerate().is_none());
+    assert_eq!(validation_error(&settings), StreamerError::InvalidFramerate { num: 30, den: 0 });
+}
+
+#[test]
+#[ignore]
+fn test_framerate_caps_follow_videorate() -> anyhow::Result<()> {
+    use gstreamer::prelude::*;
+    use hayai_playout_core::create_processing_bin;
+
+    gstreamer::init()?;
+    let settings = EncodingSettings { framerate_num: 25, ..EncodingSettings::default() };
+    let bin = create_processing_bin("rtmp://localhost/live/test", &settings)?;
+    let videorate = bin.children().into_iter()
+        .find(|e| e.factory().map_or(false, |f| f.name() == "videorate"))
+        .expect("videorate in the chain");
+    let next = videorate.static_pad("src").unwrap().peer().unwrap().parent_element().unwrap();
+    let caps = next.property::<gstreamer::Caps>("caps");
+    assert_eq!(caps.structure(0).unwrap().get::<gstreamer::Fraction>("framerate")?, gstreamer::Fraction::new(25, 1));
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use hayai_playout_core::{natural_cmp, SortOrder, Streamer};
+use std::cmp::Ordering;
+
+fn sorted(names: &[&str]) -> Vec<String> {
+    let mut names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+    names.sort_by(|a, b| natural_cmp(a, b));
+    names
+}
+
+#[test]
+fn test_numbers_sort_by_value() {
+    assert_eq!(sorted(&["clip2.mp4", "clip10.mp4", "clip1.mp4"]), ["clip1.mp4", "clip2.mp4", "clip10.mp4"]);
+    assert_eq!(sorted(&["s01e10", "s01e9", "s02e1"]), ["s01e9", "s01e10", "s02e1"]);
+}
+
+#[test]
+fn test_letters_ignore_case() {
+    assert_eq!(sorted(&["b.mkv", "A.mkv", "c.mkv"]), ["A.mkv", "b.mkv", "c.mkv"]);
+}
+
+#[test]
+fn test_prefix_sorts_first() {
+    assert_eq!(natural_cmp("clip", "clip1"), Ordering::Less);
+    assert_eq!(natural_cmp("clip1", "clip1.mp4"), Ordering::Less);
+}
+
+#[test]
+fn test_order_is_total() {
+    assert_eq!(natural_cmp("clip01", "clip1"), "clip01".cmp("clip1"), "Equal values fall back to a plain comparison");
+    assert_eq!(natural_cmp("A", "a"), "A".cmp("a"));
+    assert_eq!(natural_cmp("x", "x"), Ordering::Equal);
+    assert_eq!(natural_cmp("99999999999999999999999", "100000000000000000000000"), Ordering::Less);
+}
+
+fn streamer_with(uris: &[&str]) -> Streamer {
+    let streamer = Streamer::new().unwrap();
+    for uri in uris {
+        streamer.add_item(uri);
+    }
+    streamer
+}
+
+fn uris(streamer: &Streamer) -> Vec<String> {
+    streamer.get_playlist_clone().into_iter().map(|item| item.uri).collect()
+}
+
+#[test]
+fn test_sort_playlist_natural_and_alpha() {
+    let streamer = streamer_with(&["file:///clip2.mp4", "file:///clip10.mp4", "file:///clip1.mp4"]);
+
+    streamer.sort_playlist(SortOrder::ByUriNatural);
+    assert_eq!(uris(&streamer), ["file:///clip1.mp4", "file:///clip2.mp4", "file:///clip10.mp4"]);
+
+    streamer.sort_playlist(SortOrder::ByUriAlpha);
+    assert_eq!(uris(&streamer), ["file:///clip1.mp4", "file:///clip10.mp4", "file:///clip2.mp4"]);
+}
+
+#[test]
+fn test_sort_playlist_reverse() {
+    let streamer = streamer_with(&["B", "C", "A"]);
+    streamer.sort_playlist(SortOrder::Reverse);
+    assert_eq!(uris(&streamer), ["A", "C", "B"]);
+}
+
+#[test]
+fn test_sort_playlist_keeps_ids() {
+    let streamer = streamer_with(&["B", "A"]);
+    let before = streamer.get_playlist_clone();
+
+    streamer.sort_playlist(SortOrder::ByUriNatural);
+
+    let after = streamer.get_playlist_clone();
+    assert_eq!((after[0].id, after[1].id), (before[1].id, before[0].id));
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use hayai_playout_core::{bitrate_between, create_processing_bin, EncodingSettings, StreamStats, Streamer, FRAME_RATE_NAME};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_bitrate_between_readings() {
+    let start = Instant::now();
+    let later = start + Duration::from_secs(2);
+    assert_eq!(bitrate_between((start, 0), (later, 500_000)), 2000.0);
+    assert_eq!(bitrate_between((start, 1000), (start, 5000)), 0.0, "No time passed");
+    assert_eq!(bitrate_between((later, 0), (start, 5000)), 0.0, "Readings out of order");
+}
+
+#[test]
+fn test_stats_are_zero_when_stopped() {
+    let streamer = Streamer::new().unwrap();
+    assert_eq!(streamer.get_stats(), StreamStats::default());
+}
+
+#[test]
+#[ignore]
+fn test_processing_bin_names_frame_rate() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    let rate = bin.by_name(FRAME_RATE_NAME).expect("videorate in the video chain");
+    assert_eq!(rate.factory().unwrap().name(), "videorate");
+    Ok(())
+}
+
+// Ten seconds, long enough not to end during the test.
+fn write_long_clip(path: &std::path::Path) -> Result<()> {
+    let writer = gst::parse::launch(&format!(
+        "videotestsrc num-buffers=300 ! video/x-raw,framerate=30/1 ! matroskamux name=mux ! filesink location={} \
+         audiotestsrc num-buffers=300 ! mux.",
+        path.display()
+    ))?;
+    writer.set_state(gst::State::Playing)?;
+    writer.bus().unwrap().timed_pop_filtered(gst::ClockTime::from_seconds(20), &[gst::MessageType::Eos, gst::MessageType::Error]);
+    writer.set_state(gst::State::Null)?;
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_stats_count_while_streaming() -> Result<()> {
+    gst::init()?;
+    let temp_dir = tempfile::tempdir()?;
+    let clip_path = temp_dir.path().join("long.mkv");
+    write_long_clip(&clip_path)?;
+
+    let mut streamer = Streamer::new()?;
+    streamer.add_item(&format!("file://{}", clip_path.to_str().unwrap()));
+    streamer.start("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    thread::sleep(Duration::from_secs(3));
+    let stats = streamer.get_stats();
+    streamer.stop()?;
+
+    assert!(stats.uptime >= Duration::from_secs(3));
+    assert!(stats.bytes_sent > 0, "Nothing reached the output: {:?}", stats);
+    assert!(stats.bitrate_kbps > 0.0);
+    assert_eq!(streamer.get_stats(), StreamStats::default(), "Stats are reset on stop");
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use hayai_playout_core::{
+    create_processing_bin, Corner, EncodingSettings, Streamer, TextOverlayConfig, TextPosition, CLOCK_OVERLAY_NAME,
+    TEXT_OVERLAY_NAME,
+};
+
+#[test]
+fn test_text_overlay_defaults_show_nothing() {
+    let config = TextOverlayConfig::default();
+    assert_eq!(config.text, None);
+    assert_eq!(config.clock, None);
+    assert_eq!(config.text_position, TextPosition::Bottom);
+    assert_eq!(config.scroll_speed, 0);
+}
+
+#[test]
+fn test_text_overlay_config_fills_in_missing_fields() {
+    let config: TextOverlayConfig = serde_json::from_str(r#"{"text": "NOW PLAYING", "clock": "TopLeft"}"#).unwrap();
+    assert_eq!(config.text.as_deref(), Some("NOW PLAYING"));
+    assert_eq!(config.clock, Some(Corner::TopLeft));
+    assert_eq!(config.font, TextOverlayConfig::default().font);
+}
+
+#[test]
+fn test_set_overlay_text_before_start() {
+    let streamer = Streamer::new().unwrap();
+    streamer.set_text_overlay(TextOverlayConfig { clock: Some(Corner::TopRight), ..TextOverlayConfig::default() }).unwrap();
+    streamer.set_overlay_text(Some("NOW PLAYING: Intro")).unwrap();
+    streamer.set_overlay_text(None).unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_text_overlays_start_silent() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    for name in [TEXT_OVERLAY_NAME, CLOCK_OVERLAY_NAME] {
+        let overlay = bin.by_name(name).expect("the pango plugin is installed");
+        assert!(overlay.property::<bool>("silent"));
+    }
+    Ok(())
+}
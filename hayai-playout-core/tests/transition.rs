@@ -0,0 +1,56 @@
+use hayai_playout_core::TransitionGuard;
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+
+#[test]
+fn test_only_first_trigger_advances() {
+    let mut guard = TransitionGuard::new();
+    let generation = guard.finish();
+
+    assert!(guard.try_begin(generation));
+    assert!(!guard.try_begin(generation), "A second trigger for the same entry must not advance");
+    assert_eq!(guard.finish(), generation + 1);
+}
+
+#[test]
+fn test_late_trigger_for_previous_entry_is_ignored() {
+    let mut guard = TransitionGuard::new();
+    let old = guard.finish();
+    assert!(guard.try_begin(old));
+    let new = guard.finish();
+
+    assert!(!guard.try_begin(old));
+    assert!(guard.try_begin(new));
+}
+
+#[test]
+fn test_cancelled_transition_can_be_retried() {
+    let mut guard = TransitionGuard::new();
+    let generation = guard.finish();
+    assert!(guard.try_begin(generation));
+
+    guard.cancel();
+    assert_eq!(guard.generation(), generation);
+    assert!(guard.try_begin(generation));
+}
+
+#[test]
+fn test_racing_triggers_advance_once() {
+    let guard = Arc::new(Mutex::new(TransitionGuard::new()));
+    let generation = guard.lock().unwrap().finish();
+    let barrier = Arc::new(Barrier::new(2));
+
+    let triggers: Vec<_> = (0..2)
+        .map(|_| {
+            let guard = guard.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                guard.lock().unwrap().try_begin(generation)
+            })
+        })
+        .collect();
+    let advanced = triggers.into_iter().map(|t| t.join().unwrap()).filter(|&won| won).count();
+
+    assert_eq!(advanced, 1);
+}
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use hayai_playout_core::{create_processing_bin, EncodingSettings, Streamer, VOLUME_NAME};
+
+#[test]
+fn test_volume_defaults_to_unity() {
+    let streamer = Streamer::new().unwrap();
+    assert_eq!(streamer.get_volume(), 1.0);
+}
+
+#[test]
+fn test_volume_is_clamped() {
+    let streamer = Streamer::new().unwrap();
+    streamer.set_volume(0.5);
+    assert_eq!(streamer.get_volume(), 0.5);
+    streamer.set_volume(-1.0);
+    assert_eq!(streamer.get_volume(), 0.0);
+    streamer.set_volume(100.0);
+    assert_eq!(streamer.get_volume(), Streamer::MAX_VOLUME);
+    streamer.set_volume(f64::NAN);
+    assert_eq!(streamer.get_volume(), Streamer::MAX_VOLUME);
+}
+
+#[test]
+#[ignore]
+fn test_processing_bin_has_master_volume() -> Result<()> {
+    gst::init()?;
+    let bin = create_processing_bin("rtmp://localhost/live/test", &EncodingSettings::default())?;
+    let volume = bin.by_name(VOLUME_NAME).expect("volume element in the audio chain");
+    assert_eq!(volume.property::<f64>("volume"), 1.0);
+    Ok(())
+}
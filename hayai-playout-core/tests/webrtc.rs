@@ -0,0 +1,47 @@
+#![cfg(feature = "webrtc")]
+
+use anyhow::Result;
+use gstreamer as gst;
+use gst::prelude::*;
+use hayai_playout_core::{build_webrtc_bin, payloaders_for, EncodingSettings, WebRtcSignaling};
+use std::sync::Arc;
+
+#[test]
+fn test_payloaders_follow_codecs() {
+    assert_eq!(payloaders_for("x264enc", "opusenc").unwrap(), ("rtph264pay", "rtpopuspay"));
+    assert_eq!(payloaders_for("nvh264enc", "opusenc").unwrap(), ("rtph264pay", "rtpopuspay"));
+    assert_eq!(payloaders_for("vp8enc", "opusenc").unwrap(), ("rtpvp8pay", "rtpopuspay"));
+    assert_eq!(payloaders_for("vp9enc", "opusenc").unwrap(), ("rtpvp9pay", "rtpopuspay"));
+}
+
+#[test]
+fn test_payloaders_reject_unsupported_codecs() {
+    assert!(payloaders_for("x265enc", "opusenc").is_err());
+    assert!(payloaders_for("x264enc", "faac").is_err());
+}
+
+#[test]
+#[ignore]
+fn test_webrtc_bin_uses_codec_payloaders() -> Result<()> {
+    gst::init()?;
+    let settings = EncodingSettings {
+        video_encoder: "vp8enc".to_string(),
+        audio_encoder: "opusenc".to_string(),
+        ..EncodingSettings::default()
+    };
+    let signaling = WebRtcSignaling {
+        on_offer: Arc::new(|_| ()),
+        on_ice_candidate: Arc::new(|_, _| ()),
+    };
+    let bin = build_webrtc_bin(&settings, signaling)?;
+
+    let factories: Vec<String> = bin.children().iter()
+        .filter_map(|elem| elem.factory().map(|f| f.name().to_string()))
+        .collect();
+    for expected in ["vp8enc", "rtpvp8pay", "opusenc", "rtpopuspay", "webrtcbin"] {
+        assert!(factories.iter().any(|f| f == expected), "{} missing from {:?}", expected, factories);
+    }
+    assert!(bin.static_pad("video_sink").is_some());
+    assert!(bin.static_pad("audio_sink").is_some());
+    Ok(())
+}
@@ -1,19 +1,48 @@
 use anyhow::Result;
-use hayai_playout_core::{EncodingSettings, Streamer};
+use hayai_playout_core::{
+    is_media_file, list_encoders, user_message, EncoderKind, EncodingSettings, ItemKind, LatencyProfile, MediaInfo,
+    PlayoutEvent, PlaylistItem, Streamer, MEDIA_EXTENSIONS,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
 use gstreamer as gst;
 use gstreamer::prelude::*;
 use gtk4 as gtk;
+use gtk::gdk;
+use gtk::gio;
+use gtk::glib;
 use gtk::prelude::*;
 use gtk::{
-    Align, Application, ApplicationWindow, Box, Button, CheckButton, ComboBoxText, Entry,
-    FileChooserAction, FileChooserDialog, Grid, Label, ListBox, ListBoxRow, MessageDialog, MessageType,
-    Orientation, PolicyType, ResponseType, ScrolledWindow, SpinButton,
+    Align, Application, ApplicationWindow, Box, Button, CheckButton, ComboBoxText, Dialog, Entry,
+    FileChooserAction, FileChooserDialog, FileFilter, Grid, Label, ListBox, ListBoxRow, MessageDialog, MessageType,
+    Orientation, PasswordEntry, PolicyType, ResponseType, ScrolledWindow, SpinButton, ToggleButton,
 };
 
+mod settings;
+
+use settings::UiSettings;
+
+// Style class of the playlist row on air.
+const ON_AIR_CLASS: &str = "on-air";
+// Style class of the playlist, for its text size.
+const PLAYLIST_CLASS: &str = "playlist";
+// Playlist text sizes on offer, in points.
+const PLAYLIST_FONT_RANGE: (f64, f64) = (8.0, 32.0);
+// Audio quieter than this shows as an empty meter.
+const METER_FLOOR_DB: f64 = -60.0;
+// How long the meters hold the last reading before dropping, once no more come.
+const METER_HOLD: std::time::Duration = std::time::Duration::from_millis(500);
+
 fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     gst::init()?;
+    // Registered from this build rather than loaded from the system, whose GTK may differ.
+    if let Err(e) = gstgtk4::plugin_register_static() {
+        eprintln!("Failed to register the preview sink: {}", e);
+    }
     lower_nvdec_rank();
     let streamer = Arc::new(Mutex::new(Streamer::new()?));
     let app = Application::new(Some("com.example.hayaipLayout"), Default::default());
@@ -24,45 +53,258 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Keeps decodebin away from the NVIDIA decoders. NVENC encoders are left alone; they are
+/// only used when picked explicitly.
 fn lower_nvdec_rank() {
     let registry = gst::Registry::get();
     for factory in registry.features(gst::ElementFactory::static_type()) {
         if let Some(factory) = factory.downcast_ref::<gst::ElementFactory>() {
-            if factory.name().starts_with("nv") {
+            if factory.name().starts_with("nv") && factory.klass().contains("Decoder") {
                 factory.set_rank(gst::Rank::NONE);
             }
         }
     }
 }
 
-fn show_error_dialog(parent: &ApplicationWindow, text: &str) {
+fn show_error_dialog(parent: &ApplicationWindow, title: &str, text: &str) {
     let dialog = MessageDialog::new(
         Some(parent),
         gtk::DialogFlags::MODAL,
         MessageType::Error,
         gtk::ButtonsType::Ok,
-        "Failed to Start Stream",
+        title,
     );
     dialog.set_secondary_text(Some(text));
     dialog.connect_response(|d, _| d.close());
     dialog.show();
 }
 
-fn get_available_encoders(klass: &str) -> Vec<String> {
-    let mut encoders = Vec::new();
-    let registry = gst::Registry::get();
-    for factory in registry.features(gst::ElementFactory::static_type()) {
-        if let Some(factory) = factory.downcast_ref::<gst::ElementFactory>() {
-            if factory.klass().contains(klass) {
-                encoders.push(factory.name().to_string());
+// Enough of the playlist to tell whether it changed since it was last saved or opened.
+fn playlist_fingerprint(playlist: &[PlaylistItem]) -> Vec<(u64, bool)> {
+    playlist.iter().map(|item| (item.id, item.enabled)).collect()
+}
+
+/// Asks before a playlist with unsaved changes is thrown away, then runs `discard` if the user
+/// agrees; straight away if there is nothing to lose.
+fn confirm_discard(parent: &ApplicationWindow, unsaved: bool, discard: impl Fn() + 'static) {
+    if !unsaved {
+        discard();
+        return;
+    }
+    let dialog = MessageDialog::new(
+        Some(parent),
+        gtk::DialogFlags::MODAL,
+        MessageType::Warning,
+        gtk::ButtonsType::OkCancel,
+        "Discard the current playlist?",
+    );
+    dialog.set_secondary_text(Some("It has changes that have not been saved."));
+    dialog.connect_response(move |d, response| {
+        d.close();
+        if response == ResponseType::Ok {
+            discard();
+        }
+    });
+    dialog.show();
+}
+
+/// Runs `add` straight away when none of the `total` files being added is in the playlist
+/// already, otherwise once the user has picked; `add` is told whether to add the
+/// `duplicates` as well.
+fn confirm_duplicates(parent: &ApplicationWindow, duplicates: usize, total: usize, add: impl Fn(bool) + 'static) {
+    if duplicates == 0 {
+        add(true);
+        return;
+    }
+    let (question, detail) = if total == 1 {
+        ("Add it again?", "This file is already in the playlist.".to_string())
+    } else {
+        ("Add them again?", format!("{} of these {} files are already in the playlist.", duplicates, total))
+    };
+    let dialog = MessageDialog::new(
+        Some(parent),
+        gtk::DialogFlags::MODAL,
+        MessageType::Question,
+        gtk::ButtonsType::None,
+        question,
+    );
+    dialog.add_buttons(&[("Skip", ResponseType::Reject), ("Add Again", ResponseType::Accept)]);
+    dialog.set_secondary_text(Some(&detail));
+    dialog.connect_response(move |d, response| {
+        d.close();
+        match response {
+            ResponseType::Accept => add(true),
+            ResponseType::Reject => add(false),
+            _ => (),
+        }
+    });
+    dialog.show();
+}
+
+/// Appends `uris`, asking first about those already in the playlist, then calls `done` with
+/// how many were added. Not to be called with the streamer locked.
+fn add_uris(parent: &ApplicationWindow, streamer: &Arc<Mutex<Streamer>>, uris: Vec<String>, done: impl Fn(usize) + 'static) {
+    let duplicates = {
+        let streamer = streamer.lock().unwrap();
+        uris.iter().filter(|uri| streamer.find_by_uri(uri).is_some()).count()
+    };
+    let streamer = streamer.clone();
+    confirm_duplicates(parent, duplicates, uris.len(), move |include_duplicates| {
+        let streamer = streamer.lock().unwrap();
+        let to_add: Vec<&String> = uris.iter()
+            .filter(|uri| include_duplicates || streamer.find_by_uri(uri).is_none())
+            .collect();
+        for uri in &to_add {
+            streamer.add_item(uri);
+        }
+        drop(streamer);
+        done(to_add.len());
+    });
+}
+
+// Playlist files, and anything for those saved under another name.
+fn add_playlist_filters(chooser: &FileChooserDialog) {
+    let playlist_filter = FileFilter::new();
+    playlist_filter.set_name(Some("Playlists"));
+    playlist_filter.add_pattern("*.json");
+    let all_filter = FileFilter::new();
+    all_filter.set_name(Some("All Files"));
+    all_filter.add_pattern("*");
+    chooser.add_filter(&playlist_filter);
+    chooser.add_filter(&all_filter);
+    chooser.set_filter(&playlist_filter);
+}
+
+/// The media files among `files`, and those in dropped folders; anything else is skipped.
+fn dropped_uris(files: &gdk::FileList) -> Vec<String> {
+    let mut uris = Vec::new();
+    for file in files.files() {
+        let path = match file.path() {
+            Some(path) => path,
+            None => continue,
+        };
+        if path.is_dir() {
+            match Streamer::directory_uris(&path, MEDIA_EXTENSIONS, true) {
+                Ok(found) => uris.extend(found),
+                Err(e) => eprintln!("Failed to add folder {}: {:#}", path.display(), e),
             }
+        } else if is_media_file(&path) {
+            uris.push(file.uri().to_string());
+        } else {
+            println!("Skipping {}, not a media file.", path.display());
         }
     }
-    encoders.sort();
-    encoders
+    uris
+}
+
+// The file name of a local file, or the whole URI of anything else.
+fn display_name(uri: &str) -> String {
+    glib::filename_from_uri(uri).ok()
+        .and_then(|(path, _)| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| uri.to_string())
+}
+
+fn format_mm_ss(time: gst::ClockTime) -> String {
+    let secs = time.seconds();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+// "mm:ss · 1920×1080 · 30 fps", leaving out what the file doesn't have.
+fn describe_media(info: &MediaInfo) -> String {
+    let mut parts = Vec::new();
+    if let Some(duration) = info.duration {
+        parts.push(format_mm_ss(duration));
+    }
+    match &info.video {
+        Some(video) => {
+            parts.push(format!("{}×{}", video.width, video.height));
+            let fps = video.framerate.numer() as f64 / video.framerate.denom().max(1) as f64;
+            if fps > 0.0 {
+                let fps = format!("{:.2}", fps);
+                parts.push(format!("{} fps", fps.trim_end_matches('0').trim_end_matches('.')));
+            }
+        }
+        None if info.has_audio() => parts.push("audio only".to_string()),
+        None => {}
+    }
+    parts.join(" · ")
+}
+
+/// Fills in a playlist row's details from its probe, or flags the file as unreadable.
+fn show_probe_result(details: &Label, warning: &gtk::Image, result: &Result<MediaInfo, String>) {
+    match result {
+        Ok(info) => {
+            details.set_text(&describe_media(info));
+            warning.set_visible(false);
+        }
+        Err(e) => {
+            details.set_text("Cannot be read");
+            warning.set_tooltip_text(Some(e));
+            warning.set_visible(true);
+        }
+    }
+}
+
+// Marks the row of the item on air, by the ids of the rows in order, and unmarks the others.
+fn highlight_on_air(playlist_box: &ListBox, row_ids: &[u64], on_air: Option<u64>) {
+    for (idx, &id) in row_ids.iter().enumerate() {
+        if let Some(row) = playlist_box.row_at_index(idx as i32) {
+            if on_air == Some(id) {
+                row.add_css_class(ON_AIR_CLASS);
+            } else {
+                row.remove_css_class(ON_AIR_CLASS);
+            }
+        }
+    }
+}
+
+// The text size of the playlist rows, or the theme's with `None`.
+fn playlist_font_css(font_pt: Option<u32>) -> String {
+    match font_pt {
+        Some(pt) => format!(".{} row label {{ font-size: {}pt; }}", PLAYLIST_CLASS, pt),
+        None => String::new(),
+    }
+}
+
+// A meter bar in one colour; the theme's default offsets would mark quiet audio as a warning.
+fn meter_bar() -> gtk::LevelBar {
+    let bar = gtk::LevelBar::builder().hexpand(true).height_request(6).build();
+    for name in ["low", "high", "full"] {
+        bar.remove_offset_value(Some(name));
+    }
+    bar
+}
+
+// Sets the `(peak, rms)` bars of the L and R meters from per-channel readings in dBFS; mono
+// shows on both, and no readings empty them.
+fn show_levels(meters: &[(gtk::LevelBar, gtk::LevelBar)], peak_db: &[f64], rms_db: &[f64]) {
+    let fraction = |db: f64| ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0);
+    for (channel, (peak_bar, rms_bar)) in meters.iter().enumerate() {
+        let reading = |dbs: &[f64]| dbs.get(channel).or(dbs.last()).map_or(0.0, |&db| fraction(db));
+        peak_bar.set_value(reading(peak_db));
+        rms_bar.set_value(reading(rms_db));
+    }
+}
+
+// Ids of the items at the `selected` positions, in playlist order.
+fn selected_ids(playlist: &[PlaylistItem], selected: &BTreeSet<u32>) -> Vec<u64> {
+    selected.iter().filter_map(|&idx| playlist.get(idx as usize)).map(|item| item.id).collect()
+}
+
+/// Encoders that can actually be opened here; registered but broken hardware encoders are left out.
+fn get_available_encoders(kind: EncoderKind) -> Vec<String> {
+    list_encoders(kind).into_iter()
+        .filter(|encoder| encoder.usable)
+        .map(|encoder| encoder.name)
+        .collect()
 }
 
 fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
+    let ui_settings = Arc::new(Mutex::new(UiSettings::load()));
+    // What was used last time, or the defaults below.
+    let saved = ui_settings.lock().unwrap().clone();
+    let saved_encoding = saved.encoding.clone();
+
     let window = ApplicationWindow::builder()
         .application(app)
         .title("Hayai Playout")
@@ -70,6 +312,17 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
         .default_height(600)
         .build();
 
+    let css = gtk::CssProvider::new();
+    css.load_from_data(&format!(".{} {{ font-weight: bold; }}", ON_AIR_CLASS));
+    gtk::style_context_add_provider_for_display(&window.display(), &css, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+    // Reloaded whenever the playlist text size changes.
+    let playlist_css = gtk::CssProvider::new();
+    playlist_css.load_from_data(&playlist_font_css(saved.playlist_font_pt));
+    gtk::style_context_add_provider_for_display(&window.display(), &playlist_css, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+    if let Some(gtk_settings) = gtk::Settings::default() {
+        gtk_settings.set_gtk_application_prefer_dark_theme(saved.dark_theme);
+    }
+
     let settings_grid = Grid::builder()
         .margin_top(10).margin_bottom(10).margin_start(10).margin_end(10)
         .column_spacing(10).row_spacing(10)
@@ -81,11 +334,14 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
 
     settings_grid.attach(&Label::new(Some("Video Encoder:")), 0, 1, 1, 1);
     let video_encoder_combo = ComboBoxText::new();
-    let available_video_encoders = get_available_encoders("Codec/Encoder/Video");
+    let available_video_encoders = get_available_encoders(EncoderKind::Video);
     for enc in &available_video_encoders {
         video_encoder_combo.append_text(enc);
     }
-    if let Some(idx) = available_video_encoders.iter().position(|r| r == "x264enc") {
+    let video_encoder = saved_encoding.as_ref().map_or("x264enc", |s| s.video_encoder.as_str());
+    if let Some(idx) = available_video_encoders.iter().position(|r| r == video_encoder) {
+        video_encoder_combo.set_active(Some(idx as u32));
+    } else if let Some(idx) = available_video_encoders.iter().position(|r| r == "x264enc") {
         video_encoder_combo.set_active(Some(idx as u32));
     }
 
@@ -93,11 +349,14 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
     
     settings_grid.attach(&Label::new(Some("Audio Encoder:")), 0, 2, 1, 1);
     let audio_encoder_combo = ComboBoxText::new();
-    let available_audio_encoders = get_available_encoders("Codec/Encoder/Audio");
+    let available_audio_encoders = get_available_encoders(EncoderKind::Audio);
     for enc in &available_audio_encoders {
         audio_encoder_combo.append_text(enc);
     }
-    if let Some(idx) = available_audio_encoders.iter().position(|r| r == "faac") {
+    let audio_encoder = saved_encoding.as_ref().map_or("faac", |s| s.audio_encoder.as_str());
+    if let Some(idx) = available_audio_encoders.iter().position(|r| r == audio_encoder) {
+        audio_encoder_combo.set_active(Some(idx as u32));
+    } else if let Some(idx) = available_audio_encoders.iter().position(|r| r == "faac") {
         audio_encoder_combo.set_active(Some(idx as u32));
     } else if !available_audio_encoders.is_empty() {
         audio_encoder_combo.set_active(Some(0));
@@ -106,7 +365,7 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
 
     settings_grid.attach(&Label::new(Some("Bitrate (kbps):")), 0, 3, 1, 1);
     let bitrate_spin = SpinButton::with_range(500.0, 20000.0, 500.0);
-    bitrate_spin.set_value(4000.0);
+    bitrate_spin.set_value(saved_encoding.as_ref().map_or(4000, |s| s.bitrate_kbps) as f64);
     settings_grid.attach(&bitrate_spin, 1, 3, 1, 1);
 
     settings_grid.attach(&Label::new(Some("Preset:")), 0, 4, 1, 1);
@@ -115,24 +374,93 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
     for p in presets {
         preset_combo.append_text(p);
     }
-    preset_combo.set_active(Some(0));
+    let preset = saved_encoding.as_ref().and_then(|s| presets.iter().position(|p| *p == s.speed_preset));
+    preset_combo.set_active(Some(preset.unwrap_or(0) as u32));
     settings_grid.attach(&preset_combo, 1, 4, 1, 1);
 
     let scale_check = CheckButton::with_label("Scale Output Resolution");
+    scale_check.set_active(saved_encoding.as_ref().map_or(false, |s| s.scale_enabled));
     settings_grid.attach(&scale_check, 0, 5, 2, 1);
 
     settings_grid.attach(&Label::new(Some("Width:")), 0, 6, 1, 1);
     let width_spin = SpinButton::with_range(1.0, 7680.0, 1.0);
-    width_spin.set_value(1920.0);
-    width_spin.set_sensitive(false);
+    width_spin.set_value(saved_encoding.as_ref().map_or(1920, |s| s.scale_width) as f64);
+    width_spin.set_sensitive(scale_check.is_active());
     settings_grid.attach(&width_spin, 1, 6, 1, 1);
     
     settings_grid.attach(&Label::new(Some("Height:")), 0, 7, 1, 1);
     let height_spin = SpinButton::with_range(1.0, 4320.0, 1.0);
-    height_spin.set_value(1080.0);
-    height_spin.set_sensitive(false);
+    height_spin.set_value(saved_encoding.as_ref().map_or(1080, |s| s.scale_height) as f64);
+    height_spin.set_sensitive(scale_check.is_active());
     settings_grid.attach(&height_spin, 1, 7, 1, 1);
 
+    settings_grid.attach(&Label::new(Some("Latency:")), 0, 8, 1, 1);
+    let latency_combo = ComboBoxText::new();
+    let latency_profiles = [
+        ("Ultra-low latency", LatencyProfile::UltraLowLatency),
+        ("Balanced", LatencyProfile::Balanced),
+        ("Stable", LatencyProfile::Stable),
+    ];
+    for (label, _) in &latency_profiles {
+        latency_combo.append_text(label);
+    }
+    let latency_profile = saved_encoding.as_ref().map_or_else(LatencyProfile::default, |s| s.latency_profile);
+    if let Some(idx) = latency_profiles.iter().position(|(_, p)| *p == latency_profile) {
+        latency_combo.set_active(Some(idx as u32));
+    }
+    settings_grid.attach(&latency_combo, 1, 8, 1, 1);
+
+    // For dim control rooms, and for reading the playlist from across the room.
+    let dark_theme_check = CheckButton::builder().label("Dark Theme").active(saved.dark_theme).build();
+    settings_grid.attach(&dark_theme_check, 0, 9, 2, 1);
+    settings_grid.attach(&Label::new(Some("Playlist Text (pt):")), 0, 10, 1, 1);
+    let playlist_font_check = CheckButton::builder()
+        .label("Custom")
+        .active(saved.playlist_font_pt.is_some())
+        .build();
+    let playlist_font_spin = SpinButton::with_range(PLAYLIST_FONT_RANGE.0, PLAYLIST_FONT_RANGE.1, 1.0);
+    playlist_font_spin.set_value(saved.playlist_font_pt.map_or(14.0, f64::from));
+    playlist_font_spin.set_sensitive(saved.playlist_font_pt.is_some());
+    let playlist_font_hbox = Box::new(Orientation::Horizontal, 5);
+    playlist_font_hbox.append(&playlist_font_check);
+    playlist_font_hbox.append(&playlist_font_spin);
+    settings_grid.attach(&playlist_font_hbox, 1, 10, 1, 1);
+
+    dark_theme_check.connect_toggled({
+        let ui_settings = ui_settings.clone();
+        move |check| {
+            if let Some(gtk_settings) = gtk::Settings::default() {
+                gtk_settings.set_gtk_application_prefer_dark_theme(check.is_active());
+            }
+            let mut ui_settings = ui_settings.lock().unwrap();
+            ui_settings.dark_theme = check.is_active();
+            if let Err(e) = ui_settings.save() {
+                eprintln!("Failed to save UI settings: {}", e);
+            }
+        }
+    });
+
+    let update_playlist_font = {
+        let ui_settings = ui_settings.clone();
+        let playlist_font_check = playlist_font_check.clone();
+        let playlist_font_spin = playlist_font_spin.clone();
+        move || {
+            let font_pt = Some(playlist_font_spin.value() as u32).filter(|_| playlist_font_check.is_active());
+            playlist_font_spin.set_sensitive(font_pt.is_some());
+            playlist_css.load_from_data(&playlist_font_css(font_pt));
+            let mut ui_settings = ui_settings.lock().unwrap();
+            ui_settings.playlist_font_pt = font_pt;
+            if let Err(e) = ui_settings.save() {
+                eprintln!("Failed to save UI settings: {}", e);
+            }
+        }
+    };
+    playlist_font_check.connect_toggled({
+        let update_playlist_font = update_playlist_font.clone();
+        move |_| update_playlist_font()
+    });
+    playlist_font_spin.connect_value_changed(move |_| update_playlist_font());
+
     scale_check.connect_toggled({
         let width_spin = width_spin.clone();
         let height_spin = height_spin.clone();
@@ -145,7 +473,17 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
 
     let main_vbox = Box::new(Orientation::Vertical, 5);
     let rtmp_entry = Entry::builder().placeholder_text("rtmp://...").margin_start(10).margin_end(10).build();
+    if let Some(url) = &saved.rtmp_url {
+        rtmp_entry.set_text(url);
+    }
+    let stream_key_entry = PasswordEntry::builder()
+        .placeholder_text("Stream Key (optional)")
+        .show_peek_icon(true)
+        .margin_start(10).margin_end(10)
+        .build();
     let playlist_box = ListBox::new();
+    playlist_box.set_selection_mode(gtk::SelectionMode::Multiple);
+    playlist_box.add_css_class(PLAYLIST_CLASS);
     let scrolled_window = ScrolledWindow::builder()
         .hscrollbar_policy(PolicyType::Never).min_content_height(300)
         .vexpand(true).child(&playlist_box).build();
@@ -154,41 +492,194 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
     button_hbox.set_margin_bottom(10);
     
     let add_button = Button::with_label("Add File");
+    let new_playlist_button = Button::with_label("New Playlist");
+    let open_playlist_button = Button::with_label("Open Playlist");
+    let save_playlist_button = Button::with_label("Save Playlist");
+    let add_folder_button = Button::with_label("Add Folder");
+    let add_url_button = Button::with_label("Add URL");
+    let add_above_button = Button::with_label("Add Above");
+    add_above_button.set_sensitive(false);
+    let remove_button = Button::with_label("Remove");
+    remove_button.set_sensitive(false);
     let move_up_button = Button::with_label("Move Up");
     let move_down_button = Button::with_label("Move Down");
     let start_button = Button::with_label("Start");
     let stop_button = Button::with_label("Stop");
     stop_button.set_sensitive(false);
+    let dry_run_button = Button::with_label("Test");
+    dry_run_button.set_tooltip_text(Some("Check the first item plays through the encoders, without going live"));
+    let stop_after_button = ToggleButton::with_label("Stop After Current");
+    stop_after_button.set_sensitive(false);
+    let pause_button = ToggleButton::with_label("Pause");
+    pause_button.set_sensitive(false);
+    let previous_button = Button::with_label("Previous");
+    let next_button = Button::with_label("Next");
+    previous_button.set_sensitive(false);
+    next_button.set_sensitive(false);
     move_up_button.set_sensitive(false);
     move_down_button.set_sensitive(false);
 
+    button_hbox.append(&new_playlist_button);
+    button_hbox.append(&open_playlist_button);
+    button_hbox.append(&save_playlist_button);
     button_hbox.append(&add_button);
+    button_hbox.append(&add_folder_button);
+    button_hbox.append(&add_url_button);
+    button_hbox.append(&add_above_button);
+    button_hbox.append(&remove_button);
     button_hbox.append(&move_up_button);
     button_hbox.append(&move_down_button);
     button_hbox.append(&start_button);
+    button_hbox.append(&dry_run_button);
     button_hbox.append(&stop_button);
+    button_hbox.append(&stop_after_button);
+    button_hbox.append(&pause_button);
+    button_hbox.append(&previous_button);
+    button_hbox.append(&next_button);
+
+    // How far into the clip on air the stream is.
+    let progress_hbox = Box::new(Orientation::Horizontal, 5);
+    progress_hbox.set_margin_start(10);
+    progress_hbox.set_margin_end(10);
+    let progress_bar = gtk::ProgressBar::builder().hexpand(true).valign(Align::Center).build();
+    let progress_label = Label::new(None);
+    progress_hbox.append(&progress_bar);
+    progress_hbox.append(&progress_label);
+
+    // What is going out, when asked for; it costs a conversion per frame.
+    let preview_button = ToggleButton::with_label("Show Preview");
+    progress_hbox.append(&preview_button);
+    let preview_picture = gtk::Picture::builder().height_request(180).visible(false).build();
+
+    // Audio meters, peak over RMS for each side.
+    let meter_grid = Grid::builder().margin_start(10).margin_end(10).column_spacing(5).row_spacing(2).build();
+    let meters: Vec<(gtk::LevelBar, gtk::LevelBar)> = ["L", "R"].iter().enumerate()
+        .map(|(row, side)| {
+            let (peak_bar, rms_bar) = (meter_bar(), meter_bar());
+            let bars = Box::new(Orientation::Vertical, 1);
+            bars.append(&peak_bar);
+            bars.append(&rms_bar);
+            meter_grid.attach(&Label::new(Some(side)), 0, row as i32, 1, 1);
+            meter_grid.attach(&bars, 1, row as i32, 1, 1);
+            (peak_bar, rms_bar)
+        })
+        .collect();
+
+    let position_hbox = Box::new(Orientation::Horizontal, 5);
+    position_hbox.set_halign(Align::Center);
+    let position_spin = SpinButton::with_range(1.0, 1.0, 1.0);
+    let move_to_button = Button::with_label("Move To");
+    position_spin.set_sensitive(false);
+    move_to_button.set_sensitive(false);
+    position_hbox.append(&Label::new(Some("Position:")));
+    position_hbox.append(&position_spin);
+    position_hbox.append(&move_to_button);
     
     main_vbox.append(&settings_grid);
     main_vbox.append(&rtmp_entry);
+    main_vbox.append(&stream_key_entry);
     main_vbox.append(&scrolled_window);
+    main_vbox.append(&progress_hbox);
+    main_vbox.append(&preview_picture);
+    main_vbox.append(&meter_grid);
+    main_vbox.append(&position_hbox);
     main_vbox.append(&button_hbox);
+    let status_label = Label::builder().halign(Align::Start).margin_start(10).margin_bottom(10).build();
+    main_vbox.append(&status_label);
     window.set_child(Some(&main_vbox));
 
-    let selected_index = Arc::new(Mutex::new(None::<u32>));
+    // Positions of the selected rows.
+    let selected_indices = Arc::new(Mutex::new(BTreeSet::<u32>::new()));
+    // Probe results by URI, `None` while the probe runs, so each file is only read once.
+    let probes = Rc::new(RefCell::new(HashMap::<String, Option<Result<MediaInfo, String>>>::new()));
+    // The details label and warning icon of each row on show, to fill in when its probe is done.
+    let row_details = Rc::new(RefCell::new(Vec::<(String, Label, gtk::Image)>::new()));
+    // Item ids of the rows, in order.
+    let row_ids = Rc::new(RefCell::new(Vec::<u64>::new()));
     let update_playlist_view = {
         let playlist_box = playlist_box.clone();
         let streamer = streamer.clone();
-        let selected_index = selected_index.clone();
+        let selected_indices = selected_indices.clone();
+        let row_ids = row_ids.clone();
         move || {
-            let mut current_sel = selected_index.lock().unwrap();
+            // Removing selected rows fires selected-rows-changed, which takes this lock itself.
+            let current_sel = selected_indices.lock().unwrap().clone();
             while let Some(child) = playlist_box.first_child() { playlist_box.remove(&child); }
+            row_details.borrow_mut().clear();
             let playlist = streamer.lock().unwrap().get_playlist_clone();
+            *row_ids.borrow_mut() = playlist.iter().map(|item| item.id).collect();
             for item in playlist {
-                let label = Label::new(Some(&item.uri));
-                let row = ListBoxRow::builder().child(&label).build();
+                let enabled_check = CheckButton::builder()
+                    .active(item.enabled)
+                    .tooltip_text("Play this item")
+                    .build();
+                enabled_check.connect_toggled({
+                    let streamer = streamer.clone();
+                    move |check| {
+                        if let Err(e) = streamer.lock().unwrap().set_item_enabled(item.id, check.is_active()) {
+                            eprintln!("Failed to update item: {}", e);
+                        }
+                    }
+                });
+                let name_label = Label::builder()
+                    .label(display_name(&item.uri))
+                    .tooltip_text(item.uri.as_str())
+                    .halign(Align::Start)
+                    .build();
+                let details_label = Label::builder().halign(Align::Start).build();
+                details_label.add_css_class("dim-label");
+                let warning_icon = gtk::Image::builder().icon_name("dialog-warning-symbolic").visible(false).build();
+                let text_box = Box::new(Orientation::Vertical, 2);
+                text_box.set_hexpand(true);
+                text_box.append(&name_label);
+                text_box.append(&details_label);
+                let row_box = Box::new(Orientation::Horizontal, 5);
+                row_box.append(&enabled_check);
+                row_box.append(&warning_icon);
+                row_box.append(&text_box);
+
+                // Live feeds never end, so there is nothing to probe.
+                if let ItemKind::Live { .. } = item.kind {
+                    details_label.set_text("Live");
+                } else {
+                    let cached = probes.borrow().get(&item.uri).cloned();
+                    match cached {
+                        Some(Some(result)) => show_probe_result(&details_label, &warning_icon, &result),
+                        Some(None) => details_label.set_text("Reading…"),
+                        None => {
+                            details_label.set_text("Reading…");
+                            probes.borrow_mut().insert(item.uri.clone(), None);
+                            // Reading a big file takes a while; the rows are filled in once it is done.
+                            let uri = item.uri.clone();
+                            let probes = probes.clone();
+                            let row_details = row_details.clone();
+                            glib::MainContext::default().spawn_local(async move {
+                                let result = gio::spawn_blocking({
+                                    let uri = uri.clone();
+                                    move || Streamer::probe_item(&uri).map_err(|e| format!("{:#}", e))
+                                })
+                                .await
+                                .unwrap_or_else(|_| Err("Probing the file failed".to_string()));
+                                for (_, details, warning) in row_details.borrow().iter().filter(|(row_uri, ..)| *row_uri == uri) {
+                                    show_probe_result(details, warning, &result);
+                                }
+                                probes.borrow_mut().insert(uri, Some(result));
+                            });
+                        }
+                    }
+                    row_details.borrow_mut().push((item.uri.clone(), details_label, warning_icon));
+                }
+                let row = ListBoxRow::builder().child(&row_box).build();
+                // Rows carry their item's id, which still finds it however the view has changed.
+                let drag_source = gtk::DragSource::new();
+                drag_source.set_actions(gdk::DragAction::MOVE);
+                let id = item.id;
+                drag_source.connect_prepare(move |_, _, _| Some(gdk::ContentProvider::for_value(&glib::value::ToValue::to_value(&id))));
+                row.add_controller(drag_source);
                 playlist_box.append(&row);
             }
-            if let Some(idx) = *current_sel {
+            highlight_on_air(&playlist_box, &row_ids.borrow(), streamer.lock().unwrap().get_currently_playing_id());
+            for idx in current_sel {
                 if let Some(row) = playlist_box.row_at_index(idx as i32) {
                     playlist_box.select_row(Some(&row));
                 }
@@ -196,45 +687,175 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
         }
     };
 
-    playlist_box.connect_row_selected({
+    playlist_box.connect_selected_rows_changed({
         let move_up = move_up_button.clone();
         let move_down = move_down_button.clone();
-        let selected_index = selected_index.clone();
-        move |box_, row| {
-            let mut idx_opt = selected_index.lock().unwrap();
-            if let Some(row) = row {
-                let idx = row.index() as u32;
-                *idx_opt = Some(idx);
-                move_up.set_sensitive(idx > 0);
-                move_down.set_sensitive(idx < (box_.observe_children().n_items() - 1));
-            } else {
-                *idx_opt = None;
-                move_up.set_sensitive(false);
-                move_down.set_sensitive(false);
+        let position_spin = position_spin.clone();
+        let move_to = move_to_button.clone();
+        let add_above = add_above_button.clone();
+        let remove = remove_button.clone();
+        let selected_indices = selected_indices.clone();
+        move |box_| {
+            let mut selected = selected_indices.lock().unwrap();
+            *selected = box_.selected_rows().iter().map(|row| row.index() as u32).collect();
+            let n_items = box_.observe_children().n_items();
+            // The selection moves as a block, starting at its first row.
+            match selected.first().copied() {
+                Some(first) => {
+                    let block = selected.len() as u32;
+                    move_up.set_sensitive(first > 0);
+                    move_down.set_sensitive(first + block < n_items);
+                    position_spin.set_range(1.0, (n_items - block + 1) as f64);
+                    position_spin.set_value((first + 1) as f64);
+                    position_spin.set_sensitive(n_items > block);
+                    move_to.set_sensitive(n_items > block);
+                    add_above.set_sensitive(true);
+                    remove.set_sensitive(true);
+                }
+                None => {
+                    move_up.set_sensitive(false);
+                    move_down.set_sensitive(false);
+                    position_spin.set_sensitive(false);
+                    move_to.set_sensitive(false);
+                    add_above.set_sensitive(false);
+                    remove.set_sensitive(false);
+                }
+            }
+        }
+    });
+
+    // Dropping a row on another moves its item to that position; below the last row, to the end.
+    // Files dropped from a file manager are added at the end.
+    let drop_target = gtk::DropTarget::new(glib::Type::INVALID, gdk::DragAction::MOVE | gdk::DragAction::COPY);
+    // Both preludes bring in a `StaticType`; these are gtk's types.
+    drop_target.set_types(&[<u64 as glib::StaticType>::static_type(), <gdk::FileList as glib::StaticType>::static_type()]);
+    drop_target.connect_drop({
+        let streamer = streamer.clone();
+        let window = window.clone();
+        let update_playlist_view = update_playlist_view.clone();
+        let selected_indices = selected_indices.clone();
+        let playlist_box = playlist_box.clone();
+        move |_, value, _, y| {
+            if let Ok(files) = value.get::<gdk::FileList>() {
+                let update_playlist_view = update_playlist_view.clone();
+                add_uris(&window, &streamer, dropped_uris(&files), move |added| {
+                    println!("Added {} dropped file(s).", added);
+                    update_playlist_view();
+                });
+                return true;
+            }
+            let id = match value.get::<u64>() {
+                Ok(id) => id,
+                Err(_) => return false,
+            };
+            // Dragging a selected row takes the whole selection along.
+            let (selected, len) = streamer.lock().unwrap().with_playlist(|playlist| {
+                (selected_ids(playlist, &selected_indices.lock().unwrap()), playlist.len())
+            });
+            let ids = if selected.contains(&id) { selected } else { vec![id] };
+            let last_start = len.saturating_sub(ids.len());
+            let new_idx = playlist_box.row_at_y(y as i32).map_or(last_start, |row| (row.index() as usize).min(last_start));
+            if let Err(e) = streamer.lock().unwrap().move_items(&ids, new_idx) {
+                eprintln!("Failed to move items: {}", e);
+                return false;
+            }
+            *selected_indices.lock().unwrap() = (new_idx..new_idx + ids.len()).map(|idx| idx as u32).collect();
+            update_playlist_view();
+            true
+        }
+    });
+    playlist_box.add_controller(drop_target);
+
+    // Double-clicking (or Enter on) a row cues that item live.
+    playlist_box.connect_row_activated({
+        let streamer = streamer.clone();
+        move |_, row| {
+            let streamer = streamer.lock().unwrap();
+            let item = streamer.with_playlist(|playlist| {
+                playlist.get(row.index() as usize).map(|item| (item.id, item.uri.clone()))
+            });
+            if let Some((id, uri)) = item {
+                if let Err(e) = streamer.play_item(id) {
+                    eprintln!("Failed to play {}: {}", uri, e);
+                }
             }
         }
     });
 
     let window_clone = window.clone();
-    add_button.connect_clicked({
+    // Lets the user pick a file and adds it at `insert_at`, or at the end.
+    let add_file = {
         let streamer = streamer.clone();
         let update_playlist_view = update_playlist_view.clone();
-        move |_| {
+        let ui_settings = ui_settings.clone();
+        let selected_indices = selected_indices.clone();
+        move |insert_at: Option<usize>| {
             let file_chooser = FileChooserDialog::new(
                 Some("Select a Video File"),
                 Some(&window_clone),
                 FileChooserAction::Open,
                 &[("Open", ResponseType::Accept), ("Cancel", ResponseType::Cancel)],
             );
+
+            let media_filter = FileFilter::new();
+            media_filter.set_name(Some("Media Files"));
+            media_filter.add_mime_type("video/*");
+            media_filter.add_mime_type("audio/*");
+            let all_filter = FileFilter::new();
+            all_filter.set_name(Some("All Files"));
+            all_filter.add_pattern("*");
+            file_chooser.add_filter(&media_filter);
+            file_chooser.add_filter(&all_filter);
+            file_chooser.set_filter(&media_filter);
+
+            let start_dir = ui_settings.lock().unwrap().chooser_directory();
+            if let Err(e) = file_chooser.set_current_folder(Some(&gio::File::for_path(&start_dir))) {
+                eprintln!("Failed to open file chooser in {}: {}", start_dir.display(), e);
+            }
+
             file_chooser.connect_response({
                 let streamer = streamer.clone();
                 let update_playlist_view = update_playlist_view.clone();
+                let ui_settings = ui_settings.clone();
+                let selected_indices = selected_indices.clone();
+                let window = window_clone.clone();
                 move |dialog, response| {
                     if response == ResponseType::Accept {
                         if let Some(file) = dialog.file() {
                             let uri = file.uri();
-                            streamer.lock().unwrap().add_item(uri.as_str());
-                            update_playlist_view();
+                            let duplicate = streamer.lock().unwrap().find_by_uri(uri.as_str()).is_some();
+                            confirm_duplicates(&window, duplicate as usize, 1, {
+                                let streamer = streamer.clone();
+                                let update_playlist_view = update_playlist_view.clone();
+                                let selected_indices = selected_indices.clone();
+                                move |add_again| {
+                                    if !add_again {
+                                        return;
+                                    }
+                                    match insert_at {
+                                        Some(index) => match streamer.lock().unwrap().insert_item(uri.as_str(), index) {
+                                            Ok(_) => {
+                                                // Keep the selection on the rows it was on.
+                                                let mut selected = selected_indices.lock().unwrap();
+                                                *selected = selected.iter().map(|&idx| if idx as usize >= index { idx + 1 } else { idx }).collect();
+                                            }
+                                            Err(e) => eprintln!("Failed to insert {}: {}", uri, e),
+                                        },
+                                        None => {
+                                            streamer.lock().unwrap().add_item(uri.as_str());
+                                        }
+                                    }
+                                    update_playlist_view();
+                                }
+                            });
+
+                            if let Some(dir) = file.path().and_then(|p| p.parent().map(|d| d.to_path_buf())) {
+                                let mut ui_settings = ui_settings.lock().unwrap();
+                                ui_settings.last_directory = Some(dir);
+                                if let Err(e) = ui_settings.save() {
+                                    eprintln!("Failed to save UI settings: {}", e);
+                                }
+                            }
                         }
                     }
                     dialog.close();
@@ -242,129 +863,747 @@ fn build_ui(app: &Application, streamer: Arc<Mutex<Streamer>>) {
             });
             file_chooser.show();
         }
+    };
+
+    // The playlist as it was last saved or opened.
+    let saved_playlist = Arc::new(Mutex::new(Vec::<(u64, bool)>::new()));
+    let has_unsaved_changes = {
+        let streamer = streamer.clone();
+        let saved_playlist = saved_playlist.clone();
+        move || {
+            streamer.lock().unwrap().with_playlist(|playlist| {
+                !playlist.is_empty() && playlist_fingerprint(playlist) != *saved_playlist.lock().unwrap()
+            })
+        }
+    };
+
+    new_playlist_button.connect_clicked({
+        let streamer = streamer.clone();
+        let update_playlist_view = update_playlist_view.clone();
+        let selected_indices = selected_indices.clone();
+        let has_unsaved_changes = has_unsaved_changes.clone();
+        let window = window.clone();
+        move |_| {
+            let streamer = streamer.clone();
+            let update_playlist_view = update_playlist_view.clone();
+            let selected_indices = selected_indices.clone();
+            confirm_discard(&window, has_unsaved_changes(), move || {
+                streamer.lock().unwrap().clear_playlist();
+                selected_indices.lock().unwrap().clear();
+                update_playlist_view();
+            });
+        }
     });
 
-    start_button.connect_clicked({
+    open_playlist_button.connect_clicked({
+        let streamer = streamer.clone();
+        let update_playlist_view = update_playlist_view.clone();
+        let selected_indices = selected_indices.clone();
+        let saved_playlist = saved_playlist.clone();
+        let ui_settings = ui_settings.clone();
+        let window = window.clone();
+        move |_| {
+            let streamer = streamer.clone();
+            let update_playlist_view = update_playlist_view.clone();
+            let selected_indices = selected_indices.clone();
+            let saved_playlist = saved_playlist.clone();
+            let ui_settings = ui_settings.clone();
+            let parent = window.clone();
+            confirm_discard(&window, has_unsaved_changes(), move || {
+                let chooser = FileChooserDialog::new(
+                    Some("Open Playlist"),
+                    Some(&parent),
+                    FileChooserAction::Open,
+                    &[("Open", ResponseType::Accept), ("Cancel", ResponseType::Cancel)],
+                );
+                add_playlist_filters(&chooser);
+                let start_dir = ui_settings.lock().unwrap().chooser_directory();
+                if let Err(e) = chooser.set_current_folder(Some(&gio::File::for_path(&start_dir))) {
+                    eprintln!("Failed to open file chooser in {}: {}", start_dir.display(), e);
+                }
+                chooser.connect_response({
+                    let streamer = streamer.clone();
+                    let update_playlist_view = update_playlist_view.clone();
+                    let selected_indices = selected_indices.clone();
+                    let saved_playlist = saved_playlist.clone();
+                    let parent = parent.clone();
+                    move |dialog, response| {
+                        let path = dialog.file().and_then(|file| file.path()).filter(|_| response == ResponseType::Accept);
+                        dialog.close();
+                        if let Some(path) = path {
+                            let streamer = streamer.lock().unwrap();
+                            match streamer.load_playlist(&path) {
+                                Ok(count) => {
+                                    println!("Opened {} item(s) from {}.", count, path.display());
+                                    *saved_playlist.lock().unwrap() = streamer.with_playlist(playlist_fingerprint);
+                                    drop(streamer);
+                                    selected_indices.lock().unwrap().clear();
+                                    update_playlist_view();
+                                }
+                                Err(e) => show_error_dialog(&parent, "Failed to Open Playlist", &format!("{:#}", e)),
+                            }
+                        }
+                    }
+                });
+                chooser.show();
+            });
+        }
+    });
+
+    save_playlist_button.connect_clicked({
+        let streamer = streamer.clone();
+        let ui_settings = ui_settings.clone();
+        let window = window.clone();
+        move |_| {
+            let chooser = FileChooserDialog::new(
+                Some("Save Playlist"),
+                Some(&window),
+                FileChooserAction::Save,
+                &[("Save", ResponseType::Accept), ("Cancel", ResponseType::Cancel)],
+            );
+            add_playlist_filters(&chooser);
+            let start_dir = ui_settings.lock().unwrap().chooser_directory();
+            if let Err(e) = chooser.set_current_folder(Some(&gio::File::for_path(&start_dir))) {
+                eprintln!("Failed to open file chooser in {}: {}", start_dir.display(), e);
+            }
+            chooser.set_current_name("playlist.json");
+            chooser.connect_response({
+                let streamer = streamer.clone();
+                let saved_playlist = saved_playlist.clone();
+                let window = window.clone();
+                move |dialog, response| {
+                    let path = dialog.file().and_then(|file| file.path()).filter(|_| response == ResponseType::Accept);
+                    dialog.close();
+                    if let Some(path) = path {
+                        let streamer = streamer.lock().unwrap();
+                        match streamer.save_playlist(&path) {
+                            Ok(()) => {
+                                println!("Saved the playlist to {}.", path.display());
+                                *saved_playlist.lock().unwrap() = streamer.with_playlist(playlist_fingerprint);
+                            }
+                            Err(e) => show_error_dialog(&window, "Failed to Save Playlist", &format!("{:#}", e)),
+                        }
+                    }
+                }
+            });
+            chooser.show();
+        }
+    });
+
+    add_button.connect_clicked({
+        let add_file = add_file.clone();
+        move |_| add_file(None)
+    });
+
+    add_folder_button.connect_clicked({
         let streamer = streamer.clone();
+        let update_playlist_view = update_playlist_view.clone();
+        let ui_settings = ui_settings.clone();
         let window = window.clone();
+        move |_| {
+            let folder_chooser = FileChooserDialog::new(
+                Some("Select a Folder"),
+                Some(&window),
+                FileChooserAction::SelectFolder,
+                &[("Add", ResponseType::Accept), ("Cancel", ResponseType::Cancel)],
+            );
+            let start_dir = ui_settings.lock().unwrap().chooser_directory();
+            if let Err(e) = folder_chooser.set_current_folder(Some(&gio::File::for_path(&start_dir))) {
+                eprintln!("Failed to open folder chooser in {}: {}", start_dir.display(), e);
+            }
+            folder_chooser.connect_response({
+                let streamer = streamer.clone();
+                let window = window.clone();
+                let update_playlist_view = update_playlist_view.clone();
+                let ui_settings = ui_settings.clone();
+                move |dialog, response| {
+                    if response == ResponseType::Accept {
+                        if let Some(dir) = dialog.file().and_then(|file| file.path()) {
+                            // Subfolders too: a season or show is often split into folders.
+                            match Streamer::directory_uris(&dir, MEDIA_EXTENSIONS, true) {
+                                Ok(uris) => {
+                                    let update_playlist_view = update_playlist_view.clone();
+                                    let shown = dir.display().to_string();
+                                    add_uris(&window, &streamer, uris, move |added| {
+                                        println!("Added {} file(s) from {}.", added, shown);
+                                        update_playlist_view();
+                                    });
+                                }
+                                Err(e) => eprintln!("Failed to add folder {}: {:#}", dir.display(), e),
+                            }
+
+                            let mut ui_settings = ui_settings.lock().unwrap();
+                            ui_settings.last_directory = Some(dir);
+                            if let Err(e) = ui_settings.save() {
+                                eprintln!("Failed to save UI settings: {}", e);
+                            }
+                        }
+                    }
+                    dialog.close();
+                }
+            });
+            folder_chooser.show();
+        }
+    });
+
+    add_url_button.connect_clicked({
+        let streamer = streamer.clone();
+        let update_playlist_view = update_playlist_view.clone();
+        let window = window.clone();
+        move |_| {
+            let dialog = Dialog::with_buttons(
+                Some("Add URL"),
+                Some(&window),
+                gtk::DialogFlags::MODAL,
+                &[("Add", ResponseType::Accept), ("Cancel", ResponseType::Cancel)],
+            );
+            let url_entry = Entry::builder()
+                .placeholder_text("https://... or rtsp://...")
+                .activates_default(true)
+                .width_chars(50)
+                .margin_top(10).margin_bottom(10).margin_start(10).margin_end(10)
+                .build();
+            dialog.content_area().append(&url_entry);
+            dialog.set_default_response(ResponseType::Accept);
+            dialog.connect_response({
+                let streamer = streamer.clone();
+                let update_playlist_view = update_playlist_view.clone();
+                let window = window.clone();
+                move |dialog, response| {
+                    if response == ResponseType::Accept {
+                        let url = url_entry.text();
+                        match streamer.lock().unwrap().add_uri(url.trim()) {
+                            Ok(_) => update_playlist_view(),
+                            Err(e) => show_error_dialog(&window, "Cannot Add URL", &user_message(&e)),
+                        }
+                    }
+                    dialog.close();
+                }
+            });
+            dialog.show();
+        }
+    });
+
+    add_above_button.connect_clicked({
+        let selected_indices = selected_indices.clone();
+        move |_| {
+            let first = selected_indices.lock().unwrap().first().copied();
+            if let Some(idx) = first {
+                add_file(Some(idx as usize));
+            }
+        }
+    });
+
+    // Everything that depends on whether a stream is live, driven by the streamer rather than
+    // by which button was last clicked, so it also recovers when the pipeline stops by itself.
+    let update_controls = {
+        let start_button = start_button.clone();
+        let dry_run_button = dry_run_button.clone();
+        let stop_button = stop_button.clone();
+        let stop_after_button = stop_after_button.clone();
+        let pause_button = pause_button.clone();
+        let previous_button = previous_button.clone();
+        let next_button = next_button.clone();
         let video_encoder_combo = video_encoder_combo.clone();
         let audio_encoder_combo = audio_encoder_combo.clone();
         let bitrate_spin = bitrate_spin.clone();
         let preset_combo = preset_combo.clone();
+        let latency_combo = latency_combo.clone();
         let scale_check = scale_check.clone();
         let width_spin = width_spin.clone();
         let height_spin = height_spin.clone();
         let rtmp_entry = rtmp_entry.clone();
-        let stop_button = stop_button.clone();
+        let stream_key_entry = stream_key_entry.clone();
+        let streamer = streamer.clone();
+        // Ticks only while a stream runs.
+        let progress_timer = Rc::new(RefCell::new(None::<glib::SourceId>));
+        move |running: bool| {
+            start_button.set_sensitive(!running);
+            dry_run_button.set_sensitive(!running);
+            stop_button.set_sensitive(running);
+            if !running {
+                stop_after_button.set_active(false);
+                pause_button.set_active(false);
+            }
+            stop_after_button.set_sensitive(running);
+            pause_button.set_sensitive(running);
+            previous_button.set_sensitive(running);
+            next_button.set_sensitive(running);
+            video_encoder_combo.set_sensitive(!running);
+            audio_encoder_combo.set_sensitive(!running);
+            bitrate_spin.set_sensitive(!running);
+            preset_combo.set_sensitive(!running);
+            latency_combo.set_sensitive(!running);
+            scale_check.set_sensitive(!running);
+            let scale_editable = !running && scale_check.is_active();
+            width_spin.set_sensitive(scale_editable);
+            height_spin.set_sensitive(scale_editable);
+            rtmp_entry.set_sensitive(!running);
+            stream_key_entry.set_sensitive(!running);
+
+            let mut timer = progress_timer.borrow_mut();
+            if running && timer.is_none() {
+                *timer = Some(glib::timeout_add_local(std::time::Duration::from_millis(250), {
+                    let streamer = streamer.clone();
+                    let progress_bar = progress_bar.clone();
+                    let progress_label = progress_label.clone();
+                    move || {
+                        let position = streamer.lock().unwrap().get_position();
+                        match position {
+                            Some((position, Some(duration))) if duration > gst::ClockTime::ZERO => {
+                                progress_bar.set_fraction((position.nseconds() as f64 / duration.nseconds() as f64).min(1.0));
+                                progress_label.set_text(&format!(
+                                    "{} / -{}",
+                                    format_mm_ss(position),
+                                    format_mm_ss(duration.saturating_sub(position)),
+                                ));
+                            }
+                            // Live feeds have no end to measure against.
+                            Some((position, _)) => {
+                                progress_bar.set_fraction(0.0);
+                                progress_label.set_text(&format_mm_ss(position));
+                            }
+                            None => {
+                                progress_bar.set_fraction(0.0);
+                                progress_label.set_text("");
+                            }
+                        }
+                        glib::ControlFlow::Continue
+                    }
+                }));
+            } else if !running {
+                if let Some(timer) = timer.take() {
+                    timer.remove();
+                }
+                progress_bar.set_fraction(0.0);
+                progress_label.set_text("");
+            }
+        }
+    };
+
+    // The encoding settings as the controls have them.
+    let encoding_settings = {
+        let video_encoder_combo = video_encoder_combo.clone();
+        let audio_encoder_combo = audio_encoder_combo.clone();
+        let bitrate_spin = bitrate_spin.clone();
+        let preset_combo = preset_combo.clone();
+        let latency_combo = latency_combo.clone();
+        let scale_check = scale_check.clone();
+        let width_spin = width_spin.clone();
+        let height_spin = height_spin.clone();
+        move || EncodingSettings {
+            video_encoder: video_encoder_combo.active_text().unwrap_or_default().to_string(),
+            audio_encoder: audio_encoder_combo.active_text().unwrap_or_default().to_string(),
+            bitrate_kbps: bitrate_spin.value() as u32,
+            speed_preset: preset_combo.active_text().unwrap_or_default().to_string(),
+            scale_enabled: scale_check.is_active(),
+            scale_width: width_spin.value() as u32,
+            scale_height: height_spin.value() as u32,
+            latency_profile: latency_combo.active()
+                .and_then(|idx| latency_profiles.get(idx as usize))
+                .map_or_else(LatencyProfile::default, |(_, p)| *p),
+            ..EncodingSettings::default()
+        }
+    };
+
+    // Remembers the encoding settings and URL for next time; the stream key is left out.
+    let save_session = {
+        let ui_settings = ui_settings.clone();
+        let rtmp_entry = rtmp_entry.clone();
+        let encoding_settings = encoding_settings.clone();
+        move || {
+            let mut ui_settings = ui_settings.lock().unwrap();
+            ui_settings.encoding = Some(encoding_settings());
+            ui_settings.rtmp_url = Some(rtmp_entry.text().to_string()).filter(|url| !url.is_empty());
+            if let Err(e) = ui_settings.save() {
+                eprintln!("Failed to save settings: {}", e);
+            }
+        }
+    };
+
+    window.connect_close_request({
+        let save_session = save_session.clone();
+        let streamer = streamer.clone();
+        move |window| {
+            if !streamer.lock().unwrap().is_running() {
+                save_session();
+                return glib::Propagation::Proceed;
+            }
+            // Stopping properly ends the stream and finishes any recording; asked first, as
+            // closing by mistake would take the show off air.
+            let dialog = MessageDialog::new(
+                Some(window),
+                gtk::DialogFlags::MODAL,
+                MessageType::Warning,
+                gtk::ButtonsType::OkCancel,
+                "Stream is live — stop and quit?",
+            );
+            dialog.connect_response({
+                let streamer = streamer.clone();
+                let window = window.clone();
+                move |d, response| {
+                    d.close();
+                    if response != ResponseType::Ok {
+                        return;
+                    }
+                    if let Err(e) = streamer.lock().unwrap().stop() {
+                        eprintln!("Failed to stop stream: {}", e);
+                    }
+                    // Not live any more, so this goes through.
+                    window.close();
+                }
+            });
+            dialog.show();
+            glib::Propagation::Stop
+        }
+    });
+
+    preview_button.connect_toggled({
+        let streamer = streamer.clone();
+        let preview_picture = preview_picture.clone();
+        let window = window.clone();
+        move |button| {
+            if !button.is_active() {
+                if let Err(e) = streamer.lock().unwrap().set_preview_sink(None) {
+                    eprintln!("Failed to remove the preview: {}", e);
+                }
+                preview_picture.set_paintable(None::<&gdk::Paintable>);
+                preview_picture.set_visible(false);
+                return;
+            }
+            let sink = match gst::ElementFactory::make("gtk4paintablesink").build() {
+                Ok(sink) => sink,
+                Err(_) => {
+                    show_error_dialog(&window, "Cannot Show Preview", "The gtk4paintablesink element is not available.");
+                    button.set_active(false);
+                    return;
+                }
+            };
+            let paintable = sink.property::<gdk::Paintable>("paintable");
+            let result = streamer.lock().unwrap().set_preview_sink(Some(sink));
+            match result {
+                Ok(()) => {
+                    preview_picture.set_paintable(Some(&paintable));
+                    preview_picture.set_visible(true);
+                }
+                Err(e) => {
+                    show_error_dialog(&window, "Cannot Show Preview", &format!("{:#}", e));
+                    button.set_active(false);
+                }
+            }
+        }
+    });
+
+    dry_run_button.connect_clicked({
+        let streamer = streamer.clone();
+        let window = window.clone();
+        let encoding_settings = encoding_settings.clone();
+
+        move |_| {
+            let result = streamer.lock().unwrap().dry_run(&encoding_settings());
+            match result {
+                Ok(()) => {
+                    let dialog = MessageDialog::new(
+                        Some(&window),
+                        gtk::DialogFlags::MODAL,
+                        MessageType::Info,
+                        gtk::ButtonsType::Ok,
+                        "Dry Run Passed",
+                    );
+                    dialog.set_secondary_text(Some("The first item plays through the encoders with these settings."));
+                    dialog.connect_response(|d, _| d.close());
+                    dialog.show();
+                }
+                Err(e) => show_error_dialog(&window, "Dry Run Failed", &user_message(&e)),
+            }
+        }
+    });
 
-        move |start_button| {
+    start_button.connect_clicked({
+        let streamer = streamer.clone();
+        let window = window.clone();
+        let rtmp_entry = rtmp_entry.clone();
+        let stream_key_entry = stream_key_entry.clone();
+        let update_controls = update_controls.clone();
+
+        move |_| {
             let rtmp_url = rtmp_entry.text();
             if rtmp_url.is_empty() { 
-                show_error_dialog(&window, "RTMP URL cannot be empty.");
+                show_error_dialog(&window, "Failed to Start Stream", "RTMP URL cannot be empty.");
                 return; 
             }
 
-            let settings = EncodingSettings {
-                video_encoder: video_encoder_combo.active_text().unwrap_or_default().to_string(),
-                audio_encoder: audio_encoder_combo.active_text().unwrap_or_default().to_string(),
-                bitrate_kbps: bitrate_spin.value() as u32,
-                speed_preset: preset_combo.active_text().unwrap_or_default().to_string(),
-                scale_enabled: scale_check.is_active(),
-                scale_width: width_spin.value() as u32,
-                scale_height: height_spin.value() as u32,
-            };
+            let settings = encoding_settings();
+            save_session();
             
-            match streamer.lock().unwrap().start(&rtmp_url, &settings) {
-                Ok(_) => {
+            let (result, running) = {
+                let mut streamer = streamer.lock().unwrap();
+                (streamer.start_with_key(&rtmp_url, &stream_key_entry.text(), &settings), streamer.is_running())
+            };
+            match result {
+                Ok(report) => {
                     println!("Stream started successfully!");
-                    start_button.set_sensitive(false);
-                    stop_button.set_sensitive(true);
-                    video_encoder_combo.set_sensitive(false);
-                    audio_encoder_combo.set_sensitive(false);
-                    bitrate_spin.set_sensitive(false);
-                    preset_combo.set_sensitive(false);
-                    scale_check.set_sensitive(false);
-                    width_spin.set_sensitive(false);
-                    height_spin.set_sensitive(false);
-                    rtmp_entry.set_sensitive(false);
+                    if report.fallback_applied {
+                        println!("Encoder {} is unavailable, using {} instead.", settings.video_encoder, report.encoder_used);
+                    }
                 },
-                Err(e) => show_error_dialog(&window, &e.to_string()),
+                Err(e) => show_error_dialog(&window, "Failed to Start Stream", &user_message(&e)),
             }
+            update_controls(running);
         }
     });
 
     stop_button.connect_clicked({
         let streamer = streamer.clone();
-        let start_button = start_button.clone();
-        let video_encoder_combo = video_encoder_combo.clone();
-        let audio_encoder_combo = audio_encoder_combo.clone();
-        let bitrate_spin = bitrate_spin.clone();
-        let preset_combo = preset_combo.clone();
-        let scale_check = scale_check.clone();
-        let width_spin = width_spin.clone();
-        let height_spin = height_spin.clone();
-        let rtmp_entry = rtmp_entry.clone();
+        let update_controls = update_controls.clone();
 
-        move |stop_button| {
-             match streamer.lock().unwrap().stop() {
-                Ok(_) => {
-                    println!("Stream stopped.");
-                    stop_button.set_sensitive(false);
-                    start_button.set_sensitive(true);
-                    video_encoder_combo.set_sensitive(true);
-                    audio_encoder_combo.set_sensitive(true);
-                    bitrate_spin.set_sensitive(true);
-                    preset_combo.set_sensitive(true);
-                    scale_check.set_sensitive(true);
-                    let is_scale_active = scale_check.is_active();
-                    width_spin.set_sensitive(is_scale_active);
-                    height_spin.set_sensitive(is_scale_active);
-                    rtmp_entry.set_sensitive(true);
-                },
+        move |_| {
+            // Released before updating the controls: resetting Stop After Current locks it again.
+            let (result, running) = {
+                let mut streamer = streamer.lock().unwrap();
+                (streamer.stop(), streamer.is_running())
+            };
+            match result {
+                Ok(_) => println!("Stream stopped."),
                 Err(e) => eprintln!("Failed to stop stream: {}", e),
             }
+            update_controls(running);
         }
     });
 
-    move_up_button.connect_clicked({
+    stop_after_button.connect_toggled({
+        let streamer = streamer.clone();
+        move |button| {
+            let streamer = streamer.lock().unwrap();
+            // Also fires when the UI resets the button after a stop; only forward real changes.
+            if button.is_active() == streamer.is_stop_after_current_pending() {
+                return;
+            }
+            if let Err(e) = streamer.stop_after_current() {
+                eprintln!("Failed to arm stop after current: {}", e);
+            }
+        }
+    });
+
+    pause_button.connect_toggled({
+        let streamer = streamer.clone();
+        move |button| {
+            let mut streamer = streamer.lock().unwrap();
+            // Also fires when the UI resets the button after a stop; only forward real changes.
+            let paused = streamer.pipeline_state() == Some(gst::State::Paused);
+            if button.is_active() == paused || !streamer.is_running() {
+                return;
+            }
+            let result = if button.is_active() { streamer.pause() } else { streamer.resume() };
+            if let Err(e) = result {
+                eprintln!("Failed to {} stream: {}", if button.is_active() { "pause" } else { "resume" }, e);
+            }
+        }
+    });
+
+    previous_button.connect_clicked({
+        let streamer = streamer.clone();
+        move |_| {
+            if let Err(e) = streamer.lock().unwrap().skip_previous() {
+                eprintln!("Failed to skip back: {}", e);
+            }
+        }
+    });
+
+    next_button.connect_clicked({
+        let streamer = streamer.clone();
+        move |_| {
+            if let Err(e) = streamer.lock().unwrap().skip_next() {
+                eprintln!("Failed to skip: {}", e);
+            }
+        }
+    });
+
+    let events = streamer.lock().unwrap().subscribe();
+    glib::timeout_add_local(std::time::Duration::from_millis(250), {
+        let streamer = streamer.clone();
+        let stop_button = stop_button.clone();
+        let update_controls = update_controls.clone();
+        let status_label = status_label.clone();
+        let playlist_box = playlist_box.clone();
+        let mut levels_at: Option<std::time::Instant> = None;
+        move || {
+            let mut levels = None;
+            while let Ok(event) = events.try_recv() {
+                match event {
+                    PlayoutEvent::AudioLevels { peak_db, rms_db } => levels = Some((peak_db, rms_db)),
+                    PlayoutEvent::StoppedAfterCurrent | PlayoutEvent::PlaylistEnded => stop_button.emit_clicked(),
+                    _ => (),
+                }
+            }
+            match levels {
+                Some((peak_db, rms_db)) => {
+                    show_levels(&meters, &peak_db, &rms_db);
+                    levels_at = Some(std::time::Instant::now());
+                }
+                // Nothing coming through, as when stopped: the meters drop rather than freeze.
+                None if levels_at.map_or(false, |at| at.elapsed() > METER_HOLD) => {
+                    show_levels(&meters, &[], &[]);
+                    levels_at = None;
+                }
+                None => (),
+            }
+            // Catches a stream that ended on its own, e.g. after reconnecting gave up.
+            let (running, stats, on_air) = {
+                let streamer = streamer.lock().unwrap();
+                (streamer.is_running(), streamer.get_stats(), streamer.get_currently_playing_id())
+            };
+            highlight_on_air(&playlist_box, &row_ids.borrow(), on_air);
+            if running != stop_button.is_sensitive() {
+                update_controls(running);
+            }
+            if running {
+                let uptime = stats.uptime.as_secs();
+                status_label.set_text(&format!(
+                    "{:.0} kbps · {:.1} MB sent · {} dropped · {:02}:{:02}:{:02}",
+                    stats.bitrate_kbps,
+                    stats.bytes_sent as f64 / 1_000_000.0,
+                    stats.dropped_frames,
+                    uptime / 3600,
+                    uptime / 60 % 60,
+                    uptime % 60,
+                ));
+            } else {
+                status_label.set_text("");
+            }
+            glib::ControlFlow::Continue
+        }
+    });
+
+    remove_button.connect_clicked({
         let streamer = streamer.clone();
         let update_playlist_view = update_playlist_view.clone();
-        let selected_index = selected_index.clone();
+        let selected_indices = selected_indices.clone();
         move |_| {
-            let mut idx_opt = selected_index.lock().unwrap();
-            if let Some(idx) = *idx_opt {
-                if idx > 0 {
-                    let playlist = streamer.lock().unwrap().get_playlist_clone();
-                    let item_id = playlist[idx as usize].id;
-                    let new_idx = idx - 1;
-                    if streamer.lock().unwrap().move_item(item_id, new_idx as usize).is_ok() {
-                        *idx_opt = Some(new_idx);
-                        update_playlist_view();
-                    }
+            let current_sel = selected_indices.lock().unwrap().clone();
+            if let Some(&first) = current_sel.first() {
+                let streamer = streamer.lock().unwrap();
+                let ids = streamer.with_playlist(|playlist| selected_ids(playlist, &current_sel));
+                for &id in &ids {
+                    streamer.remove_item(id);
+                }
+                // The row below the first removed one takes the selection, or the last row.
+                let remaining = streamer.playlist_len() as u32;
+                drop(streamer);
+                let mut selected = selected_indices.lock().unwrap();
+                selected.clear();
+                if remaining > 0 {
+                    selected.insert(first.min(remaining - 1));
                 }
+                drop(selected);
+                update_playlist_view();
             }
         }
     });
 
-    move_down_button.connect_clicked({
+    // Delete removes the selected rows, like the Remove button.
+    let key_controller = gtk::EventControllerKey::new();
+    key_controller.connect_key_pressed({
+        let remove_button = remove_button.clone();
+        move |_, key, _, _| {
+            if key == gdk::Key::Delete && remove_button.is_sensitive() {
+                remove_button.emit_clicked();
+                glib::Propagation::Stop
+            } else {
+                glib::Propagation::Proceed
+            }
+        }
+    });
+    playlist_box.add_controller(key_controller);
+
+    // Moves the selected rows, as a block in their playlist order, to start at `new_index`,
+    // which is clamped so the block still fits.
+    let move_selection = {
         let streamer = streamer.clone();
         let update_playlist_view = update_playlist_view.clone();
-        let selected_index = selected_index.clone();
+        let selected_indices = selected_indices.clone();
+        move |new_index: usize| {
+            let (ids, len) = streamer.lock().unwrap().with_playlist(|playlist| {
+                (selected_ids(playlist, &selected_indices.lock().unwrap()), playlist.len())
+            });
+            if ids.is_empty() {
+                return;
+            }
+            let new_index = new_index.min(len - ids.len());
+            if let Err(e) = streamer.lock().unwrap().move_items(&ids, new_index) {
+                eprintln!("Failed to move items: {}", e);
+                return;
+            }
+            *selected_indices.lock().unwrap() = (new_index..new_index + ids.len()).map(|idx| idx as u32).collect();
+            update_playlist_view();
+        }
+    };
+
+    move_up_button.connect_clicked({
+        let selected_indices = selected_indices.clone();
+        let move_selection = move_selection.clone();
         move |_| {
-            let mut idx_opt = selected_index.lock().unwrap();
-            if let Some(idx) = *idx_opt {
-                let playlist = streamer.lock().unwrap().get_playlist_clone();
-                if idx < (playlist.len() - 1) as u32 {
-                    let item_id = playlist[idx as usize].id;
-                    let new_idx = idx + 1;
-                    if streamer.lock().unwrap().move_item(item_id, new_idx as usize).is_ok() {
-                        *idx_opt = Some(new_idx);
-                        update_playlist_view();
-                    }
+            let first = selected_indices.lock().unwrap().first().copied();
+            if let Some(first) = first {
+                if first > 0 {
+                    move_selection(first as usize - 1);
                 }
             }
         }
     });
 
+    move_down_button.connect_clicked({
+        let selected_indices = selected_indices.clone();
+        let move_selection = move_selection.clone();
+        move |_| {
+            let first = selected_indices.lock().unwrap().first().copied();
+            if let Some(first) = first {
+                move_selection(first as usize + 1);
+            }
+        }
+    });
+
+    move_to_button.connect_clicked({
+        let position_spin = position_spin.clone();
+        move |_| {
+            // Positions are 1-based in the UI.
+            let target = position_spin.value_as_int().max(1) as usize - 1;
+            move_selection(target);
+        }
+    });
+
+    // Hotkeys, listed in the buttons' tooltips. They are caught before the focused widget
+    // sees them, so Space doesn't just press whichever button has focus, but never while
+    // typing in a text field.
+    start_button.set_tooltip_text(Some("Start (Space or F5)"));
+    stop_button.set_tooltip_text(Some("Stop (Space or F5)"));
+    next_button.set_tooltip_text(Some("Skip to the next item (N)"));
+    previous_button.set_tooltip_text(Some("Back to the previous item (P)"));
+    let shortcuts = gtk::ShortcutController::new();
+    shortcuts.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let bindings = [
+        ("space|F5", vec![start_button.clone(), stop_button.clone()]),
+        ("n", vec![next_button.clone()]),
+        ("p", vec![previous_button.clone()]),
+    ];
+    for (keys, buttons) in bindings {
+        let trigger = gtk::ShortcutTrigger::parse_string(keys);
+        let action = gtk::CallbackAction::new(move |widget, _| {
+            let typing = widget.downcast_ref::<gtk::Window>()
+                .and_then(|window| GtkWindowExt::focus(window))
+                .map_or(false, |focus| focus.is::<gtk::Editable>());
+            // The first of the buttons that can be pressed right now.
+            match buttons.iter().find(|button| button.is_sensitive()) {
+                Some(button) if !typing => {
+                    button.emit_clicked();
+                    glib::Propagation::Stop
+                }
+                _ => glib::Propagation::Proceed,
+            }
+        });
+        shortcuts.add_shortcut(gtk::Shortcut::new(trigger, Some(action)));
+    }
+    window.add_controller(shortcuts);
+
     window.present();
 }
\ No newline at end of file
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use gtk4 as gtk;
+use hayai_playout_core::EncodingSettings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Frontend state remembered between sessions.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UiSettings {
+    #[serde(default)]
+    pub last_directory: Option<PathBuf>,
+    /// What the last stream was started with, to fill the controls in with.
+    #[serde(default)]
+    pub encoding: Option<EncodingSettings>,
+    #[serde(default)]
+    pub rtmp_url: Option<String>,
+    #[serde(default)]
+    pub dark_theme: bool,
+    /// Text size of the playlist rows in points; `None` for the theme's.
+    #[serde(default)]
+    pub playlist_font_pt: Option<u32>,
+}
+
+impl UiSettings {
+    fn path() -> PathBuf {
+        gtk::glib::user_config_dir().join("hayai-playout").join("ui.json")
+    }
+
+    /// Loads the saved settings, falling back to defaults if the file is missing or unreadable.
+    pub fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Where the file chooser should open: the last used directory if it still exists,
+    /// otherwise the home directory.
+    pub fn chooser_directory(&self) -> PathBuf {
+        self.last_directory.clone()
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(gtk::glib::home_dir)
+    }
+}